@@ -11,6 +11,15 @@ export type ValuePath = {
     "path": (ValuePathComponent)[];
 };
 export type F64 = number;
+
+/**
+ * A date, date-time or time value.
+ * 
+ * Values are stored in their RFC 3339 form (as TOML writes them), so local
+ * values and offsets survive a round trip unchanged. Values without an
+ * offset are interpreted in the site timezone (see `FieldConfig`), and
+ * time-only values are placed on 1970-01-01.
+ */
 export type DateTime = {
     "raw": string;
 };
@@ -35,8 +44,25 @@ export type MetaValue = ({
     "Array": MetaValue[];
 } | {
     "Map": Record<string, MetaValue>;
-});
+} | 
+/**
+ * An unset value. Since TOML has no null, these are omitted when
+ * written, and read back as a missing key.
+ */
+"Null");
 export type Meta = Record<string, MetaValue>;
+
+/**
+ * A point on the earth, with an optional address and place name.
+ * 
+ * Locations are sorted by latitude and then longitude.
+ */
+export type Location = {
+    "lat": F64;
+    "lng": F64;
+    "address": (string | null);
+    "name": (string | null);
+};
 export type FieldValue = ({
     "String": string;
 } | {
@@ -53,6 +79,10 @@ export type FieldValue = ({
     "File": File;
 } | {
     "Meta": Meta;
+} | {
+    "Location": Location;
+} | {
+    "List": FieldValue[];
 });
 export type AddObjectValue = {
     "path": ValuePath;
@@ -73,12 +103,23 @@ export type DeleteObjectEvent = {
     "filename": string;
     "source": (string | null);
 };
+export type RestoreObjectEvent = {
+    "object": string;
+    "filename": string;
+    "source": (string | null);
+};
 export type EditFieldEvent = {
     "object": string;
     "filename": string;
     "path": ValuePath;
     "field": string;
     "value": (FieldValue | null);
+
+    /**
+     * When set to a locale other than the site's default, edits the
+     * translated value for that locale.
+     */
+    "locale"?: (string | null);
     "source": (string | null);
 };
 export type EditOrderEvent = {
@@ -91,6 +132,22 @@ export type ChildEvent = {
     "object": string;
     "filename": string;
     "path": ValuePath;
+
+    /**
+     * The type of block to add, when adding to a blocks field.
+     */
+    "block"?: (string | null);
+    "source": (string | null);
+};
+export type MoveChildEvent = {
+    "object": string;
+    "filename": string;
+
+    /**
+     * The path of the child to move, ending in its current index.
+     */
+    "path": ValuePath;
+    "to": Usize;
     "source": (string | null);
 };
 export type ArchivalEvent = ({
@@ -99,6 +156,8 @@ export type ArchivalEvent = ({
     "AddRootObject": AddRootObjectEvent;
 } | {
     "DeleteObject": DeleteObjectEvent;
+} | {
+    "RestoreObject": RestoreObjectEvent;
 } | {
     "EditField": EditFieldEvent;
 } | {
@@ -107,4 +166,6 @@ export type ArchivalEvent = ({
     "AddChild": ChildEvent;
 } | {
     "RemoveChild": ChildEvent;
+} | {
+    "MoveChild": MoveChildEvent;
 });
@@ -0,0 +1,79 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+/// The content-hashed names of static files, keyed by their paths in the
+/// static dir. Shared with the `asset_url` filter, and written to the build
+/// dir as `asset-manifest.json`.
+#[derive(Debug, Clone, Default)]
+pub struct AssetManifest {
+    assets: Arc<RwLock<BTreeMap<PathBuf, PathBuf>>>,
+}
+
+impl AssetManifest {
+    /// The fingerprinted name of a file with the given hash, e.g.
+    /// `style/theme.1a2b3c4d5e6f7a8b.css`.
+    pub fn fingerprinted_path(file: &Path, hash: u64) -> PathBuf {
+        let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+        file.with_file_name(match file.extension() {
+            Some(ext) => format!("{}.{:016x}.{}", stem, hash, ext.to_string_lossy()),
+            None => format!("{}.{:016x}", stem, hash),
+        })
+    }
+
+    pub fn get(&self, file: &Path) -> Option<PathBuf> {
+        self.assets.read().unwrap().get(file).cloned()
+    }
+
+    /// Records the fingerprinted path of a file, returning the previous one.
+    pub fn insert(&self, file: PathBuf, fingerprinted: PathBuf) -> Option<PathBuf> {
+        self.assets.write().unwrap().insert(file, fingerprinted)
+    }
+
+    /// Removes the assets of files that are no longer in the static dir,
+    /// returning their fingerprinted paths.
+    pub fn retain(&self, files: &HashSet<PathBuf>) -> Vec<PathBuf> {
+        let mut assets = self.assets.write().unwrap();
+        let mut removed = vec![];
+        assets.retain(|file, fingerprinted| {
+            let keep = files.contains(file);
+            if !keep {
+                removed.push(fingerprinted.clone());
+            }
+            keep
+        });
+        removed
+    }
+
+    /// The fingerprinted path of an asset, keeping a leading slash, or the
+    /// path itself when it isn't a fingerprinted asset.
+    pub fn asset_url(&self, path: &str) -> String {
+        let file = path.trim_start_matches('/');
+        match self.get(Path::new(file)) {
+            Some(fingerprinted) => format!(
+                "{}{}",
+                &path[..path.len() - file.len()],
+                fingerprinted.to_string_lossy().replace('\\', "/")
+            ),
+            None => path.to_string(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let assets = self.assets.read().unwrap();
+        serde_json::to_string_pretty(
+            &assets
+                .iter()
+                .map(|(k, v)| {
+                    (
+                        k.to_string_lossy().replace('\\', "/"),
+                        v.to_string_lossy().replace('\\', "/"),
+                    )
+                })
+                .collect::<BTreeMap<String, String>>(),
+        )
+        .unwrap()
+    }
+}
@@ -22,6 +22,10 @@ impl BinaryCommand for Command {
         let _ = fs.remove_dir_all(&site.manifest.build_dir);
         site.sync_static_files(&mut fs)?;
         site.build(&mut fs)?;
+        let minify_stats = site.take_minify_stats();
+        if minify_stats.files > 0 {
+            println!("{}", minify_stats);
+        }
         Ok(ExitStatus::Ok)
     }
 }
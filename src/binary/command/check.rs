@@ -0,0 +1,36 @@
+use super::BinaryCommand;
+use crate::{binary::ExitStatus, file_system_stdlib, site::Site, FileSystemAPI};
+use clap::ArgMatches;
+use std::path::Path;
+
+pub struct Command {}
+impl BinaryCommand for Command {
+    fn name(&self) -> &str {
+        "check"
+    }
+    fn cli(&self, cmd: clap::Command) -> clap::Command {
+        cmd.about("builds an archival site and checks it for broken links")
+    }
+    fn handler(
+        &self,
+        build_dir: &Path,
+        _args: &ArgMatches,
+    ) -> Result<crate::binary::ExitStatus, Box<dyn std::error::Error>> {
+        let mut fs = file_system_stdlib::NativeFileSystem::new(build_dir);
+        let site = Site::load(&fs)?;
+        let _ = fs.remove_dir_all(&site.manifest.build_dir);
+        site.sync_static_files(&mut fs)?;
+        site.build(&mut fs)?;
+        let broken = site.check_links(&fs)?;
+        for link in &broken {
+            println!("{}", link);
+        }
+        if broken.is_empty() {
+            println!("no broken links");
+            Ok(ExitStatus::Ok)
+        } else {
+            println!("{} broken links", broken.len());
+            Ok(ExitStatus::Error)
+        }
+    }
+}
@@ -372,6 +372,7 @@ impl Command {
                                 object: object.to_string(),
                                 filename: f.to_string(),
                                 path: current_path.to_owned(),
+                                block: None,
                                 source: None,
                             }),
                             None,
@@ -434,6 +435,7 @@ impl Command {
                                 path: current_path.clone(),
                                 value: Some(value),
                                 field: name.to_string(),
+                                locale: None,
                                 source: None,
                             }),
                             None,
@@ -478,6 +480,7 @@ impl Command {
                                     path: current_path.clone().concat(col_path),
                                     value: Some(value),
                                     field: col_field,
+                                    locale: None,
                                     source: None,
                                 }),
                                 None,
@@ -504,10 +507,11 @@ mod csv_tests {
     use super::{Command, ImportFormat, ImportName};
     use crate::fields::DateTime;
     use crate::object::ValuePath;
-    use crate::{unpack_zip, FieldValue, MemoryFileSystem};
+    use crate::{unpack_zip, FieldValue, FileSystemAPI, MemoryFileSystem};
     use std::collections::HashMap;
     use std::error::Error;
     use std::io::BufReader;
+    use std::path::Path;
 
     #[test]
     fn parse_csv_data_to_files() -> Result<(), Box<dyn Error>> {
@@ -548,7 +552,7 @@ mod csv_tests {
                     Some(&crate::FieldValue::Markdown(" string".to_string()))
                 );
                 let expected_date =
-                    crate::FieldValue::Date(DateTime::from_ymd(1987, 1, 21)).to_string();
+                    crate::FieldValue::Date(DateTime::from_ymd(1987, 1, 21)?).to_string();
                 assert_eq!(post.values.get("date").unwrap().to_string(), expected_date);
             }
         }
@@ -612,7 +616,7 @@ mod csv_tests {
                         Some(&crate::FieldValue::String("hello".to_string()))
                     );
                     let expected_date =
-                        crate::FieldValue::Date(DateTime::from_ymd(1987, 1, 21)).to_string();
+                        crate::FieldValue::Date(DateTime::from_ymd(1987, 1, 21)?).to_string();
                     assert_eq!(new.get("date").unwrap().to_string(), expected_date);
                 }
             }
@@ -620,4 +624,44 @@ mod csv_tests {
         assert!(found);
         Ok(())
     }
+
+    #[test]
+    fn parse_csv_list_fields() -> Result<(), Box<dyn Error>> {
+        let csv_data = "title,tags\nlisted,\"rust, web\"";
+        let mut reader = BufReader::new(csv_data.as_bytes());
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../../../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        let objects_toml = fs.read_to_string(Path::new("objects.toml"))?.unwrap();
+        fs.write_str(
+            Path::new("objects.toml"),
+            objects_toml.replace("[post]\n", "[post]\ntags = \"string[]\"\n"),
+        )?;
+        let archival = crate::Archival::new(fs)?;
+        let obj_def = archival.site.object_definitions.get("post").unwrap();
+        Command::parse(
+            &mut reader,
+            "post",
+            HashMap::new(),
+            ImportName::Field("title".to_string()),
+            ValuePath::empty(),
+            obj_def,
+            &ImportFormat::Csv,
+            &archival,
+            |m, p, t| println!("{} ({}/{})", m, p, t),
+        )?;
+        let objects = archival.get_objects()?;
+        let post = objects["post"]
+            .into_iter()
+            .find(|o| o.filename == "listed")
+            .unwrap();
+        assert_eq!(
+            post.values.get("tags"),
+            Some(&FieldValue::List(vec![
+                FieldValue::String("rust".to_string()),
+                FieldValue::String("web".to_string()),
+            ]))
+        );
+        Ok(())
+    }
 }
@@ -1,12 +1,14 @@
 use clap::{ArgMatches, Command};
 use std::{error::Error, path::Path};
 mod build;
+mod check;
 mod compat;
 mod import;
 mod login;
 mod manifest;
 mod objects;
 mod prebuild;
+mod purge;
 mod run;
 #[cfg(feature = "json-schema")]
 mod schemas;
@@ -34,8 +36,9 @@ pub trait BinaryCommand {
     fn handler(&self, build_dir: &Path, args: &ArgMatches) -> Result<ExitStatus, Box<dyn Error>>;
 }
 
-pub const COMMANDS: [&dyn BinaryCommand; 10] = [
+pub const COMMANDS: [&dyn BinaryCommand; 12] = [
     &build::Command {},
+    &check::Command {},
     &run::Command {},
     &manifest::Command {},
     &prebuild::Command {},
@@ -45,4 +48,5 @@ pub const COMMANDS: [&dyn BinaryCommand; 10] = [
     &import::Command {},
     &objects::Command {},
     &schemas::Command {},
+    &purge::Command {},
 ];
@@ -1,6 +1,7 @@
 use super::BinaryCommand;
 use crate::{
-    binary::ExitStatus, file_system_stdlib, object::ObjectEntry, page::debug_context, site::Site,
+    binary::ExitStatus, fields::MarkdownContext, file_system_stdlib, object::ObjectEntry,
+    page::debug_context, site::Site,
 };
 use clap::ArgMatches;
 use liquid_core::Value;
@@ -23,9 +24,13 @@ impl BinaryCommand for Command {
         let site = Site::load(&fs)?;
         let mut objects: HashMap<String, liquid::model::Value> = HashMap::new();
         for (name, obj_entry) in site.get_objects(&fs)? {
+            let definition = site.object_definitions.get(&name);
             let values = match obj_entry {
-                ObjectEntry::List(l) => Value::array(l.iter().map(|o| o.liquid_object())),
-                ObjectEntry::Object(o) => o.liquid_object(),
+                ObjectEntry::List(l) => Value::array(
+                    l.iter()
+                        .map(|o| o.liquid_object(definition, &MarkdownContext::default())),
+                ),
+                ObjectEntry::Object(o) => o.liquid_object(definition, &MarkdownContext::default()),
             };
             objects.insert(name.to_string(), values);
         }
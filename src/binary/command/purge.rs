@@ -0,0 +1,30 @@
+use super::BinaryCommand;
+use crate::{binary::ExitStatus, file_system_stdlib, site::Site};
+use clap::{arg, value_parser, ArgMatches};
+use std::path::Path;
+
+pub struct Command {}
+impl BinaryCommand for Command {
+    fn name(&self) -> &str {
+        "purge"
+    }
+    fn cli(&self, cmd: clap::Command) -> clap::Command {
+        cmd.about("permanently deletes trashed objects").arg(
+            arg!(-o --object <name> "an object name. If not provided, will purge all trashed objects.")
+                .required(false)
+                .value_parser(value_parser!(String)),
+        )
+    }
+    fn handler(
+        &self,
+        build_dir: &Path,
+        args: &ArgMatches,
+    ) -> Result<crate::binary::ExitStatus, Box<dyn std::error::Error>> {
+        let mut fs = file_system_stdlib::NativeFileSystem::new(build_dir);
+        let object = args.get_one::<String>("object");
+        let site = Site::load(&fs)?;
+        let purged = site.purge_trash(object.map(|o| o.as_str()), &mut fs)?;
+        println!("purged {} trashed objects", purged);
+        Ok(ExitStatus::Ok)
+    }
+}
@@ -59,6 +59,7 @@ impl BinaryCommand for Command {
         let path = build_dir.join(&site.manifest.build_dir);
         if !args.get_one::<bool>("noserve").unwrap() {
             let mut sb = server::ServerBuilder::new(&path, Some("404.html"));
+            sb.formats(site.manifest.formats.clone());
             if let Some(port) = args.get_one::<u16>("port") {
                 sb.port(*port);
             }
@@ -7,6 +7,9 @@ pub const BUILD_DIR_NAME: &str = "dist";
 pub const SCHEMAS_DIR_NAME: &str = "schemas";
 pub const STATIC_DIR_NAME: &str = "public";
 pub const LAYOUT_DIR_NAME: &str = "layout";
+pub const HIGHLIGHT_CSS_FILE_NAME: &str = "highlight.css";
+pub const ASSET_MANIFEST_FILE_NAME: &str = "asset-manifest.json";
+pub const MINIFY_OUTPUTS: [&str; 4] = ["html", "css", "json", "js"];
 pub const NESTED_TYPES: [&str; 5] = ["meta", "upload", "video", "audio", "image"];
 #[cfg(debug_assertions)]
 pub const UPLOADS_URL: &str = "http://localhost:7777";
@@ -8,14 +8,17 @@ use crate::{value_path::ValuePath, FieldValue};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "typescript", derive(TypeDef))]
+#[allow(clippy::large_enum_variant)]
 pub enum ArchivalEvent {
     AddObject(AddObjectEvent),
     AddRootObject(AddRootObjectEvent),
     DeleteObject(DeleteObjectEvent),
+    RestoreObject(RestoreObjectEvent),
     EditField(EditFieldEvent),
     EditOrder(EditOrderEvent),
     AddChild(ChildEvent),
     RemoveChild(ChildEvent),
+    MoveChild(MoveChildEvent),
 }
 
 impl ArchivalEvent {
@@ -24,10 +27,12 @@ impl ArchivalEvent {
             ArchivalEvent::AddObject(evt) => &evt.object,
             ArchivalEvent::AddRootObject(evt) => &evt.object,
             ArchivalEvent::DeleteObject(evt) => &evt.object,
+            ArchivalEvent::RestoreObject(evt) => &evt.object,
             ArchivalEvent::EditField(evt) => &evt.object,
             ArchivalEvent::EditOrder(evt) => &evt.object,
             ArchivalEvent::AddChild(evt) => &evt.object,
             ArchivalEvent::RemoveChild(evt) => &evt.object,
+            ArchivalEvent::MoveChild(evt) => &evt.object,
         }
     }
     pub fn filename(&self) -> &str {
@@ -35,10 +40,12 @@ impl ArchivalEvent {
             ArchivalEvent::AddObject(evt) => &evt.filename,
             ArchivalEvent::AddRootObject(evt) => &evt.object,
             ArchivalEvent::DeleteObject(evt) => &evt.filename,
+            ArchivalEvent::RestoreObject(evt) => &evt.filename,
             ArchivalEvent::EditField(evt) => &evt.filename,
             ArchivalEvent::EditOrder(evt) => &evt.filename,
             ArchivalEvent::AddChild(evt) => &evt.filename,
             ArchivalEvent::RemoveChild(evt) => &evt.filename,
+            ArchivalEvent::MoveChild(evt) => &evt.filename,
         }
     }
 }
@@ -57,6 +64,8 @@ impl Display for ArchivalEvent {
                 }
                 ArchivalEvent::DeleteObject(evt) =>
                     format!("Delete {} '{}'", evt.object, evt.filename),
+                ArchivalEvent::RestoreObject(evt) =>
+                    format!("Restore {} '{}'", evt.object, evt.filename),
                 ArchivalEvent::EditField(evt) => format!(
                     "Change field {} in {} '{}'",
                     evt.field, evt.object, evt.filename
@@ -66,6 +75,16 @@ impl Display for ArchivalEvent {
                 }
                 ArchivalEvent::AddChild(evt) => {
                     let child_name = &evt.path.first().to_string();
+                    if let Some(block) = &evt.block {
+                        return write!(
+                            f,
+                            "Add {} block to {} of {} '{}'",
+                            indefinite(block),
+                            child_name,
+                            evt.object,
+                            evt.filename
+                        );
+                    }
                     format!(
                         "Add {} child to {} '{}'",
                         indefinite(child_name),
@@ -82,6 +101,15 @@ impl Display for ArchivalEvent {
                         evt.filename
                     )
                 }
+                ArchivalEvent::MoveChild(evt) => {
+                    let child_name = &evt.path.first().to_string();
+                    format!(
+                        "Move {} child of {} '{}'",
+                        indefinite(child_name),
+                        evt.object,
+                        evt.filename
+                    )
+                }
             }
         )
     }
@@ -102,6 +130,10 @@ pub struct EditFieldEvent {
     pub path: ValuePath,
     pub field: String,
     pub value: Option<FieldValue>,
+    /// When set to a locale other than the site's default, edits the
+    /// translated value for that locale.
+    #[serde(default)]
+    pub locale: Option<String>,
     pub source: Option<String>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -121,6 +153,14 @@ pub struct DeleteObjectEvent {
     pub source: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(TypeDef))]
+pub struct RestoreObjectEvent {
+    pub object: String,
+    pub filename: String,
+    pub source: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "typescript", derive(TypeDef))]
 pub struct AddObjectValue {
@@ -150,6 +190,20 @@ pub struct ChildEvent {
     pub object: String,
     pub filename: String,
     pub path: ValuePath,
+    /// The type of block to add, when adding to a blocks field.
+    #[serde(default)]
+    pub block: Option<String>,
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(TypeDef))]
+pub struct MoveChildEvent {
+    pub object: String,
+    pub filename: String,
+    /// The path of the child to move, ending in its current index.
+    pub path: ValuePath,
+    pub to: usize,
     pub source: Option<String>,
 }
 
@@ -1,129 +1,250 @@
-use super::InvalidFieldError;
+use super::{FieldConfig, InvalidFieldError};
 use liquid::model;
-use once_cell::sync::Lazy;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::{
-    error::Error,
-    fmt::{self, Debug, Display},
+use std::fmt::{self, Debug, Display};
+use time::{
+    format_description::well_known::Iso8601, Date, Month, OffsetDateTime, PrimitiveDateTime, Time,
+    UtcOffset,
 };
-use time::{format_description, UtcOffset};
 
+/// Which parts of a point in time a value specifies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateKind {
+    Date,
+    DateTime,
+    Time,
+}
+
+/// A date, date-time or time value.
+///
+/// Values are stored in their RFC 3339 form (as TOML writes them), so local
+/// values and offsets survive a round trip unchanged. Values without an
+/// offset are interpreted in the site timezone (see `FieldConfig`), and
+/// time-only values are placed on 1970-01-01.
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, PartialOrd)]
+#[serde(try_from = "RawDateTime")]
 #[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
 pub struct DateTime {
     #[serde(skip)]
-    inner: Option<model::DateTime>,
+    inner: model::DateTime,
+    raw: String,
+}
+
+/// The serialized form of a DateTime, which is parsed when deserializing so
+/// that invalid values fail there instead of when they are rendered.
+#[derive(Deserialize)]
+struct RawDateTime {
     raw: String,
 }
 
-static YEAR_FIRST_FMT_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"(?<year>\d{4})[\/-](?<month>\d{2})[\/-](?<day>\d{2})").unwrap());
+impl TryFrom<RawDateTime> for DateTime {
+    type Error = InvalidFieldError;
+    fn try_from(value: RawDateTime) -> Result<Self, Self::Error> {
+        Self::from(&value.raw).or_else(|_| Self::from_legacy(&value.raw))
+    }
+}
+
+/// Parses a site timezone, which is either `UTC`/`Z` or a fixed offset such
+/// as `+02:00`, `-0800` or `+05`. Named timezones (`America/Los_Angeles`)
+/// are not supported and return None.
+pub fn parse_timezone(timezone: &str) -> Option<UtcOffset> {
+    let timezone = timezone.trim();
+    if matches!(timezone, "UTC" | "utc" | "Z") {
+        return Some(UtcOffset::UTC);
+    }
+    let sign = match timezone.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let digits = timezone[1..].replace(':', "");
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i8>().ok()?, 0),
+        4 => (digits[..2].parse().ok()?, digits[2..].parse::<i8>().ok()?),
+        _ => return None,
+    };
+    UtcOffset::from_hms(sign * hours, sign * minutes, 0).ok()
+}
+
+fn to_toml_parts(
+    date: Option<Date>,
+    time: Option<Time>,
+    offset: Option<UtcOffset>,
+) -> toml_datetime::Datetime {
+    toml_datetime::Datetime {
+        date: date.map(|d| toml_datetime::Date {
+            year: d.year() as u16,
+            month: d.month() as u8,
+            day: d.day(),
+        }),
+        time: time.map(|t| toml_datetime::Time {
+            hour: t.hour(),
+            minute: t.minute(),
+            second: t.second(),
+            nanosecond: t.nanosecond(),
+        }),
+        offset: offset.map(|o| {
+            if o.is_utc() {
+                toml_datetime::Offset::Z
+            } else {
+                toml_datetime::Offset::Custom {
+                    minutes: o.whole_minutes(),
+                }
+            }
+        }),
+    }
+}
 
 impl DateTime {
+    /// Parses an ISO 8601 date (`2024-01-31`), date-time
+    /// (`2024-01-31T10:00:00+01:00`, with `T` or a space) or time
+    /// (`10:00:00`).
     pub fn from(str: &str) -> Result<Self, InvalidFieldError> {
-        let liquid_date = model::DateTime::from_str(str)
+        let trimmed = str.trim();
+        let toml_datetime = trimmed
+            .parse::<toml_datetime::Datetime>()
+            .ok()
+            .or_else(|| Self::parse_iso8601(trimmed))
             .ok_or_else(|| InvalidFieldError::InvalidDate(str.to_owned()))?;
-        Ok(Self {
-            inner: Some(liquid_date),
-            raw: str.to_owned(),
-        })
+        Self::from_toml(&toml_datetime)
     }
-    pub fn from_toml(toml_datetime: &toml_datetime::Datetime) -> Result<Self, InvalidFieldError> {
-        // Convert to `YYYY-MM-DD HH:MM:SS`
-        let mut date_str = if let Some(date) = toml_datetime.date {
-            date.to_string()
-        } else {
-            let (y, m, d) = model::DateTime::now().to_calendar_date();
-            format!("{:04}-{:02}-{:02}", y, m as u8, d)
-        };
-        if let Some(time) = toml_datetime.time {
-            date_str += &format!(" {}", time);
-        } else {
-            date_str += " 00:00:00";
+
+    /// Handles the ISO 8601 forms that are not valid TOML, e.g. times without
+    /// seconds or the basic (`20240131T1000Z`) format.
+    fn parse_iso8601(str: &str) -> Option<toml_datetime::Datetime> {
+        let iso = Iso8601::DEFAULT;
+        if let Ok(dt) = OffsetDateTime::parse(str, &iso) {
+            return Some(to_toml_parts(
+                Some(dt.date()),
+                Some(dt.time()),
+                Some(dt.offset()),
+            ));
+        }
+        if let Ok(dt) = PrimitiveDateTime::parse(str, &iso) {
+            return Some(to_toml_parts(Some(dt.date()), Some(dt.time()), None));
+        }
+        if let Ok(date) = Date::parse(str, &iso) {
+            return Some(to_toml_parts(Some(date), None, None));
         }
-        let liquid_date = if let Some(dt) = model::DateTime::from_str(&date_str) {
-            dt
+        Time::parse(str, &iso)
+            .ok()
+            .map(|time| to_toml_parts(None, Some(time), None))
+    }
+
+    /// Parses a date string in one of the non-ISO formats that older
+    /// versions wrote (e.g. `MM/DD/YYYY`), so that existing content keeps
+    /// loading. These values are written back in ISO 8601 form.
+    pub fn from_legacy(str: &str) -> Result<Self, InvalidFieldError> {
+        let invalid = || InvalidFieldError::InvalidDate(str.to_owned());
+        let has_time = str.contains(':');
+        let with_time = if has_time {
+            str.to_string()
         } else {
-            return Err(InvalidFieldError::InvalidDate(toml_datetime.to_string()));
+            format!("{} 00:00:00", str)
+        };
+        // Values that already have an offset fail to parse with another one.
+        let (parsed, has_offset) = match model::DateTime::from_str(&format!("{} +0000", with_time))
+        {
+            Some(dt) => (dt, false),
+            None => (
+                model::DateTime::from_str(&with_time).ok_or_else(invalid)?,
+                true,
+            ),
+        };
+        Self::from_toml(&to_toml_parts(
+            Some((*parsed).date()),
+            has_time.then(|| parsed.time()),
+            (has_time && has_offset).then(|| parsed.offset()),
+        ))
+    }
+
+    pub fn from_toml(toml_datetime: &toml_datetime::Datetime) -> Result<Self, InvalidFieldError> {
+        let invalid = || InvalidFieldError::InvalidDate(toml_datetime.to_string());
+        let date = match toml_datetime.date {
+            Some(d) => Date::from_calendar_date(
+                d.year as i32,
+                Month::try_from(d.month).map_err(|_| invalid())?,
+                d.day,
+            )
+            .map_err(|_| invalid())?,
+            None => OffsetDateTime::UNIX_EPOCH.date(),
         };
+        let time = match toml_datetime.time {
+            Some(t) => Time::from_hms_nano(t.hour, t.minute, t.second, t.nanosecond)
+                .map_err(|_| invalid())?,
+            None => Time::MIDNIGHT,
+        };
+        let offset = match toml_datetime.offset {
+            Some(toml_datetime::Offset::Z) => UtcOffset::UTC,
+            Some(toml_datetime::Offset::Custom { minutes }) => {
+                UtcOffset::from_whole_seconds(minutes as i32 * 60).map_err(|_| invalid())?
+            }
+            None => FieldConfig::get().timezone,
+        };
+        let mut inner = model::DateTime::now();
+        *inner = PrimitiveDateTime::new(date, time).assume_offset(offset);
         Ok(Self {
-            inner: Some(liquid_date),
+            inner,
             raw: toml_datetime.to_string(),
         })
     }
-    pub fn now() -> Self {
-        let inner = model::DateTime::now();
-        let raw = inner.to_string();
-        Self {
-            inner: Some(inner),
-            raw,
-        }
+
+    /// The TOML representation of this value, which has the same date, time
+    /// and offset (or lack thereof) that it was created with.
+    pub fn to_toml(&self) -> toml_datetime::Datetime {
+        self.raw.parse().unwrap_or_else(|_| {
+            let dt = *self.as_liquid_datetime();
+            to_toml_parts(Some(dt.date()), Some(dt.time()), Some(dt.offset()))
+        })
     }
-    pub fn from_ymd(year: i32, month: u8, date: u8) -> Self {
-        let inner = model::DateTime::from_ymd(year, month, date);
-        let raw = inner.to_string();
-        Self {
-            inner: Some(inner),
-            raw,
+
+    pub fn kind(&self) -> DateKind {
+        let toml_datetime = self.to_toml();
+        match (toml_datetime.date, toml_datetime.time) {
+            (Some(_), None) => DateKind::Date,
+            (None, Some(_)) => DateKind::Time,
+            _ => DateKind::DateTime,
         }
     }
 
-    // Supported formats:
-    //
-    // * `default` - `YYYY-MM-DD HH:MM:SS`
-    // * `day_month` - `DD Month YYYY HH:MM:SS`
-    // * `day_mon` - `DD Mon YYYY HH:MM:SS`
-    // * `mdy` -  `MM/DD/YYYY HH:MM:SS`
-    // * `dow_mon` - `Dow Mon DD HH:MM:SS YYYY`
-    //
-    // Offsets in one of the following forms, and are catenated with any of
-    // the above formats.
-    //
-    // * `+HHMM`
-    // * `-HHMM`
-    pub fn parse_date_string(mut date_str: String) -> Result<String, Box<dyn Error>> {
-        // Legacy: support year-first formats:
-        date_str = YEAR_FIRST_FMT_RE
-            .replace(&date_str, "$month/$day/$year")
-            .to_string();
-        // Also pretty lazy: check if we're missing time and add it
-        if !date_str.contains(':') {
-            date_str = format!("{} 00:00:00", date_str);
-        }
-        // Append local offset if available
-        if let Ok(offset) = UtcOffset::current_local_offset() {
-            let fmt = format_description::parse("[offset_hour sign:mandatory][offset_minute]")?;
-            date_str = format!("{} {}", date_str, offset.format(&fmt)?);
-        }
-        Ok(date_str)
+    pub fn now() -> Self {
+        let now = OffsetDateTime::now_utc();
+        Self::from_toml(&to_toml_parts(
+            Some(now.date()),
+            Some(now.time()),
+            Some(now.offset()),
+        ))
+        .expect("the current time is always valid")
+    }
+
+    /// The current date in the site timezone.
+    pub fn today() -> Self {
+        let offset = FieldConfig::get().timezone;
+        let today = OffsetDateTime::now_utc().to_offset(offset).date();
+        Self::from_toml(&to_toml_parts(Some(today), None, None))
+            .expect("the current date is always valid")
     }
 
-    pub fn bounce(&mut self) {
-        let date_str = Self::parse_date_string(self.raw.to_string())
-            .unwrap_or_else(|_| format!("Invalid date value {}", self.raw));
-        self.inner = Some(
-            model::DateTime::from_str(&date_str)
-                .unwrap_or_else(|| panic!("Invalid date value {}", self.raw)),
-        )
+    pub fn from_ymd(year: i32, month: u8, day: u8) -> Result<Self, InvalidFieldError> {
+        let invalid = || InvalidFieldError::InvalidDate(format!("{}-{}-{}", year, month, day));
+        let month = Month::try_from(month).map_err(|_| invalid())?;
+        let date = Date::from_calendar_date(year, month, day).map_err(|_| invalid())?;
+        Self::from_toml(&to_toml_parts(Some(date), None, None))
     }
 
+    /// Values are parsed when they are created, so there is nothing to do
+    /// here.
+    pub fn bounce(&mut self) {}
+
     pub fn borrowed_as_datetime(&self) -> &model::DateTime {
-        if self.inner.is_none() {
-            panic!("cannot borrow datetime before it is bounced");
-        }
-        self.inner.as_ref().unwrap()
+        &self.inner
     }
 
     pub fn as_liquid_datetime(&self) -> model::DateTime {
-        if let Some(inner) = self.inner {
-            inner
-        } else {
-            let date_str = Self::parse_date_string(self.raw.to_string())
-                .unwrap_or_else(|_| format!("Invalid date value {}", self.raw));
-            model::DateTime::from_str(&date_str)
-                .unwrap_or_else(|| panic!("Invalid date value {}", self.raw))
-        }
+        self.inner
     }
 }
 
@@ -132,3 +253,96 @@ impl Display for DateTime {
         write!(f, "{}", self.raw.to_owned())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_iso8601() -> Result<(), InvalidFieldError> {
+        assert_eq!(DateTime::from("2024-01-31")?.kind(), DateKind::Date);
+        assert_eq!(
+            DateTime::from("2024-01-31T10:00:00+01:00")?.kind(),
+            DateKind::DateTime
+        );
+        assert_eq!(DateTime::from("10:30")?.to_string(), "10:30:00");
+        assert_eq!(DateTime::from("10:30:15")?.kind(), DateKind::Time);
+        assert_eq!(
+            DateTime::from("20240131T1000Z")?.to_string(),
+            "2024-01-31T10:00:00Z"
+        );
+        assert_eq!(
+            DateTime::from("2024-01-31 10:00:00")?.to_string(),
+            "2024-01-31T10:00:00"
+        );
+        assert!(DateTime::from("01/31/2024").is_err());
+        assert_eq!(
+            DateTime::from_legacy("01/31/2024")?.to_string(),
+            "2024-01-31"
+        );
+        assert_eq!(
+            DateTime::from_legacy("01/31/2024 10:00:00")?.to_string(),
+            "2024-01-31T10:00:00"
+        );
+        assert!(DateTime::from("2024-02-30").is_err());
+        assert!(DateTime::from("tomorrow").is_err());
+        assert_eq!(DateTime::from_ymd(2024, 1, 31)?.to_string(), "2024-01-31");
+        assert!(DateTime::from_ymd(2024, 2, 30).is_err());
+        assert!(DateTime::from_ymd(2024, 13, 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn deserializes_legacy_values() -> Result<(), Box<dyn std::error::Error>> {
+        let dt: DateTime = serde_json::from_str(r#"{"raw": "01/21/1987"}"#)?;
+        assert_eq!(dt.to_string(), "1987-01-21");
+        assert_eq!(dt.as_liquid_datetime().year(), 1987);
+        let dt: DateTime = serde_json::from_str(r#"{"raw": "2024-01-31T10:00:00Z"}"#)?;
+        assert_eq!(dt.as_liquid_datetime().hour(), 10);
+        assert!(serde_json::from_str::<DateTime>(r#"{"raw": "tomorrow"}"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn round_trips_offsets() -> Result<(), InvalidFieldError> {
+        for raw in [
+            "2024-01-31T10:00:00-08:00",
+            "2024-01-31T10:00:00Z",
+            "2024-01-31T10:00:00",
+            "2024-01-31",
+            "10:00:00",
+        ] {
+            let toml_datetime: toml_datetime::Datetime =
+                raw.parse().expect("test values are valid TOML datetimes");
+            let dt = DateTime::from_toml(&toml_datetime)?;
+            assert_eq!(dt.to_toml(), toml_datetime);
+        }
+        let dt = DateTime::from("2024-01-31T10:00:00-08:00")?;
+        let liquid_dt = dt.as_liquid_datetime();
+        assert_eq!(liquid_dt.offset().whole_hours(), -8);
+        assert_eq!(liquid_dt.hour(), 10);
+        Ok(())
+    }
+
+    #[test]
+    fn local_values_use_the_site_timezone() -> Result<(), InvalidFieldError> {
+        // Local values resolve to UTC unless a site timezone is configured,
+        // regardless of the machine's local offset.
+        let dt = DateTime::from("2024-01-31T10:00:00")?;
+        assert_eq!(
+            dt.as_liquid_datetime().offset(),
+            FieldConfig::get().timezone
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn timezones() {
+        assert_eq!(parse_timezone("UTC"), Some(UtcOffset::UTC));
+        assert_eq!(parse_timezone("-08:00"), UtcOffset::from_hms(-8, 0, 0).ok());
+        assert_eq!(parse_timezone("+0530"), UtcOffset::from_hms(5, 30, 0).ok());
+        assert_eq!(parse_timezone("+02"), UtcOffset::from_hms(2, 0, 0).ok());
+        assert_eq!(parse_timezone("America/Los_Angeles"), None);
+        assert_eq!(parse_timezone("+2:00"), None);
+    }
+}
@@ -2,10 +2,14 @@ use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Display};
 use thiserror::Error;
 
+use super::{
+    file::FileError, formats, meta::MetaError, DateKind, DateTime, LocationError, MarkdownOptions,
+    MetaSchema,
+};
 use crate::manifest::EditorTypes;
 
 #[cfg(feature = "json-schema")]
-use super::{file::DisplayType, File};
+use super::{file::DisplayType, File, Location};
 
 #[derive(Error, Debug, Clone)]
 pub enum InvalidFieldError {
@@ -37,6 +41,30 @@ pub enum InvalidFieldError {
     NoDefaultForType(String),
     #[error("field '{0}' failed validator '{1}'")]
     FailedValidation(String, String),
+    #[error("cannot translate '{0}', it is not a field of {1}")]
+    InvalidTranslatableField(String, String),
+    #[error("invalid taxonomy for field '{0}' of {1}")]
+    InvalidTaxonomy(String, String),
+    #[error("invalid template {1:?} for {0}")]
+    InvalidTemplate(String, String),
+    #[error("unknown block type {1:?} for {0}")]
+    InvalidBlockType(String, String),
+    #[error("{0} defines blocks, so it cannot also define fields or children")]
+    MixedBlocks(String),
+    #[error("invalid meta schema {0}")]
+    InvalidMetaSchema(String),
+    #[error("unexpected meta key {0}")]
+    UnexpectedMetaKey(String),
+    #[error("invalid markdown options {0}")]
+    InvalidMarkdownOptions(String),
+    #[error("{0:?} is not a valid {1}")]
+    InvalidFormat(String, String),
+    #[error(transparent)]
+    InvalidFile(#[from] FileError),
+    #[error(transparent)]
+    InvalidMeta(#[from] MetaError),
+    #[error(transparent)]
+    InvalidLocation(#[from] LocationError),
 }
 
 #[cfg(feature = "typescript")]
@@ -51,6 +79,12 @@ mod typedefs {
             r#ref: TypeExpr::ident(Ident("[FieldType, string]")),
         });
     }
+    pub struct ListTypeDef;
+    impl TypeDef for ListTypeDef {
+        const INFO: TypeInfo = TypeInfo::Native(NativeTypeInfo {
+            r#ref: TypeExpr::ident(Ident("FieldType")),
+        });
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
@@ -59,6 +93,8 @@ pub enum FieldType {
     String,
     Number,
     Date,
+    DateTime,
+    Time,
     Markdown,
     Boolean,
     Image,
@@ -66,10 +102,28 @@ pub enum FieldType {
     Upload,
     Audio,
     Meta,
+    // String types with built-in validation and normalization, see
+    // FieldType::normalize.
+    Color,
+    Url,
+    Email,
+    Slug,
+    Location,
+    // A meta field with a schema, defined by an editor type or by the
+    // `_meta_schemas` key of an object definition.
+    TypedMeta(Box<MetaSchema>),
+    // A markdown field with its own rendering options, defined by an editor
+    // type or by the `_markdown_options` key of an object definition.
+    TypedMarkdown(Box<MarkdownOptions>),
     Alias(
         #[cfg_attr(feature = "typescript", type_def(type_of = "typedefs::AliasTypeDef"))]
         Box<(FieldType, String)>,
     ),
+    // A list of scalar values, defined with a `[]` suffix, e.g. "string[]"
+    List(
+        #[cfg_attr(feature = "typescript", type_def(type_of = "typedefs::ListTypeDef"))]
+        Box<FieldType>,
+    ),
 }
 
 impl FieldType {
@@ -78,16 +132,47 @@ impl FieldType {
             Self::String => "string",
             Self::Number => "number",
             Self::Date => "date",
-            Self::Markdown => "markdown",
+            Self::DateTime => "datetime",
+            Self::Time => "time",
+            Self::Markdown | Self::TypedMarkdown(_) => "markdown",
             Self::Boolean => "boolean",
             Self::Image => "image",
             Self::Video => "video",
             Self::Audio => "audio",
             Self::Upload => "upload",
-            Self::Meta => "meta",
+            Self::Meta | Self::TypedMeta(_) => "meta",
+            Self::Color => "color",
+            Self::Url => "url",
+            Self::Email => "email",
+            Self::Slug => "slug",
+            Self::Location => "location",
             Self::Alias(a) => a.0.to_str(),
+            Self::List(t) => match **t {
+                Self::String => "string[]",
+                Self::Number => "number[]",
+                Self::Date => "date[]",
+                Self::DateTime => "datetime[]",
+                Self::Time => "time[]",
+                Self::Boolean => "boolean[]",
+                Self::Color => "color[]",
+                Self::Url => "url[]",
+                Self::Email => "email[]",
+                Self::Slug => "slug[]",
+                // Not constructible through FieldType::list
+                _ => "list",
+            },
         }
     }
+    /// A list of scalar values, e.g. `string[]`.
+    pub fn list(item_type: FieldType) -> Result<FieldType, InvalidFieldError> {
+        if !item_type.is_scalar() {
+            return Err(InvalidFieldError::UnrecognizedType(format!(
+                "{}[]",
+                item_type
+            )));
+        }
+        Ok(FieldType::List(Box::new(item_type)))
+    }
     pub fn from_str(
         string: &str,
         editor_types: &EditorTypes,
@@ -96,6 +181,8 @@ impl FieldType {
             "string" => Ok(FieldType::String),
             "number" => Ok(FieldType::Number),
             "date" => Ok(FieldType::Date),
+            "datetime" => Ok(FieldType::DateTime),
+            "time" => Ok(FieldType::Time),
             "markdown" => Ok(FieldType::Markdown),
             "boolean" => Ok(FieldType::Boolean),
             "image" => Ok(FieldType::Image),
@@ -103,12 +190,33 @@ impl FieldType {
             "audio" => Ok(FieldType::Audio),
             "upload" => Ok(FieldType::Upload),
             "meta" => Ok(FieldType::Meta),
+            "color" => Ok(FieldType::Color),
+            "url" => Ok(FieldType::Url),
+            "email" => Ok(FieldType::Email),
+            "slug" => Ok(FieldType::Slug),
+            "location" => Ok(FieldType::Location),
+            t if t.ends_with("[]") => {
+                let item_type = FieldType::from_str(&t[..t.len() - 2], editor_types)
+                    .map_err(|_| InvalidFieldError::UnrecognizedType(string.to_string()))?;
+                FieldType::list(item_type)
+            }
             t => {
                 if let Some(et) = editor_types.get(t) {
-                    Ok(FieldType::Alias(Box::new((
-                        FieldType::from_str(&et.alias_of, editor_types)?,
-                        t.to_string(),
-                    ))))
+                    let mut alias_of = FieldType::from_str(&et.alias_of, editor_types)?;
+                    if let Some(schema) = &et.schema {
+                        alias_of = alias_of
+                            .with_meta_schema(schema.clone())
+                            .ok_or_else(|| InvalidFieldError::InvalidMetaSchema(t.to_string()))?;
+                    }
+                    if let Some(options) = &et.markdown {
+                        alias_of =
+                            alias_of
+                                .with_markdown_options(options.clone())
+                                .ok_or_else(|| {
+                                    InvalidFieldError::InvalidMarkdownOptions(t.to_string())
+                                })?;
+                    }
+                    Ok(FieldType::Alias(Box::new((alias_of, t.to_string()))))
                 } else {
                     Err(InvalidFieldError::UnrecognizedType(string.to_string()))
                 }
@@ -116,6 +224,111 @@ impl FieldType {
         }
     }
 
+    /// Returns this type with the given schema attached, if it is a meta type
+    /// or an alias of one.
+    pub fn with_meta_schema(&self, schema: MetaSchema) -> Option<FieldType> {
+        match self {
+            FieldType::Meta | FieldType::TypedMeta(_) => {
+                Some(FieldType::TypedMeta(Box::new(schema)))
+            }
+            FieldType::Alias(a) => {
+                a.0.with_meta_schema(schema)
+                    .map(|t| FieldType::Alias(Box::new((t, a.1.to_string()))))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn meta_schema(&self) -> Option<&MetaSchema> {
+        match self {
+            FieldType::TypedMeta(s) => Some(s),
+            FieldType::Alias(a) => a.0.meta_schema(),
+            _ => None,
+        }
+    }
+
+    /// Returns this type with the given rendering options, if it is a markdown
+    /// type or an alias of one.
+    pub fn with_markdown_options(&self, options: MarkdownOptions) -> Option<FieldType> {
+        match self {
+            FieldType::Markdown | FieldType::TypedMarkdown(_) => {
+                Some(FieldType::TypedMarkdown(Box::new(options)))
+            }
+            FieldType::Alias(a) => {
+                a.0.with_markdown_options(options)
+                    .map(|t| FieldType::Alias(Box::new((t, a.1.to_string()))))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn markdown_options(&self) -> Option<&MarkdownOptions> {
+        match self {
+            FieldType::TypedMarkdown(o) => Some(o),
+            FieldType::Alias(a) => a.0.markdown_options(),
+            _ => None,
+        }
+    }
+
+    /// Types which may be used as the items of a list type.
+    pub fn is_scalar(&self) -> bool {
+        matches!(
+            self,
+            FieldType::String | FieldType::Number | FieldType::Boolean
+        ) || self.is_date_type()
+            || self.is_string_format()
+    }
+
+    /// Types which are stored as FieldValue::Date.
+    pub fn is_date_type(&self) -> bool {
+        matches!(
+            self,
+            FieldType::Date | FieldType::DateTime | FieldType::Time
+        )
+    }
+
+    /// Checks that a date value has the parts this type requires: times must
+    /// not have a date, and dates and datetimes must. Date fields also accept
+    /// datetimes, since that's what they have historically stored.
+    pub fn accepts_date(&self, value: &DateTime) -> bool {
+        match self {
+            FieldType::Date | FieldType::DateTime => value.kind() != DateKind::Time,
+            FieldType::Time => value.kind() == DateKind::Time,
+            FieldType::Alias(a) => a.0.accepts_date(value),
+            _ => false,
+        }
+    }
+
+    /// Types which are stored as strings, but validated and normalized to a
+    /// canonical format.
+    pub fn is_string_format(&self) -> bool {
+        matches!(
+            self,
+            FieldType::Color | FieldType::Url | FieldType::Email | FieldType::Slug
+        )
+    }
+
+    /// Validates and normalizes a string value of this type, e.g. lowercasing
+    /// hex colors or slugifying slugs. Empty values are unset and always
+    /// valid, and values of other types are returned unchanged.
+    pub fn normalize(&self, value: &str) -> Result<String, InvalidFieldError> {
+        if value.trim().is_empty() {
+            return Ok(if self.is_string_format() {
+                String::new()
+            } else {
+                value.to_string()
+            });
+        }
+        match self {
+            FieldType::Color => formats::normalize_color(value),
+            FieldType::Url => formats::normalize_url(value),
+            FieldType::Email => formats::normalize_email(value),
+            FieldType::Slug => formats::normalize_slug(value),
+            FieldType::Alias(a) => a.0.normalize(value),
+            _ => Ok(value.to_string()),
+        }
+    }
+
     pub fn is_file_type(&self) -> bool {
         matches!(
             self,
@@ -149,10 +362,35 @@ impl FieldType {
     ) -> crate::json_schema::ObjectSchema {
         match self {
             Self::Alias(a) => a.0.to_json_schema_property(description, options),
+            Self::TypedMeta(m) => {
+                let mut schema = serde_json::Map::new();
+                schema.insert("description".into(), description.into());
+                schema.extend(m.to_json_schema(options));
+                schema
+            }
+            Self::Location => Location::to_json_schema_property(description, options),
+            Self::List(t) => {
+                let mut items = t.to_json_schema_property(description, options);
+                items.remove("description");
+                let mut schema = serde_json::Map::new();
+                schema.insert("description".into(), description.into());
+                schema.insert("type".into(), "array".into());
+                schema.insert("items".into(), items.into());
+                schema
+            }
             _ => {
                 if let Some(display_type) = self.maybe_file_type() {
                     File::to_json_schema_property(description, display_type, options)
-                } else if matches!(self, Self::Date) {
+                } else if matches!(self, Self::Time) {
+                    let mut schema = serde_json::Map::new();
+                    schema.insert("description".into(), description.into());
+                    schema.insert("type".into(), "string".into());
+                    schema.insert(
+                        "pattern".into(),
+                        "^\\d{2}:\\d{2}(:\\d{2}(\\.\\d+)?)?$".into(),
+                    );
+                    schema
+                } else if self.is_date_type() {
                     let mut schema = serde_json::Map::new();
                     schema.insert("description".into(), description.into());
                     schema.insert("type".into(), "string".into());
@@ -163,10 +401,31 @@ impl FieldType {
                         .unwrap();
                         let date_str = date.with_time(time::Time::MIDNIGHT).format(&fmt).unwrap();
                         schema.insert("const".into(), date_str.into());
+                    } else if matches!(self, Self::DateTime) {
+                        schema.insert("format".into(), "date-time".into());
                     } else {
                         schema.insert("format".into(), "date".into());
                     }
                     schema
+                } else if self.is_string_format() {
+                    let mut schema = serde_json::Map::new();
+                    schema.insert("description".into(), description.into());
+                    schema.insert("type".into(), "string".into());
+                    match self {
+                        Self::Url => {
+                            schema.insert("format".into(), "uri".into());
+                        }
+                        Self::Email => {
+                            schema.insert("format".into(), "email".into());
+                        }
+                        Self::Color => {
+                            schema.insert("pattern".into(), "^#[0-9a-f]{6}([0-9a-f]{2})?$".into());
+                        }
+                        // Slugs may contain any alphanumeric characters, so
+                        // there's no portable pattern for them.
+                        _ => {}
+                    }
+                    schema
                 } else {
                     let mut schema = serde_json::Map::new();
                     schema.insert("description".into(), description.into());
@@ -177,13 +436,11 @@ impl FieldType {
                         match self {
                             Self::String => "string".into(),
                             Self::Number => "number".into(),
-                            Self::Markdown => "string".into(),
+                            Self::Markdown | Self::TypedMarkdown(_) => "string".into(),
                             Self::Boolean => "boolean".into(),
-                            // At some point, we should support providing a
-                            // schema for meta types, which would require
-                            // either inferring types based on validation or
-                            // allowing the template to directly provide a
-                            // schema for a given type.
+                            // Meta types without a schema have no known
+                            // structure, see TypedMeta for meta types with
+                            // a schema.
                             Self::Meta => {
                                 is_object = true;
                                 "object".into()
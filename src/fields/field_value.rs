@@ -1,8 +1,8 @@
 use super::file::File;
+use super::location::Location;
 use super::meta::Meta;
 use super::DateTime;
-use super::{FieldType, InvalidFieldError};
-use comrak::{markdown_to_html, ComrakOptions};
+use super::{FieldConfig, FieldType, InvalidFieldError};
 use liquid::{model, ValueView};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
@@ -35,6 +35,12 @@ mod typedefs {
             r#ref: TypeExpr::ident(Ident("Record<string, FieldValue>[]")),
         });
     }
+    pub struct ListValuesTypeDef;
+    impl TypeDef for ListValuesTypeDef {
+        const INFO: TypeInfo = TypeInfo::Native(NativeTypeInfo {
+            r#ref: TypeExpr::ident(Ident("FieldValue[]")),
+        });
+    }
 }
 
 macro_rules! compare_values {
@@ -70,6 +76,14 @@ pub enum FieldValue {
     Boolean(bool),
     File(File),
     Meta(Meta),
+    Location(Location),
+    List(
+        #[cfg_attr(
+            feature = "typescript",
+            type_def(type_of = "typedefs::ListValuesTypeDef")
+        )]
+        Vec<FieldValue>,
+    ),
 }
 fn err(f_type: &FieldType, value: String) -> FieldValueError {
     FieldValueError::InvalidValue(f_type.to_string(), value.to_owned())
@@ -87,14 +101,19 @@ impl FieldValue {
             Self::Number,
             Self::Date,
             Self::Boolean,
-            Self::File
+            Self::File,
+            Self::Location
         )
     }
-    pub fn val_with_type(f_type: &FieldType, value: String) -> Result<Self, Box<dyn Error>> {
+    pub fn val_with_type(
+        key: &String,
+        f_type: &FieldType,
+        value: String,
+    ) -> Result<Self, Box<dyn Error>> {
         let t_val = toml::Value::try_from(&value)?;
         Ok(match f_type {
             FieldType::Boolean => Self::Boolean(t_val.as_bool().ok_or_else(|| err(f_type, value))?),
-            FieldType::Markdown => Self::Markdown(
+            FieldType::Markdown | FieldType::TypedMarkdown(_) => Self::Markdown(
                 t_val
                     .as_str()
                     .ok_or_else(|| err(f_type, value))?
@@ -114,9 +133,20 @@ impl FieldValue {
                     .ok_or_else(|| err(f_type, value))?
                     .to_string(),
             ),
-            FieldType::Date => Self::Date(DateTime::from_toml(
-                t_val.as_datetime().ok_or_else(|| err(f_type, value))?,
-            )?),
+            FieldType::Date | FieldType::DateTime | FieldType::Time => {
+                let date = DateTime::from_toml(
+                    t_val
+                        .as_datetime()
+                        .ok_or_else(|| err(f_type, value.clone()))?,
+                )?;
+                if !f_type.accepts_date(&date) {
+                    return Err(err(f_type, value).into());
+                }
+                Self::Date(date)
+            }
+            FieldType::Color | FieldType::Url | FieldType::Email | FieldType::Slug => {
+                Self::String(f_type.normalize(t_val.as_str().ok_or_else(|| err(f_type, value))?)?)
+            }
             FieldType::Image => {
                 let f_info = t_val.as_table().ok_or_else(|| err(f_type, value))?;
                 Self::File(File::image().fill_from_toml_map(f_info).unwrap())
@@ -133,14 +163,39 @@ impl FieldValue {
                 let f_info = t_val.as_table().ok_or_else(|| err(f_type, value))?;
                 Self::File(File::download().fill_from_toml_map(f_info).unwrap())
             }
-            FieldType::Meta => {
+            FieldType::Meta | FieldType::TypedMeta(_) => {
                 let f_info = t_val.as_table().ok_or_else(|| err(f_type, value))?;
                 Self::Meta(Meta::from(f_info))
             }
-            FieldType::Alias(a) => Self::val_with_type(&a.0, value)?,
+            FieldType::Location => {
+                let l_info = t_val.as_table().ok_or_else(|| err(f_type, value))?;
+                Self::Location(Location::from_toml_map(l_info)?)
+            }
+            FieldType::Alias(a) => Self::val_with_type(key, &a.0, value)?,
+            FieldType::List(_) => Self::from_string(key, f_type, value)?,
         })
     }
 
+    /// Validates and normalizes string values of formatted types, see
+    /// FieldType::normalize. Untyped objects become locations when the field
+    /// is a location.
+    pub fn normalized(self, field_type: &FieldType) -> Result<Self, InvalidFieldError> {
+        match (self, field_type) {
+            (Self::String(s), t) => Ok(Self::String(t.normalize(&s)?)),
+            (Self::Meta(m), FieldType::Location) => Ok(Self::Location(Location::try_from(
+                &serde_json::Value::from(&m),
+            )?)),
+            (Self::List(items), FieldType::List(t)) => Ok(Self::List(
+                items
+                    .into_iter()
+                    .map(|i| i.normalized(t))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            (value, FieldType::Alias(a)) => value.normalized(&a.0),
+            (value, _) => Ok(value),
+        }
+    }
+
     #[cfg(test)]
     pub fn liquid_date(&self) -> model::DateTime {
         match self {
@@ -162,23 +217,7 @@ impl From<&FieldValue> for Option<toml::Value> {
             FieldValue::String(v) => Some(toml::Value::String(v.to_owned())),
             FieldValue::Markdown(v) => Some(toml::Value::String(v.to_owned())),
             FieldValue::Number(n) => Some(toml::Value::Float(*n)),
-            FieldValue::Date(d) => {
-                let d = d.as_liquid_datetime();
-                Some(toml::Value::Datetime(toml_datetime::Datetime {
-                    date: Some(toml_datetime::Date {
-                        year: d.year() as u16,
-                        month: d.month(),
-                        day: d.day(),
-                    }),
-                    time: Some(toml_datetime::Time {
-                        hour: d.hour(),
-                        minute: d.minute(),
-                        second: d.second(),
-                        nanosecond: d.nanosecond(),
-                    }),
-                    offset: None,
-                }))
-            }
+            FieldValue::Date(d) => Some(toml::Value::Datetime(d.to_toml())),
             FieldValue::Boolean(v) => Some(toml::Value::Boolean(v.to_owned())),
             FieldValue::Objects(o) => Some(toml::Value::Array(
                 o.iter()
@@ -195,6 +234,10 @@ impl From<&FieldValue> for Option<toml::Value> {
             )),
             FieldValue::File(f) => Some(toml::Value::Table(f.to_toml())),
             FieldValue::Meta(m) => Some(toml::Value::Table(m.to_toml())),
+            FieldValue::Location(l) => Some(toml::Value::Table(l.to_toml())),
+            FieldValue::List(l) => Some(toml::Value::Array(
+                l.iter().filter_map(|v| v.into()).collect(),
+            )),
         }
     }
 }
@@ -224,6 +267,8 @@ impl ValueView for FieldValue {
             FieldValue::Boolean(_) => "boolean",
             FieldValue::File(_) => "file",
             FieldValue::Meta(_) => "meta",
+            FieldValue::Location(_) => "location",
+            FieldValue::List(_) => "list",
         }
     }
     /// Interpret as a string.
@@ -246,19 +291,23 @@ impl ValueView for FieldValue {
             FieldValue::Number(n) => Some(model::ScalarCow::new(*n)),
             // TODO: should be able to return a datetime value here
             FieldValue::Date(d) => Some(model::ScalarCow::new((*d).as_liquid_datetime())),
-            FieldValue::Markdown(s) => Some(model::ScalarCow::new(markdown_to_html(
-                s,
-                &ComrakOptions::default(),
-            ))),
+            // Without the field type, we can't know whether the field
+            // allows raw HTML, see MarkdownOptions::safe.
+            FieldValue::Markdown(s) => Some(model::ScalarCow::new(
+                FieldConfig::get().markdown.safe().render(s),
+            )),
             FieldValue::Boolean(b) => Some(model::ScalarCow::new(*b)),
             FieldValue::Objects(_) => None,
             FieldValue::File(_f) => None,
             FieldValue::Meta(_m) => None,
+            FieldValue::Location(_l) => None,
+            FieldValue::List(_l) => None,
         }
     }
     fn as_array(&self) -> Option<&dyn model::ArrayView> {
         match self {
             FieldValue::Objects(a) => Some(a),
+            FieldValue::List(l) => Some(l),
             _ => None,
         }
     }
@@ -266,6 +315,7 @@ impl ValueView for FieldValue {
         match self {
             FieldValue::File(f) => Some(f),
             FieldValue::Meta(m) => Some(m),
+            FieldValue::Location(l) => Some(l),
             _ => None,
         }
     }
@@ -280,6 +330,8 @@ impl ValueView for FieldValue {
             FieldValue::Objects(_) => self.as_array().to_value(),
             FieldValue::File(_) => self.as_object().to_value(),
             FieldValue::Meta(_) => self.as_object().to_value(),
+            FieldValue::Location(_) => self.as_object().to_value(),
+            FieldValue::List(_) => self.as_array().to_value(),
         }
     }
 }
@@ -294,9 +346,13 @@ impl FieldValue {
             // Defaults
             let default_val = match field_type {
                 FieldType::String => Ok(FieldValue::String(value.clone())),
-                FieldType::Markdown => Ok(FieldValue::Markdown(value.clone())),
+                FieldType::Markdown | FieldType::TypedMarkdown(_) => {
+                    Ok(FieldValue::Markdown(value.clone()))
+                }
                 FieldType::Number => Ok(FieldValue::Number(0.0)),
                 FieldType::Boolean => Ok(FieldValue::Boolean(false)),
+                FieldType::List(_) => Ok(FieldValue::List(vec![])),
+                t if t.is_string_format() => Ok(FieldValue::String(value.clone())),
                 _ => Err(InvalidFieldError::NoDefaultForType(field_type.to_string())),
             };
             if default_val.is_ok() {
@@ -305,7 +361,7 @@ impl FieldValue {
         }
         match field_type {
             FieldType::String => Ok(FieldValue::String(value)),
-            FieldType::Markdown => Ok(FieldValue::Markdown(value)),
+            FieldType::Markdown | FieldType::TypedMarkdown(_) => Ok(FieldValue::Markdown(value)),
             FieldType::Number => Ok(FieldValue::Number(value.parse::<f64>().map_err(|_| {
                 InvalidFieldError::TypeMismatch {
                     field: key.to_owned(),
@@ -320,16 +376,23 @@ impl FieldValue {
                     value,
                 },
             )?)),
-            FieldType::Date => {
-                let date_str = DateTime::parse_date_string(value.to_string()).map_err(|_| {
-                    InvalidFieldError::TypeMismatch {
-                        field: key.to_owned(),
-                        field_type: field_type.to_string(),
-                        value,
-                    }
-                })?;
-                Ok(FieldValue::Date(DateTime::from(&date_str)?))
+            FieldType::Date | FieldType::DateTime | FieldType::Time => {
+                let date = DateTime::from(&value).or_else(|_| DateTime::from_legacy(&value))?;
+                Ok(FieldValue::Date(date_value(key, field_type, date)?))
+            }
+            FieldType::Color | FieldType::Url | FieldType::Email | FieldType::Slug => {
+                Ok(FieldValue::String(field_type.normalize(&value)?))
             }
+            FieldType::Location => Ok(FieldValue::Location(Location::from_coordinates(&value)?)),
+            // Lists are parsed from comma-separated values
+            FieldType::List(t) => Ok(FieldValue::List(
+                value
+                    .split(',')
+                    .map(|v| v.trim())
+                    .filter(|v| !v.is_empty())
+                    .map(|v| FieldValue::from_string(key, t, v.to_string()))
+                    .collect::<Result<Vec<FieldValue>, InvalidFieldError>>()?,
+            )),
             _ => Err(InvalidFieldError::UnsupportedStringValue(
                 field_type.to_string(),
             )),
@@ -353,7 +416,16 @@ impl FieldValue {
                     })?
                     .to_string(),
             )),
-            FieldType::Markdown => Ok(FieldValue::Markdown(
+            FieldType::Color | FieldType::Url | FieldType::Email | FieldType::Slug => Ok(
+                FieldValue::String(field_type.normalize(value.as_str().ok_or_else(|| {
+                    InvalidFieldError::TypeMismatch {
+                        field: key.to_owned(),
+                        field_type: field_type.to_string(),
+                        value: value.to_string(),
+                    }
+                })?)?),
+            ),
+            FieldType::Markdown | FieldType::TypedMarkdown(_) => Ok(FieldValue::Markdown(
                 value
                     .as_str()
                     .ok_or_else(|| InvalidFieldError::TypeMismatch {
@@ -384,21 +456,21 @@ impl FieldValue {
                     value: value.to_string(),
                 }
             })?)),
-            FieldType::Date => {
-                if let Value::Datetime(val) = value {
-                    return Ok(FieldValue::Date(DateTime::from_toml(val)?));
-                }
-                let mut date_str =
-                    (value
-                        .as_str()
-                        .ok_or_else(|| InvalidFieldError::TypeMismatch {
-                            field: key.to_owned(),
-                            field_type: field_type.to_string(),
-                            value: value.to_string(),
-                        })?)
-                    .to_string();
-                date_str = DateTime::parse_date_string(date_str)?;
-                Ok(FieldValue::Date(DateTime::from(&date_str)?))
+            FieldType::Date | FieldType::DateTime | FieldType::Time => {
+                let date = if let Value::Datetime(val) = value {
+                    DateTime::from_toml(val)?
+                } else {
+                    let date_str =
+                        value
+                            .as_str()
+                            .ok_or_else(|| InvalidFieldError::TypeMismatch {
+                                field: key.to_owned(),
+                                field_type: field_type.to_string(),
+                                value: value.to_string(),
+                            })?;
+                    DateTime::from(date_str).or_else(|_| DateTime::from_legacy(date_str))?
+                };
+                Ok(FieldValue::Date(date_value(key, field_type, date)?))
             }
             FieldType::Audio => Ok(FieldValue::File(
                 File::audio().fill_from_toml_map(value.as_table().ok_or_else(|| {
@@ -436,14 +508,37 @@ impl FieldValue {
                     }
                 })?)?,
             )),
-            FieldType::Meta => Ok(FieldValue::Meta(Meta::from(value.as_table().ok_or_else(
-                || InvalidFieldError::TypeMismatch {
-                    field: key.to_owned(),
-                    field_type: field_type.to_string(),
-                    value: value.to_string(),
-                },
-            )?))),
+            FieldType::Meta | FieldType::TypedMeta(_) => {
+                Ok(FieldValue::Meta(Meta::from(value.as_table().ok_or_else(
+                    || InvalidFieldError::TypeMismatch {
+                        field: key.to_owned(),
+                        field_type: field_type.to_string(),
+                        value: value.to_string(),
+                    },
+                )?)))
+            }
+            FieldType::Location => Ok(FieldValue::Location(Location::from_toml_map(
+                value
+                    .as_table()
+                    .ok_or_else(|| InvalidFieldError::TypeMismatch {
+                        field: key.to_owned(),
+                        field_type: field_type.to_string(),
+                        value: value.to_string(),
+                    })?,
+            )?)),
             FieldType::Alias(a) => Self::from_toml(key, &a.0, value),
+            FieldType::List(t) => Ok(FieldValue::List(
+                value
+                    .as_array()
+                    .ok_or_else(|| InvalidFieldError::TypeMismatch {
+                        field: key.to_owned(),
+                        field_type: field_type.to_string(),
+                        value: value.to_string(),
+                    })?
+                    .iter()
+                    .map(|v| Self::from_toml(key, t, v))
+                    .collect::<Result<Vec<FieldValue>, Box<dyn Error>>>()?,
+            )),
         }
     }
 
@@ -457,65 +552,141 @@ impl FieldValue {
             FieldValue::Objects(o) => format!("{:?}", o),
             FieldValue::File(f) => format!("{:?}", f.to_map(true)),
             FieldValue::Meta(m) => format!("{:?}", serde_json::Value::from(m)),
+            FieldValue::Location(l) => format!("{}, {}", l.lat, l.lng),
+            FieldValue::List(l) => l
+                .iter()
+                .map(|v| v.as_string())
+                .collect::<Vec<String>>()
+                .join(", "),
         }
     }
 }
 
-impl From<&serde_json::Value> for FieldValue {
-    fn from(value: &serde_json::Value) -> Self {
+impl FieldValue {
+    /// Converts a json value, where `null` means the value is unset.
+    pub fn from_json(value: &serde_json::Value) -> Result<Option<Self>, InvalidFieldError> {
         match value {
+            serde_json::Value::Null => Ok(None),
+            value => Self::try_from(value).map(Some),
+        }
+    }
+}
+
+/// Fails for `null`, which has no value - use FieldValue::from_json to treat
+/// it as unset.
+impl TryFrom<&serde_json::Value> for FieldValue {
+    type Error = InvalidFieldError;
+    fn try_from(value: &serde_json::Value) -> Result<Self, Self::Error> {
+        Ok(match value {
             serde_json::Value::String(s) => FieldValue::String(s.to_string()),
             serde_json::Value::Bool(b) => FieldValue::Boolean(*b),
-            serde_json::Value::Number(n) => FieldValue::Number(n.as_f64().unwrap()),
-            serde_json::Value::Null => FieldValue::String("".into()),
+            serde_json::Value::Number(n) => {
+                FieldValue::Number(n.as_f64().ok_or_else(|| InvalidFieldError::TypeMismatch {
+                    field: "(json)".to_string(),
+                    field_type: "number".to_string(),
+                    value: n.to_string(),
+                })?)
+            }
+            serde_json::Value::Null => {
+                return Err(InvalidFieldError::TypeMismatch {
+                    field: "(json)".to_string(),
+                    field_type: "value".to_string(),
+                    value: value.to_string(),
+                })
+            }
             serde_json::Value::Object(o) => {
-                // fill_from_json_map fails when a file field is missing, so we
-                // may incorrectly map to meta if the source data is not
-                // structured correctly, which will likely cause a serialization
-                // error downstream.
-                match File::download().fill_from_json_map(o) {
-                    Ok(file) => FieldValue::File(file),
-                    Err(_) => FieldValue::Meta(Meta::from(o)),
+                // Objects that are not valid files are treated as meta, so
+                // we may incorrectly map to meta if the source data is not
+                // structured correctly, which will likely cause a validation
+                // error downstream. Locations look like any other object, so
+                // they are only created once the field type is known, see
+                // FieldValue::normalized.
+                if let Ok(file) = File::try_from(value) {
+                    FieldValue::File(file)
+                } else {
+                    FieldValue::Meta(Meta::try_from(o)?)
                 }
             }
+            serde_json::Value::Array(v) if v.iter().any(|val| !val.is_object()) => {
+                FieldValue::List(
+                    v.iter()
+                        .filter(|val| !val.is_null())
+                        .map(FieldValue::try_from)
+                        .collect::<Result<Vec<_>, _>>()?,
+                )
+            }
             serde_json::Value::Array(v) => FieldValue::Objects(
                 v.iter()
-                    .map(|val| {
+                    .enumerate()
+                    .map(|(index, val)| {
+                        let obj =
+                            val.as_object()
+                                .ok_or_else(|| InvalidFieldError::InvalidChild {
+                                    key: "(json)".to_string(),
+                                    index,
+                                    child: val.to_string(),
+                                })?;
                         let mut map = BTreeMap::new();
-                        if let Some(obj) = val.as_object() {
-                            for (k, v) in obj.iter() {
-                                map.insert(k.to_string(), FieldValue::from(v));
+                        for (k, v) in obj.iter() {
+                            if let Some(v) = FieldValue::from_json(v)? {
+                                map.insert(k.to_string(), v);
                             }
-                        } else {
-                            panic!("Invalid value {} for child", val);
                         }
-                        map
+                        Ok(map)
                     })
-                    .collect(),
+                    .collect::<Result<Vec<_>, InvalidFieldError>>()?,
             ),
-        }
+        })
     }
 }
 
-fn default_val(f_type: &FieldType) -> FieldValue {
-    match f_type {
-        FieldType::String => FieldValue::String("".to_string()),
+fn date_value(
+    key: &str,
+    field_type: &FieldType,
+    date: DateTime,
+) -> Result<DateTime, InvalidFieldError> {
+    if field_type.accepts_date(&date) {
+        Ok(date)
+    } else {
+        Err(InvalidFieldError::TypeMismatch {
+            field: key.to_owned(),
+            field_type: field_type.to_string(),
+            value: date.to_string(),
+        })
+    }
+}
+
+/// The value of a new field, or None for types that are unset until a value
+/// is provided.
+fn default_val(f_type: &FieldType) -> Option<FieldValue> {
+    Some(match f_type {
+        FieldType::String
+        | FieldType::Color
+        | FieldType::Url
+        | FieldType::Email
+        | FieldType::Slug => FieldValue::String("".to_string()),
         FieldType::Number => FieldValue::Number(0.0),
-        FieldType::Date => FieldValue::Date(DateTime::now()),
-        FieldType::Markdown => FieldValue::Markdown("".to_string()),
+        FieldType::Date => FieldValue::Date(DateTime::today()),
+        FieldType::DateTime => FieldValue::Date(DateTime::now()),
+        FieldType::Time => FieldValue::Date(DateTime::from("00:00:00").unwrap()),
+        FieldType::Markdown | FieldType::TypedMarkdown(_) => FieldValue::Markdown("".to_string()),
         FieldType::Boolean => FieldValue::Boolean(false),
         FieldType::Image => FieldValue::File(File::image()),
         FieldType::Video => FieldValue::File(File::video()),
         FieldType::Audio => FieldValue::File(File::audio()),
         FieldType::Upload => FieldValue::File(File::download()),
-        FieldType::Meta => FieldValue::Meta(Meta::default()),
-        FieldType::Alias(a) => default_val(&a.0),
-    }
+        FieldType::Meta | FieldType::TypedMeta(_) => FieldValue::Meta(Meta::default()),
+        FieldType::Location => return None,
+        FieldType::Alias(a) => return default_val(&a.0),
+        FieldType::List(_) => FieldValue::List(vec![]),
+    })
 }
 pub fn def_to_values(def: &BTreeMap<String, FieldType>) -> BTreeMap<String, FieldValue> {
     let mut vals = ObjectValues::new();
     for (key, f_type) in def {
-        vals.insert(key.to_string(), default_val(f_type));
+        if let Some(val) = default_val(f_type) {
+            vals.insert(key.to_string(), val);
+        }
     }
     vals
 }
@@ -6,10 +6,16 @@ use std::{collections::HashMap, fmt::Display, str::FromStr};
 use thiserror::Error;
 use tracing::warn;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum FileError {
     #[error("Missing field {0}")]
     MissingField(String),
+    #[error("Invalid value {1} for field {0}")]
+    InvalidField(String, String),
+    #[error("Invalid display type {0}")]
+    InvalidDisplayType(String),
+    #[error("Not a file: {0}")]
+    NotAFile(String),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -44,17 +50,24 @@ impl From<DisplayType> for String {
         value.to_str().to_string()
     }
 }
-impl From<&str> for DisplayType {
-    fn from(value: &str) -> Self {
+impl TryFrom<&str> for DisplayType {
+    type Error = FileError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
-            "image" => DisplayType::Image,
-            "audio" => DisplayType::Audio,
-            "video" => DisplayType::Video,
-            "upload" => DisplayType::Download,
-            &_ => todo!(),
+            "image" => Ok(DisplayType::Image),
+            "audio" => Ok(DisplayType::Audio),
+            "video" => Ok(DisplayType::Video),
+            "upload" => Ok(DisplayType::Download),
+            _ => Err(FileError::InvalidDisplayType(value.to_string())),
         }
     }
 }
+impl FromStr for DisplayType {
+    type Err = FileError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
+}
 
 #[cfg(feature = "typescript")]
 mod typedefs {
@@ -151,15 +164,24 @@ impl File {
         }
         Ok(self)
     }
+    /// Null values unset optional fields, and leave other fields unchanged.
     pub fn fill_from_json_map(
         mut self,
         map: &serde_json::Map<String, serde_json::Value>,
     ) -> Result<Self, FileError> {
         for (k, v) in map {
+            if v.is_null() {
+                match &k[..] {
+                    "name" => self.name = None,
+                    "description" => self.description = None,
+                    _ => {}
+                }
+                continue;
+            }
             self.fill_field(k, || {
                 v.as_str()
                     .map(|v| v.to_string())
-                    .ok_or_else(|| FileError::MissingField(k.into()))
+                    .ok_or_else(|| FileError::InvalidField(k.into(), v.to_string()))
             })?;
         }
         Ok(self)
@@ -178,7 +200,9 @@ impl File {
             "description" => self.description = Some(get_val()?),
             "filename" => self.filename = get_val()?,
             "mime" => self.mime = get_val()?,
-            "display_type" => self.display_type = get_val()?,
+            "display_type" => {
+                self.display_type = DisplayType::try_from(&get_val()?[..])?.to_string()
+            }
             _ => {
                 warn!("unknown file field {}", k);
             }
@@ -272,6 +296,21 @@ impl File {
     }
 }
 
+impl TryFrom<&serde_json::Value> for File {
+    type Error = FileError;
+    fn try_from(value: &serde_json::Value) -> Result<Self, Self::Error> {
+        let map = value
+            .as_object()
+            .ok_or_else(|| FileError::NotAFile(value.to_string()))?;
+        for required in ["sha", "filename", "mime", "display_type"] {
+            if !map.get(required).is_some_and(|v| v.is_string()) {
+                return Err(FileError::MissingField(required.to_string()));
+            }
+        }
+        File::download().fill_from_json_map(map)
+    }
+}
+
 #[cfg(feature = "json-schema")]
 impl File {
     pub fn to_json_schema_property(
@@ -379,4 +418,46 @@ pub mod tests {
             assert!(!file.url().contains('.'));
         }
     }
+    #[test]
+    fn display_types() {
+        assert_eq!(
+            DisplayType::try_from("upload").unwrap(),
+            DisplayType::Download
+        );
+        assert_eq!("image".parse::<DisplayType>().unwrap(), DisplayType::Image);
+        assert!(matches!(
+            DisplayType::try_from("hologram"),
+            Err(FileError::InvalidDisplayType(t)) if t == "hologram"
+        ));
+    }
+    #[test]
+    fn files_from_json() {
+        let file = File::try_from(&serde_json::json!({
+            "sha": "fake-sha",
+            "filename": "video.mp4",
+            "mime": "video/mp4",
+            "display_type": "video",
+            "name": null,
+        }))
+        .unwrap();
+        assert_eq!(file.display_type, "video");
+        assert_eq!(file.name, None);
+        assert!(matches!(
+            File::try_from(&serde_json::json!({"sha": "fake-sha"})),
+            Err(FileError::MissingField(_))
+        ));
+        assert!(matches!(
+            File::try_from(&serde_json::json!({
+                "sha": "fake-sha",
+                "filename": "video.mp4",
+                "mime": "video/mp4",
+                "display_type": "hologram",
+            })),
+            Err(FileError::InvalidDisplayType(_))
+        ));
+        assert!(matches!(
+            File::try_from(&serde_json::json!("a string")),
+            Err(FileError::NotAFile(_))
+        ));
+    }
 }
@@ -0,0 +1,138 @@
+use super::InvalidFieldError;
+use crate::taxonomy::slugify;
+
+fn invalid(value: &str, format: &str) -> InvalidFieldError {
+    InvalidFieldError::InvalidFormat(value.to_string(), format.to_string())
+}
+
+/// Normalizes a hex color to lowercase `#rrggbb` or `#rrggbbaa`. Shorthand
+/// `#rgb` and `#rgba` colors are expanded, and the `#` is optional.
+pub fn normalize_color(value: &str) -> Result<String, InvalidFieldError> {
+    let hex = value.trim();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid(value, "color"));
+    }
+    let hex = hex.to_ascii_lowercase();
+    match hex.len() {
+        3 | 4 => Ok(hex.chars().fold("#".to_string(), |mut c, d| {
+            c.push(d);
+            c.push(d);
+            c
+        })),
+        6 | 8 => Ok(format!("#{}", hex)),
+        _ => Err(invalid(value, "color")),
+    }
+}
+
+/// Parses a normalized color into its red, green, blue and alpha components.
+pub fn color_components(value: &str) -> Option<(u8, u8, u8, u8)> {
+    let hex = normalize_color(value).ok()?;
+    let component = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some((
+        component(1)?,
+        component(3)?,
+        component(5)?,
+        component(7).unwrap_or(255),
+    ))
+}
+
+/// Schemes allowed in url fields. Others, like `javascript:` and `data:`,
+/// could run scripts when rendered as links.
+pub const URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Normalizes an absolute URL, e.g. lowercasing the scheme and host and
+/// adding a trailing slash to bare hosts.
+pub fn normalize_url(value: &str) -> Result<String, InvalidFieldError> {
+    let url = url::Url::parse(value.trim()).map_err(|_| invalid(value, "url"))?;
+    if !URL_SCHEMES.contains(&url.scheme()) {
+        return Err(invalid(value, "url"));
+    }
+    Ok(url.to_string())
+}
+
+/// Validates an email address and lowercases its domain. Local parts are case
+/// sensitive, so they are left as-is.
+pub fn normalize_email(value: &str) -> Result<String, InvalidFieldError> {
+    let email = value.trim();
+    let (local, domain) = email
+        .rsplit_once('@')
+        .ok_or_else(|| invalid(value, "email"))?;
+    let valid_domain = domain
+        .split('.')
+        .all(|p| !p.is_empty() && p.chars().all(|c| c.is_alphanumeric() || c == '-'))
+        && domain.contains('.');
+    if local.is_empty()
+        || local.contains('@')
+        || local.chars().any(|c| c.is_whitespace())
+        || !valid_domain
+    {
+        return Err(invalid(value, "email"));
+    }
+    Ok(format!("{}@{}", local, domain.to_lowercase()))
+}
+
+pub fn normalize_slug(value: &str) -> Result<String, InvalidFieldError> {
+    let slug = slugify(value);
+    if slug.is_empty() {
+        return Err(invalid(value, "slug"));
+    }
+    Ok(slug)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors() {
+        assert_eq!(normalize_color("#FF8800").unwrap(), "#ff8800");
+        assert_eq!(normalize_color("f80").unwrap(), "#ff8800");
+        assert_eq!(normalize_color("#F808").unwrap(), "#ff880088");
+        assert!(normalize_color("#ff88").is_ok());
+        assert!(normalize_color("#ff880").is_err());
+        assert!(normalize_color("red").is_err());
+        assert_eq!(color_components("#ff8800"), Some((255, 136, 0, 255)));
+        assert_eq!(color_components("#ff880080"), Some((255, 136, 0, 128)));
+    }
+
+    #[test]
+    fn urls() {
+        assert_eq!(
+            normalize_url("HTTPS://Archival.dev").unwrap(),
+            "https://archival.dev/"
+        );
+        assert_eq!(
+            normalize_url(" https://archival.dev/a?b=c ").unwrap(),
+            "https://archival.dev/a?b=c"
+        );
+        assert!(normalize_url("/relative/path").is_err());
+        assert!(normalize_url("not a url").is_err());
+        assert_eq!(
+            normalize_url("mailto:jesse@archival.dev").unwrap(),
+            "mailto:jesse@archival.dev"
+        );
+        assert!(normalize_url("javascript:alert(1)").is_err());
+        assert!(normalize_url("JavaScript:alert(1)").is_err());
+        assert!(normalize_url("data:text/html,<script>alert(1)</script>").is_err());
+        assert!(normalize_url("ftp://archival.dev").is_err());
+    }
+
+    #[test]
+    fn emails() {
+        assert_eq!(
+            normalize_email("Jesse@Archival.DEV").unwrap(),
+            "Jesse@archival.dev"
+        );
+        assert!(normalize_email("jesse").is_err());
+        assert!(normalize_email("@archival.dev").is_err());
+        assert!(normalize_email("jesse@localhost").is_err());
+        assert!(normalize_email("je sse@archival.dev").is_err());
+    }
+
+    #[test]
+    fn slugs() {
+        assert_eq!(normalize_slug("Hello, World!").unwrap(), "hello-world");
+        assert!(normalize_slug("!!").is_err());
+    }
+}
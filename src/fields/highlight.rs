@@ -0,0 +1,101 @@
+use comrak::{
+    adapters::SyntaxHighlighterAdapter,
+    html::{escape, write_opening_tag},
+};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+use syntect::{
+    highlighting::ThemeSet,
+    html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+
+/// Highlighted code uses prefixed classes, so that theme css doesn't apply to
+/// anything else on the page.
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+/// The class added to the `<pre>` tag of highlighted code blocks, which theme
+/// css uses for the block's foreground and background colors.
+pub const CODE_BLOCK_CLASS: &str = "hl-code";
+
+/// Highlights fenced code blocks with class names rather than inline styles,
+/// so that sites can style them with the css from `theme_css` or their own.
+pub struct ClassHighlighter;
+
+impl SyntaxHighlighterAdapter for ClassHighlighter {
+    fn write_highlighted(
+        &self,
+        output: &mut dyn Write,
+        lang: Option<&str>,
+        code: &str,
+    ) -> io::Result<()> {
+        let syntax = lang
+            .filter(|l| !l.is_empty())
+            .and_then(|l| SYNTAX_SET.find_syntax_by_token(l))
+            .or_else(|| SYNTAX_SET.find_syntax_by_first_line(code))
+            .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+        let mut generator =
+            ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, CLASS_STYLE);
+        for line in LinesWithEndings::from(code) {
+            if generator
+                .parse_html_for_line_which_includes_newline(line)
+                .is_err()
+            {
+                return escape(output, code.as_bytes());
+            }
+        }
+        output.write_all(generator.finalize().as_bytes())
+    }
+
+    fn write_pre_tag(
+        &self,
+        output: &mut dyn Write,
+        mut attributes: HashMap<String, String>,
+    ) -> io::Result<()> {
+        let class = match attributes.remove("class") {
+            Some(class) => format!("{} {}", CODE_BLOCK_CLASS, class),
+            None => CODE_BLOCK_CLASS.to_string(),
+        };
+        attributes.insert("class".to_string(), class);
+        write_opening_tag(output, "pre", attributes)
+    }
+
+    fn write_code_tag(
+        &self,
+        output: &mut dyn Write,
+        attributes: HashMap<String, String>,
+    ) -> io::Result<()> {
+        write_opening_tag(output, "code", attributes)
+    }
+}
+
+/// The css for one of the built-in themes, e.g. `InspiredGitHub` or
+/// `base16-ocean.dark`, matching the classes that ClassHighlighter emits.
+pub fn theme_css(theme: &str) -> Option<String> {
+    let theme = THEME_SET.themes.get(theme)?;
+    css_for_theme_with_class_style(theme, CLASS_STYLE).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes() {
+        let css = theme_css("InspiredGitHub").unwrap();
+        assert!(
+            css.contains(&format!(".{} {{", CODE_BLOCK_CLASS)),
+            "{}",
+            css
+        );
+        assert!(css.contains(".hl-"), "{}", css);
+        assert!(theme_css("not-a-theme").is_none());
+    }
+}
@@ -0,0 +1,340 @@
+use liquid::{ObjectView, ValueView};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Mean radius of the earth, used for distance calculations.
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+const KM_PER_MILE: f64 = 1.609344;
+
+#[derive(Error, Debug, Clone)]
+pub enum LocationError {
+    #[error("Missing location field {0}")]
+    MissingField(String),
+    #[error("Invalid value {1} for location field {0}")]
+    InvalidField(String, String),
+    #[error("Latitude {0} is out of range (-90 to 90)")]
+    InvalidLatitude(f64),
+    #[error("Longitude {0} is out of range (-180 to 180)")]
+    InvalidLongitude(f64),
+    #[error("Not a location: {0}")]
+    NotALocation(String),
+    #[error("Invalid distance unit {0}")]
+    InvalidUnit(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DistanceUnit {
+    Kilometers,
+    Miles,
+}
+
+impl FromStr for DistanceUnit {
+    type Err = LocationError;
+    fn from_str(unit: &str) -> Result<Self, Self::Err> {
+        match unit {
+            "km" | "kilometers" => Ok(Self::Kilometers),
+            "mi" | "miles" => Ok(Self::Miles),
+            _ => Err(LocationError::InvalidUnit(unit.to_string())),
+        }
+    }
+}
+
+/// A point on the earth, with an optional address and place name.
+///
+/// Locations are sorted by latitude and then longitude.
+#[derive(Debug, ObjectView, ValueView, Deserialize, Serialize, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
+pub struct Location {
+    pub lat: f64,
+    pub lng: f64,
+    pub address: Option<String>,
+    pub name: Option<String>,
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Self {
+            lat: 0.0,
+            lng: 0.0,
+            address: None,
+            name: None,
+        }
+    }
+}
+
+impl Location {
+    pub fn new(lat: f64, lng: f64) -> Result<Self, LocationError> {
+        let location = Self {
+            lat,
+            lng,
+            ..Default::default()
+        };
+        location.validate()?;
+        Ok(location)
+    }
+
+    pub fn validate(&self) -> Result<(), LocationError> {
+        if !(-90.0..=90.0).contains(&self.lat) {
+            return Err(LocationError::InvalidLatitude(self.lat));
+        }
+        if !(-180.0..=180.0).contains(&self.lng) {
+            return Err(LocationError::InvalidLongitude(self.lng));
+        }
+        Ok(())
+    }
+
+    /// Parses a `lat, lng` string, e.g. from a csv import.
+    pub fn from_coordinates(value: &str) -> Result<Self, LocationError> {
+        let invalid = || LocationError::NotALocation(value.to_string());
+        let (lat, lng) = value.split_once(',').ok_or_else(invalid)?;
+        Self::new(
+            lat.trim().parse().map_err(|_| invalid())?,
+            lng.trim().parse().map_err(|_| invalid())?,
+        )
+    }
+
+    pub fn from_toml_map(map: &toml::map::Map<String, toml::Value>) -> Result<Self, LocationError> {
+        let coordinate = |key: &str| match map.get(key) {
+            Some(toml::Value::Float(f)) => Ok(*f),
+            Some(toml::Value::Integer(i)) => Ok(*i as f64),
+            Some(v) => Err(LocationError::InvalidField(key.to_string(), v.to_string())),
+            None => Err(LocationError::MissingField(key.to_string())),
+        };
+        let string = |key: &str| match map.get(key) {
+            Some(toml::Value::String(s)) => Ok(Some(s.to_string())),
+            Some(v) => Err(LocationError::InvalidField(key.to_string(), v.to_string())),
+            None => Ok(None),
+        };
+        let location = Self {
+            lat: coordinate("lat")?,
+            lng: coordinate("lng")?,
+            address: string("address")?,
+            name: string("name")?,
+        };
+        location.validate()?;
+        Ok(location)
+    }
+
+    pub fn to_toml(&self) -> toml::map::Map<String, toml::Value> {
+        let mut map = toml::map::Map::new();
+        map.insert("lat".into(), self.lat.into());
+        map.insert("lng".into(), self.lng.into());
+        if let Some(address) = &self.address {
+            map.insert("address".into(), address.to_string().into());
+        }
+        if let Some(name) = &self.name {
+            map.insert("name".into(), name.to_string().into());
+        }
+        map
+    }
+
+    /// Reads a location from a liquid value, e.g. a rendered location field.
+    pub fn from_liquid(value: &dyn ValueView) -> Option<Self> {
+        let object = value.as_object()?;
+        let coordinate = |key: &str| object.get(key)?.as_scalar()?.to_float();
+        let string = |key: &str| {
+            object
+                .get(key)
+                .filter(|v| !v.is_nil())
+                .map(|v| v.to_kstr().to_string())
+        };
+        let location = Self {
+            lat: coordinate("lat")?,
+            lng: coordinate("lng")?,
+            address: string("address"),
+            name: string("name"),
+        };
+        location.validate().ok().map(|_| location)
+    }
+
+    /// The great-circle distance to another location, using the haversine
+    /// formula.
+    pub fn distance_to(&self, other: &Location, unit: DistanceUnit) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lng = (other.lng - self.lng).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lng / 2.0).sin().powi(2);
+        let km = 2.0 * EARTH_RADIUS_KM * a.sqrt().asin();
+        match unit {
+            DistanceUnit::Kilometers => km,
+            DistanceUnit::Miles => km / KM_PER_MILE,
+        }
+    }
+
+    /// A GeoJSON `Feature` for this location. Note that GeoJSON coordinates
+    /// are ordered longitude first.
+    pub fn to_geojson(&self) -> serde_json::Value {
+        let mut properties = serde_json::Map::new();
+        if let Some(name) = &self.name {
+            properties.insert("name".into(), name.to_string().into());
+        }
+        if let Some(address) = &self.address {
+            properties.insert("address".into(), address.to_string().into());
+        }
+        json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Point",
+                "coordinates": [self.lng, self.lat],
+            },
+            "properties": properties,
+        })
+    }
+}
+
+impl TryFrom<&serde_json::Value> for Location {
+    type Error = LocationError;
+    fn try_from(value: &serde_json::Value) -> Result<Self, Self::Error> {
+        let map = value
+            .as_object()
+            .ok_or_else(|| LocationError::NotALocation(value.to_string()))?;
+        if let Some(key) = map
+            .keys()
+            .find(|k| !["lat", "lng", "address", "name"].contains(&k.as_str()))
+        {
+            return Err(LocationError::InvalidField(
+                key.to_string(),
+                map[key].to_string(),
+            ));
+        }
+        let coordinate = |key: &str| match map.get(key) {
+            Some(v) => v
+                .as_f64()
+                .ok_or_else(|| LocationError::InvalidField(key.to_string(), v.to_string())),
+            None => Err(LocationError::MissingField(key.to_string())),
+        };
+        let string = |key: &str| match map.get(key) {
+            Some(serde_json::Value::String(s)) => Ok(Some(s.to_string())),
+            Some(serde_json::Value::Null) | None => Ok(None),
+            Some(v) => Err(LocationError::InvalidField(key.to_string(), v.to_string())),
+        };
+        let location = Self {
+            lat: coordinate("lat")?,
+            lng: coordinate("lng")?,
+            address: string("address")?,
+            name: string("name")?,
+        };
+        location.validate()?;
+        Ok(location)
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl Location {
+    pub fn to_json_schema_property(
+        description: &str,
+        options: &crate::json_schema::ObjectSchemaOptions,
+    ) -> crate::json_schema::ObjectSchema {
+        let mut property = serde_json::Map::new();
+        property.insert("type".to_string(), "object".into());
+        property.insert("description".to_string(), description.into());
+        property.insert(
+            "properties".into(),
+            json!({
+                "lat": {
+                    "type": "number",
+                    "description": "latitude in decimal degrees",
+                    "minimum": -90,
+                    "maximum": 90,
+                },
+                "lng": {
+                    "type": "number",
+                    "description": "longitude in decimal degrees",
+                    "minimum": -180,
+                    "maximum": 180,
+                },
+                "address": {
+                    "type": "string",
+                    "description": "the street address of this location",
+                },
+                "name": {
+                    "type": "string",
+                    "description": "the name of this place",
+                },
+            }),
+        );
+        let mut required_fields = vec!["lat", "lng"];
+        if options.all_fields_required {
+            required_fields.push("address");
+            required_fields.push("name");
+        }
+        property.insert("required".into(), required_fields.into());
+        property.insert("additionalProperties".into(), false.into());
+        property
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validation() {
+        assert!(Location::new(45.0, -122.0).is_ok());
+        assert!(matches!(
+            Location::new(91.0, 0.0),
+            Err(LocationError::InvalidLatitude(_))
+        ));
+        assert!(matches!(
+            Location::new(0.0, -181.0),
+            Err(LocationError::InvalidLongitude(_))
+        ));
+        let location = Location::from_coordinates("45.52, -122.68").unwrap();
+        assert_eq!((location.lat, location.lng), (45.52, -122.68));
+        assert!(Location::from_coordinates("45.52").is_err());
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let map: toml::Table =
+            toml::from_str("lat = 45.52\nlng = -122\nname = \"Crystal Ballroom\"").unwrap();
+        let location = Location::from_toml_map(&map).unwrap();
+        assert_eq!(location.lng, -122.0);
+        assert_eq!(location.name.as_deref(), Some("Crystal Ballroom"));
+        assert_eq!(location.address, None);
+        assert_eq!(
+            Location::from_toml_map(&location.to_toml()).unwrap(),
+            location
+        );
+        let missing: toml::Table = toml::from_str("lat = 45.52").unwrap();
+        assert!(matches!(
+            Location::from_toml_map(&missing),
+            Err(LocationError::MissingField(f)) if f == "lng"
+        ));
+    }
+
+    #[test]
+    fn from_json() {
+        let location = Location::try_from(&json!({"lat": 1, "lng": 2.5, "address": null})).unwrap();
+        assert_eq!((location.lat, location.lng), (1.0, 2.5));
+        assert!(Location::try_from(&json!({"lat": 1, "lng": 2, "sha": "x"})).is_err());
+        assert!(Location::try_from(&json!({"lat": 100, "lng": 2})).is_err());
+    }
+
+    #[test]
+    fn distance() {
+        let portland = Location::new(45.5152, -122.6784).unwrap();
+        let seattle = Location::new(47.6062, -122.3321).unwrap();
+        let km = portland.distance_to(&seattle, DistanceUnit::Kilometers);
+        assert!((km - 233.5).abs() < 1.0, "{}", km);
+        let mi = portland.distance_to(&seattle, DistanceUnit::Miles);
+        assert!((mi - 145.1).abs() < 1.0, "{}", mi);
+        assert_eq!(portland.distance_to(&portland, DistanceUnit::Miles), 0.0);
+    }
+
+    #[test]
+    fn geojson() {
+        let mut location = Location::new(45.5, -122.6).unwrap();
+        location.name = Some("Home".to_string());
+        assert_eq!(
+            location.to_geojson(),
+            json!({
+                "type": "Feature",
+                "geometry": {"type": "Point", "coordinates": [-122.6, 45.5]},
+                "properties": {"name": "Home"}
+            })
+        );
+    }
+}
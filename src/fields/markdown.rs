@@ -0,0 +1,400 @@
+use super::InvalidFieldError;
+use crate::{
+    links::{resolve_wiki_links, Links},
+    output_format::mark_rendered_html,
+    shortcodes,
+};
+use comrak::{
+    adapters::{HeadingAdapter, HeadingMeta},
+    format_html_with_plugins,
+    html::escape,
+    nodes::{AstNode, NodeValue, Sourcepos},
+    parse_document, Anchorizer, Arena, ComrakOptions, ComrakPlugins,
+};
+use liquid::{ObjectView, ValueView};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+};
+
+/// Marks the end of the excerpt of a markdown field, see the `excerpt` filter.
+/// It is kept in rendered html even when raw html is not.
+pub const MORE_MARKER: &str = "<!-- more -->";
+const MORE_PLACEHOLDER: &str = "ARCHIVALMOREMARKER";
+
+/// A heading of a markdown field. Anchors are the ids of the rendered
+/// headings, so they may be linked to as `#{{ entry.anchor }}`.
+#[derive(Debug, ObjectView, ValueView, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    pub level: u8,
+    /// The plain text of the heading, without any markup.
+    pub text: String,
+    pub anchor: String,
+}
+
+/// Renders headings with ids, collecting them into a table of contents.
+struct HeadingAnchors<'a> {
+    prefix: &'a str,
+    anchorizer: RefCell<Anchorizer>,
+    toc: RefCell<Vec<TocEntry>>,
+}
+
+impl HeadingAdapter for HeadingAnchors<'_> {
+    fn enter(
+        &self,
+        output: &mut dyn Write,
+        heading: &HeadingMeta,
+        _sourcepos: Option<Sourcepos>,
+    ) -> io::Result<()> {
+        let anchor = format!(
+            "{}{}",
+            self.prefix,
+            self.anchorizer
+                .borrow_mut()
+                .anchorize(heading.content.to_string())
+        );
+        write!(output, "<h{} id=\"", heading.level)?;
+        escape(output, anchor.as_bytes())?;
+        output.write_all(b"\">")?;
+        self.toc.borrow_mut().push(TocEntry {
+            level: heading.level,
+            text: heading.content.to_string(),
+            anchor,
+        });
+        Ok(())
+    }
+
+    fn exit(&self, output: &mut dyn Write, heading: &HeadingMeta) -> io::Result<()> {
+        writeln!(output, "</h{}>", heading.level)
+    }
+}
+
+/// What markdown may refer to when it is rendered as part of a site.
+#[derive(Default, Clone, Copy)]
+pub struct MarkdownContext<'a> {
+    /// Resolves wiki links to the pages of the objects they point to.
+    pub links: Option<&'a Links>,
+    /// Renders shortcodes using the partials this parser was built with.
+    pub parser: Option<&'a liquid::Parser>,
+    /// The object whose markdown is rendered, which shortcodes get as `object`.
+    pub object: Option<&'a liquid::Object>,
+}
+
+/// Options for rendering markdown fields, which map onto comrak's extension,
+/// parse and render options.
+///
+/// Site-wide options are set in the `[markdown]` section of the manifest, and
+/// may be overridden per field, e.g. a field for user-submitted content that
+/// never renders raw HTML:
+///
+/// ```toml
+/// [markdown]
+/// tables = true
+/// raw_html = true
+/// ```
+///
+/// Unset options fall back to the site options, and then to comrak's
+/// defaults, which is how markdown was rendered before these were added.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
+pub struct MarkdownOptions {
+    pub tables: Option<bool>,
+    pub strikethrough: Option<bool>,
+    /// Links bare URLs and email addresses.
+    pub autolinks: Option<bool>,
+    pub tasklists: Option<bool>,
+    pub footnotes: Option<bool>,
+    pub superscript: Option<bool>,
+    pub description_lists: Option<bool>,
+    /// Escapes the HTML tags that GitHub disallows, e.g. `<script>`, even when
+    /// raw HTML is rendered.
+    pub tagfilter: Option<bool>,
+    /// A prefix for the ids of headings, which are otherwise the GitHub-style
+    /// slug of their text, e.g. `getting-started`.
+    pub header_ids: Option<String>,
+    /// Converts quotes, dashes and ellipses to their typographic forms.
+    pub smart_punctuation: Option<bool>,
+    /// Renders soft line breaks as `<br>`.
+    pub hard_breaks: Option<bool>,
+    /// Renders raw HTML and potentially dangerous links instead of replacing
+    /// them with comments. Never enable this for user-submitted content.
+    pub raw_html: Option<bool>,
+    /// Highlights fenced code blocks using class names, when archival is
+    /// built with the `syntax-highlighting` feature.
+    pub syntax_highlighting: Option<bool>,
+    /// A built-in theme, e.g. `InspiredGitHub`, whose css is written to
+    /// `highlight.css` in the build dir. Only read from the manifest.
+    pub highlight_theme: Option<String>,
+}
+
+impl MarkdownOptions {
+    pub fn from_toml(table: &toml::Table) -> Result<Self, InvalidFieldError> {
+        toml::Value::Table(table.clone())
+            .try_into()
+            .map_err(|e: toml::de::Error| InvalidFieldError::InvalidMarkdownOptions(e.to_string()))
+    }
+
+    pub fn to_toml(&self) -> toml::Table {
+        match toml::Value::try_from(self) {
+            Ok(toml::Value::Table(t)) => t,
+            _ => toml::Table::new(),
+        }
+    }
+
+    /// These options, with any options set in `overrides` replacing them.
+    pub fn merge(&self, overrides: &MarkdownOptions) -> MarkdownOptions {
+        MarkdownOptions {
+            tables: overrides.tables.or(self.tables),
+            strikethrough: overrides.strikethrough.or(self.strikethrough),
+            autolinks: overrides.autolinks.or(self.autolinks),
+            tasklists: overrides.tasklists.or(self.tasklists),
+            footnotes: overrides.footnotes.or(self.footnotes),
+            superscript: overrides.superscript.or(self.superscript),
+            description_lists: overrides.description_lists.or(self.description_lists),
+            tagfilter: overrides.tagfilter.or(self.tagfilter),
+            header_ids: overrides
+                .header_ids
+                .clone()
+                .or_else(|| self.header_ids.clone()),
+            smart_punctuation: overrides.smart_punctuation.or(self.smart_punctuation),
+            hard_breaks: overrides.hard_breaks.or(self.hard_breaks),
+            raw_html: overrides.raw_html.or(self.raw_html),
+            syntax_highlighting: overrides.syntax_highlighting.or(self.syntax_highlighting),
+            highlight_theme: self.highlight_theme.clone(),
+        }
+    }
+
+    /// These options with raw HTML disabled, for rendering values whose field
+    /// options are not known. A field may have disabled raw HTML even when
+    /// the site allows it, so the safe option wins.
+    pub fn safe(&self) -> MarkdownOptions {
+        MarkdownOptions {
+            raw_html: Some(false),
+            ..self.clone()
+        }
+    }
+
+    pub fn to_comrak(&self) -> ComrakOptions {
+        let mut options = ComrakOptions::default();
+        let extension = &mut options.extension;
+        extension.table = self.tables.unwrap_or(extension.table);
+        extension.strikethrough = self.strikethrough.unwrap_or(extension.strikethrough);
+        extension.autolink = self.autolinks.unwrap_or(extension.autolink);
+        extension.tasklist = self.tasklists.unwrap_or(extension.tasklist);
+        extension.footnotes = self.footnotes.unwrap_or(extension.footnotes);
+        extension.superscript = self.superscript.unwrap_or(extension.superscript);
+        extension.description_lists = self
+            .description_lists
+            .unwrap_or(extension.description_lists);
+        extension.tagfilter = self.tagfilter.unwrap_or(extension.tagfilter);
+        options.parse.smart = self.smart_punctuation.unwrap_or(options.parse.smart);
+        options.render.hardbreaks = self.hard_breaks.unwrap_or(options.render.hardbreaks);
+        options.render.unsafe_ = self.raw_html.unwrap_or(options.render.unsafe_);
+        options
+    }
+
+    pub fn render(&self, markdown: &str) -> String {
+        self.render_with_toc(markdown, &MarkdownContext::default())
+            .0
+    }
+
+    /// Renders markdown to html, along with the table of contents of its
+    /// headings. See MarkdownContext for what is rendered in context.
+    pub fn render_with_toc(
+        &self,
+        markdown: &str,
+        context: &MarkdownContext,
+    ) -> (String, Vec<TocEntry>) {
+        let (markdown, shortcodes) = match context.parser {
+            Some(_) => shortcodes::extract(markdown),
+            None => (markdown.to_string(), vec![]),
+        };
+        let headings = HeadingAnchors {
+            prefix: self.header_ids.as_deref().unwrap_or_default(),
+            anchorizer: RefCell::new(Anchorizer::new()),
+            toc: RefCell::new(vec![]),
+        };
+        let mut plugins = ComrakPlugins::default();
+        plugins.render.heading_adapter = Some(&headings);
+        #[cfg(feature = "syntax-highlighting")]
+        if self.syntax_highlighting.unwrap_or(false) {
+            plugins.render.codefence_syntax_highlighter = Some(&super::highlight::ClassHighlighter);
+        }
+        let options = self.to_comrak();
+        let arena = Arena::new();
+        let root = parse_document(&arena, &markdown, &options);
+        if let Some(links) = context.links {
+            resolve_wiki_links(&arena, root, links);
+        }
+        let has_more = mark_more(root);
+        let mut html = vec![];
+        format_html_with_plugins(root, &options, &mut html, &plugins)
+            .expect("writing to a vec cannot fail");
+        let mut html = String::from_utf8(html).expect("comrak renders valid utf-8");
+        if has_more {
+            html = html.replacen(MORE_PLACEHOLDER, MORE_MARKER, 1);
+        }
+        if let Some(parser) = context.parser {
+            html = shortcodes::insert(html, &shortcodes, |shortcode| {
+                let content = shortcode
+                    .inner
+                    .as_ref()
+                    .map(|inner| self.render_with_toc(inner, context).0);
+                shortcode.render(parser, content, context.object)
+            });
+        }
+        mark_rendered_html(&html);
+        (html, headings.toc.into_inner())
+    }
+}
+
+/// Replaces the first `<!-- more -->` in a document with a placeholder, which
+/// renders regardless of the raw html option.
+fn mark_more<'a>(root: &'a AstNode<'a>) -> bool {
+    for node in root.descendants() {
+        let mut data = node.data.borrow_mut();
+        let is_more = match &data.value {
+            NodeValue::HtmlBlock(block) => block.literal.trim() == MORE_MARKER,
+            NodeValue::HtmlInline(html) => html.trim() == MORE_MARKER,
+            _ => false,
+        };
+        if is_more {
+            data.value = NodeValue::Text(MORE_PLACEHOLDER.to_string());
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use comrak::markdown_to_html;
+    use std::error::Error;
+
+    #[test]
+    fn defaults_match_comrak() {
+        let markdown = "~~gone~~ <b>bold</b> https://archival.dev";
+        assert_eq!(
+            MarkdownOptions::default().render(markdown),
+            markdown_to_html(markdown, &ComrakOptions::default())
+        );
+    }
+
+    #[test]
+    fn extensions() -> Result<(), Box<dyn Error>> {
+        let options = MarkdownOptions::from_toml(&toml::from_str(
+            "tables = true\nstrikethrough = true\nautolinks = true",
+        )?)?;
+        let html = options.render("| a |\n|---|\n| b |\n\n~~gone~~ https://archival.dev");
+        assert!(html.contains("<table>"), "{}", html);
+        assert!(html.contains("<del>gone</del>"), "{}", html);
+        assert!(
+            html.contains("<a href=\"https://archival.dev\">"),
+            "{}",
+            html
+        );
+        assert!(MarkdownOptions::from_toml(&toml::from_str("tablez = true")?).is_err());
+        assert_eq!(MarkdownOptions::from_toml(&options.to_toml())?, options);
+        Ok(())
+    }
+
+    #[test]
+    fn overrides() {
+        let site = MarkdownOptions {
+            raw_html: Some(true),
+            tables: Some(true),
+            ..Default::default()
+        };
+        assert!(site.render("<b>hi</b>").contains("<b>hi</b>"));
+        let safe = site.merge(&MarkdownOptions {
+            raw_html: Some(false),
+            ..Default::default()
+        });
+        assert_eq!(safe.tables, Some(true));
+        let html = safe.render("<b>hi</b>");
+        assert!(!html.contains("<b>"), "{}", html);
+        assert_eq!(site.safe(), safe);
+    }
+
+    #[test]
+    fn headings() {
+        let (html, toc) = MarkdownOptions::default().render_with_toc(
+            "# Getting *Started*\n\ntext\n\n## Setup\n\n## Setup\n",
+            &MarkdownContext::default(),
+        );
+        assert!(
+            html.starts_with("<h1 id=\"getting-started\">Getting <em>Started</em></h1>"),
+            "{}",
+            html
+        );
+        assert!(html.contains("<h2 id=\"setup-1\">Setup</h2>"), "{}", html);
+        assert_eq!(
+            toc,
+            vec![
+                TocEntry {
+                    level: 1,
+                    text: "Getting Started".to_string(),
+                    anchor: "getting-started".to_string()
+                },
+                TocEntry {
+                    level: 2,
+                    text: "Setup".to_string(),
+                    anchor: "setup".to_string()
+                },
+                TocEntry {
+                    level: 2,
+                    text: "Setup".to_string(),
+                    anchor: "setup-1".to_string()
+                },
+            ]
+        );
+        let prefixed = MarkdownOptions {
+            header_ids: Some("h-".to_string()),
+            ..Default::default()
+        };
+        let (html, toc) = prefixed.render_with_toc("## Setup", &MarkdownContext::default());
+        assert!(html.contains("<h2 id=\"h-setup\">"), "{}", html);
+        assert_eq!(toc[0].anchor, "h-setup");
+    }
+
+    #[test]
+    fn syntax_highlighting() {
+        let markdown = "```rust\nfn main() {}\n```";
+        let options = MarkdownOptions {
+            syntax_highlighting: Some(true),
+            ..Default::default()
+        };
+        let html = options.render(markdown);
+        if cfg!(feature = "syntax-highlighting") {
+            assert!(html.starts_with("<pre class=\"hl-code\">"), "{}", html);
+            assert!(html.contains("<span class=\"hl-"), "{}", html);
+            assert!(html.contains("main"), "{}", html);
+        } else {
+            assert_eq!(html, MarkdownOptions::default().render(markdown));
+        }
+        let plain = MarkdownOptions::default().render(markdown);
+        assert!(
+            plain.contains("<code class=\"language-rust\">"),
+            "{}",
+            plain
+        );
+    }
+
+    #[test]
+    fn more_marker() {
+        let options = MarkdownOptions::default();
+        let html = options.render("Intro\n\n<!-- more -->\n\nRest <!-- more -->");
+        assert_eq!(
+            html,
+            "<p>Intro</p>\n<!-- more -->\n<p>Rest <!-- raw HTML omitted --></p>\n"
+        );
+        let raw = MarkdownOptions {
+            raw_html: Some(true),
+            ..Default::default()
+        };
+        assert!(raw.render("a\n\n<!-- more -->\n").contains("<!-- more -->"));
+    }
+}
@@ -1,6 +1,7 @@
 use liquid_core::model;
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, fmt::Display};
+use thiserror::Error;
 
 use super::DateTime;
 
@@ -25,6 +26,12 @@ mod typedefs {
     }
 }
 
+#[derive(Error, Debug, Clone)]
+pub enum MetaError {
+    #[error("invalid number {0}")]
+    InvalidNumber(String),
+}
+
 #[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
 #[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
 pub struct Meta(pub BTreeMap<String, MetaValue>);
@@ -36,10 +43,13 @@ impl Display for Meta {
 }
 
 impl Meta {
+    /// Null values are unset, so they are omitted from the output.
     pub fn to_toml(&self) -> toml::map::Map<std::string::String, toml::Value> {
         let mut m = toml::map::Map::new();
         for (k, v) in &self.0 {
-            m.insert(k.to_string(), v.to_toml());
+            if let Some(v) = v.to_toml() {
+                m.insert(k.to_string(), v);
+            }
         }
         m
     }
@@ -48,28 +58,35 @@ impl Meta {
     }
 }
 
-impl From<&serde_json::Value> for MetaValue {
-    fn from(value: &serde_json::Value) -> Self {
-        match value {
+impl TryFrom<&serde_json::Value> for MetaValue {
+    type Error = MetaError;
+    fn try_from(value: &serde_json::Value) -> Result<Self, Self::Error> {
+        Ok(match value {
             serde_json::Value::Bool(b) => MetaValue::Boolean(*b),
-            serde_json::Value::Number(n) => MetaValue::Number(n.as_f64().unwrap()),
+            serde_json::Value::Number(n) => MetaValue::Number(
+                n.as_f64()
+                    .ok_or_else(|| MetaError::InvalidNumber(n.to_string()))?,
+            ),
             serde_json::Value::String(s) => MetaValue::String(s.into()),
-            serde_json::Value::Array(a) => MetaValue::Array(a.iter().map(|i| i.into()).collect()),
-            serde_json::Value::Object(o) => MetaValue::Map(o.into()),
-            serde_json::Value::Null => {
-                todo!("null values unsupported when converting from json to MetaValue")
-            }
-        }
+            serde_json::Value::Array(a) => MetaValue::Array(
+                a.iter()
+                    .map(MetaValue::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            serde_json::Value::Object(o) => MetaValue::Map(o.try_into()?),
+            serde_json::Value::Null => MetaValue::Null,
+        })
     }
 }
 
-impl From<&serde_json::Map<String, serde_json::Value>> for Meta {
-    fn from(value: &serde_json::Map<String, serde_json::Value>) -> Self {
+impl TryFrom<&serde_json::Map<String, serde_json::Value>> for Meta {
+    type Error = MetaError;
+    fn try_from(value: &serde_json::Map<String, serde_json::Value>) -> Result<Self, Self::Error> {
         let mut meta = Self::default();
         for (k, v) in value {
-            meta.0.insert(k.to_string(), v.into());
+            meta.0.insert(k.to_string(), v.try_into()?);
         }
-        meta
+        Ok(meta)
     }
 }
 
@@ -86,6 +103,7 @@ impl From<&MetaValue> for model::Value {
                     .collect::<Vec<model::Value>>(),
             ),
             MetaValue::Map(m) => model::Value::Object(model::Object::from(m)),
+            MetaValue::Null => model::Value::Nil,
         }
     }
 }
@@ -122,39 +140,23 @@ pub enum MetaValue {
     ),
     // Workaround for circular type: https://github.com/dbeckwith/rust-typescript-type-def/issues/18#issuecomment-2078469020
     Map(#[cfg_attr(feature = "typescript", type_def(type_of = "typedefs::MetaTypeDef"))] Meta),
+    /// An unset value. Since TOML has no null, these are omitted when
+    /// written, and read back as a missing key.
+    Null,
 }
 
 impl MetaValue {
-    pub fn to_toml(&self) -> toml::Value {
-        match self {
+    /// Returns None for null values, which have no TOML representation.
+    pub fn to_toml(&self) -> Option<toml::Value> {
+        Some(match self {
             Self::String(s) => toml::Value::String(s.to_string()),
             Self::Number(v) => toml::Value::Float(*v),
             Self::Boolean(v) => toml::Value::Boolean(*v),
-            Self::DateTime(d) => {
-                let dt = *d.as_liquid_datetime();
-                let date = dt.date();
-                let time = dt.time();
-                let offset = dt.offset();
-                toml::Value::Datetime(toml_datetime::Datetime {
-                    date: Some(toml_datetime::Date {
-                        year: date.year() as u16,
-                        month: date.month() as u8,
-                        day: date.day(),
-                    }),
-                    time: Some(toml_datetime::Time {
-                        hour: time.hour(),
-                        minute: time.minute(),
-                        second: time.second(),
-                        nanosecond: time.nanosecond(),
-                    }),
-                    offset: Some(toml_datetime::Offset::Custom {
-                        minutes: offset.whole_minutes(),
-                    }),
-                })
-            }
-            Self::Array(v) => toml::Value::Array(v.iter().map(|n| n.to_toml()).collect()),
+            Self::DateTime(d) => toml::Value::Datetime(d.to_toml()),
+            Self::Array(v) => toml::Value::Array(v.iter().filter_map(|n| n.to_toml()).collect()),
             Self::Map(m) => toml::Value::Table(m.to_toml()),
-        }
+            Self::Null => return None,
+        })
     }
 }
 
@@ -169,6 +171,7 @@ impl From<&MetaValue> for serde_json::Value {
             MetaValue::DateTime(d) => d.to_string().into(),
             MetaValue::Array(v) => v.iter().collect::<serde_json::Value>(),
             MetaValue::Map(m) => m.into(),
+            MetaValue::Null => serde_json::Value::Null,
         }
     }
 }
@@ -389,6 +392,7 @@ impl model::ValueView for MetaValue {
             MetaValue::Number(v) => v.render(),
             MetaValue::Boolean(v) => v.render(),
             MetaValue::DateTime(d) => d.borrowed_as_datetime().render(),
+            MetaValue::Null => "".render(),
             _ => todo!("MetaValue render not implemented for non-scalar values"),
         }
     }
@@ -405,9 +409,14 @@ impl model::ValueView for MetaValue {
             MetaValue::DateTime(_) => "meta:datetime",
             MetaValue::Array(_) => "meta:array",
             MetaValue::Map(_) => "meta:map",
+            MetaValue::Null => "meta:null",
         }
     }
 
+    fn is_nil(&self) -> bool {
+        matches!(self, MetaValue::Null)
+    }
+
     fn query_state(&self, _state: model::State) -> bool {
         false
     }
@@ -420,3 +429,74 @@ impl model::ValueView for MetaValue {
         self.into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FieldValue;
+    use model::ValueView;
+    use serde_json::json;
+
+    #[test]
+    fn null_values_are_omitted_from_toml() -> Result<(), Box<dyn std::error::Error>> {
+        let meta = Meta::try_from(
+            json!({"title": "A", "subtitle": null, "tags": ["a", null], "nested": {"b": null}})
+                .as_object()
+                .unwrap(),
+        )?;
+        assert_eq!(meta.0["subtitle"], MetaValue::Null);
+        assert!(meta.0["subtitle"].is_nil());
+        assert_eq!(serde_json::Value::from(&meta)["subtitle"], json!(null));
+        let toml = meta.to_toml();
+        assert!(!toml.contains_key("subtitle"));
+        assert_eq!(toml["tags"].as_array().unwrap().len(), 1);
+        assert!(toml["nested"].as_table().unwrap().is_empty());
+        let reparsed = Meta::from(&toml);
+        assert_eq!(reparsed.get_value("subtitle"), None);
+        assert_eq!(reparsed.0["title"], MetaValue::String("A".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn field_values_from_json() -> Result<(), Box<dyn std::error::Error>> {
+        assert!(matches!(
+            FieldValue::try_from(&json!({"rating": null}))?,
+            FieldValue::Meta(m) if m.0["rating"] == MetaValue::Null
+        ));
+        assert!(matches!(
+            FieldValue::try_from(&json!({
+                "sha": "fake-sha",
+                "filename": "image.png",
+                "mime": "image/png",
+                "display_type": "image"
+            }))?,
+            FieldValue::File(_)
+        ));
+        assert_eq!(
+            FieldValue::try_from(&json!(["a", "b"]))?,
+            FieldValue::List(vec![
+                FieldValue::String("a".to_string()),
+                FieldValue::String("b".to_string())
+            ])
+        );
+        assert!(matches!(
+            FieldValue::try_from(&json!([{"name": "a"}]))?,
+            FieldValue::Objects(o) if o.len() == 1
+        ));
+        // Null values are unset
+        assert_eq!(FieldValue::from_json(&json!(null))?, None);
+        assert!(FieldValue::try_from(&json!(null)).is_err());
+        assert_eq!(
+            FieldValue::try_from(&json!([{"name": "a", "title": null}]))?,
+            FieldValue::Objects(vec![BTreeMap::from([(
+                "name".to_string(),
+                FieldValue::String("a".to_string())
+            )])])
+        );
+        assert_eq!(
+            FieldValue::try_from(&json!(["a", null]))?,
+            FieldValue::List(vec![FieldValue::String("a".to_string())])
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+use super::{meta::Meta, InvalidFieldError, MetaValue};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[cfg(feature = "typescript")]
+mod typedefs {
+    use typescript_type_def::{
+        type_expr::{Ident, NativeTypeInfo, TypeExpr, TypeInfo},
+        TypeDef,
+    };
+    pub struct MetaTypeArrTypeDef;
+    impl TypeDef for MetaTypeArrTypeDef {
+        const INFO: TypeInfo = TypeInfo::Native(NativeTypeInfo {
+            r#ref: TypeExpr::ident(Ident("MetaType")),
+        });
+    }
+
+    pub struct MetaSchemaTypeDef;
+    impl TypeDef for MetaSchemaTypeDef {
+        const INFO: TypeInfo = TypeInfo::Native(NativeTypeInfo {
+            r#ref: TypeExpr::ident(Ident("Record<string, MetaType>")),
+        });
+    }
+}
+
+/// The structure of a meta field. Keys may be omitted from a value, but keys
+/// that are present must match their type, and keys that are not in the
+/// schema are not allowed.
+///
+/// Schemas are written as tables of types, where a table is a nested map, a
+/// `[]` suffix or a single-item array is an array, e.g.
+///
+/// ```toml
+/// title = "string"
+/// rating = "number"
+/// tags = "string[]"
+/// author = { name = "string", url = "string" }
+/// links = [{ url = "string", label = "string" }]
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
+pub struct MetaSchema(pub BTreeMap<String, MetaType>);
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
+pub enum MetaType {
+    String,
+    Number,
+    Boolean,
+    DateTime,
+    Array(
+        #[cfg_attr(
+            feature = "typescript",
+            type_def(type_of = "typedefs::MetaTypeArrTypeDef")
+        )]
+        Box<MetaType>,
+    ),
+    Map(
+        #[cfg_attr(
+            feature = "typescript",
+            type_def(type_of = "typedefs::MetaSchemaTypeDef")
+        )]
+        MetaSchema,
+    ),
+}
+
+impl MetaSchema {
+    pub fn from_toml(table: &toml::Table) -> Result<Self, InvalidFieldError> {
+        let mut schema = BTreeMap::new();
+        for (key, value) in table {
+            schema.insert(key.to_string(), MetaType::from_toml(value)?);
+        }
+        Ok(Self(schema))
+    }
+
+    pub fn to_toml(&self) -> toml::Table {
+        self.0
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_toml()))
+            .collect()
+    }
+
+    pub fn validate(&self, meta: &Meta) -> Result<(), InvalidFieldError> {
+        self.validate_at("", meta)
+    }
+
+    fn validate_at(&self, path: &str, meta: &Meta) -> Result<(), InvalidFieldError> {
+        for (key, value) in &meta.0 {
+            let key_path = if path.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            let meta_type = self
+                .0
+                .get(key)
+                .ok_or_else(|| InvalidFieldError::UnexpectedMetaKey(key_path.to_string()))?;
+            meta_type.validate_at(&key_path, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl MetaType {
+    pub fn from_toml(value: &toml::Value) -> Result<Self, InvalidFieldError> {
+        let invalid = || InvalidFieldError::InvalidMetaSchema(value.to_string());
+        match value {
+            toml::Value::String(s) => Self::from_str(s).ok_or_else(invalid),
+            toml::Value::Table(t) => Ok(Self::Map(MetaSchema::from_toml(t)?)),
+            toml::Value::Array(a) if a.len() == 1 => {
+                Ok(Self::Array(Box::new(Self::from_toml(&a[0])?)))
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    fn from_str(string: &str) -> Option<Self> {
+        match string {
+            "string" => Some(Self::String),
+            "number" => Some(Self::Number),
+            "boolean" => Some(Self::Boolean),
+            "date" | "datetime" => Some(Self::DateTime),
+            t if t.ends_with("[]") => {
+                Self::from_str(&t[..t.len() - 2]).map(|t| Self::Array(Box::new(t)))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_toml(&self) -> toml::Value {
+        match self {
+            Self::String => "string".into(),
+            Self::Number => "number".into(),
+            Self::Boolean => "boolean".into(),
+            Self::DateTime => "datetime".into(),
+            Self::Array(t) => toml::Value::Array(vec![t.to_toml()]),
+            Self::Map(m) => toml::Value::Table(m.to_toml()),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::DateTime => "datetime",
+            Self::Array(_) => "array",
+            Self::Map(_) => "map",
+        }
+    }
+
+    fn validate_at(&self, path: &str, value: &MetaValue) -> Result<(), InvalidFieldError> {
+        match (self, value) {
+            (Self::String, MetaValue::String(_))
+            | (Self::Number, MetaValue::Number(_))
+            | (Self::Boolean, MetaValue::Boolean(_))
+            | (Self::DateTime, MetaValue::DateTime(_)) => Ok(()),
+            // Null values are unset, which is allowed for any key
+            (_, MetaValue::Null) => Ok(()),
+            (Self::Array(t), MetaValue::Array(items)) => {
+                for (index, item) in items.iter().enumerate() {
+                    t.validate_at(&format!("{}.{}", path, index), item)?;
+                }
+                Ok(())
+            }
+            (Self::Map(schema), MetaValue::Map(meta)) => schema.validate_at(path, meta),
+            _ => Err(InvalidFieldError::TypeMismatch {
+                field: path.to_string(),
+                field_type: self.name().to_string(),
+                value: format!("{:?}", value),
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl MetaSchema {
+    pub fn to_json_schema(
+        &self,
+        options: &crate::json_schema::ObjectSchemaOptions,
+    ) -> crate::json_schema::ObjectSchema {
+        let mut schema = serde_json::Map::new();
+        schema.insert("type".into(), "object".into());
+        schema.insert("additionalProperties".into(), false.into());
+        let properties: serde_json::Map<String, serde_json::Value> = self
+            .0
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_json_schema(options).into()))
+            .collect();
+        let required: Vec<String> = if options.all_fields_required {
+            self.0.keys().map(|k| k.to_string()).collect()
+        } else {
+            vec![]
+        };
+        schema.insert("properties".into(), properties.into());
+        schema.insert("required".into(), required.into());
+        schema
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl MetaType {
+    fn to_json_schema(
+        &self,
+        options: &crate::json_schema::ObjectSchemaOptions,
+    ) -> crate::json_schema::ObjectSchema {
+        let mut schema = serde_json::Map::new();
+        match self {
+            Self::String => {
+                schema.insert("type".into(), "string".into());
+            }
+            Self::Number => {
+                schema.insert("type".into(), "number".into());
+            }
+            Self::Boolean => {
+                schema.insert("type".into(), "boolean".into());
+            }
+            Self::DateTime => {
+                schema.insert("type".into(), "string".into());
+                schema.insert("format".into(), "date-time".into());
+            }
+            Self::Array(t) => {
+                schema.insert("type".into(), "array".into());
+                schema.insert("items".into(), t.to_json_schema(options).into());
+            }
+            Self::Map(m) => return m.to_json_schema(options),
+        }
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    fn schema() -> MetaSchema {
+        MetaSchema::from_toml(
+            &toml::from_str(
+                "title = \"string\"
+                tags = \"string[]\"
+                author = { name = \"string\", age = \"number\" }
+                links = [{ url = \"string\" }]",
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn meta(toml: &str) -> Meta {
+        Meta::from(&toml::from_str::<toml::Table>(toml).unwrap())
+    }
+
+    #[test]
+    fn parsing() -> Result<(), Box<dyn Error>> {
+        let schema = schema();
+        assert_eq!(schema.0["title"], MetaType::String);
+        assert_eq!(
+            schema.0["tags"],
+            MetaType::Array(Box::new(MetaType::String))
+        );
+        let MetaType::Array(links) = &schema.0["links"] else {
+            panic!("links is not an array");
+        };
+        assert!(matches!(**links, MetaType::Map(_)));
+        assert_eq!(MetaSchema::from_toml(&schema.to_toml())?, schema);
+        assert!(MetaSchema::from_toml(&toml::from_str("a = \"image\"")?).is_err());
+        assert!(MetaSchema::from_toml(&toml::from_str("a = 1")?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validation() {
+        let schema = schema();
+        assert!(schema
+            .validate(&meta(
+                "title = \"A\"
+                tags = [\"a\", \"b\"]
+                author = { name = \"B\" }
+                links = [{ url = \"https://archival.dev\" }]"
+            ))
+            .is_ok());
+        assert!(schema.validate(&meta("")).is_ok());
+        assert!(matches!(
+            schema.validate(&meta("title = 1")),
+            Err(InvalidFieldError::TypeMismatch { field, .. }) if field == "title"
+        ));
+        assert!(matches!(
+            schema.validate(&meta("links = [{ url = false }]")),
+            Err(InvalidFieldError::TypeMismatch { field, .. }) if field == "links.0.url"
+        ));
+        assert!(matches!(
+            schema.validate(&meta("author = { email = \"x\" }")),
+            Err(InvalidFieldError::UnexpectedMetaKey(key)) if key == "author.email"
+        ));
+    }
+}
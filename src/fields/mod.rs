@@ -2,14 +2,26 @@ mod date_time;
 pub(crate) mod field_type;
 pub(crate) mod field_value;
 mod file;
+pub(crate) mod formats;
+#[cfg(feature = "syntax-highlighting")]
+mod highlight;
+mod location;
+mod markdown;
 pub(crate) mod meta;
-pub use date_time::DateTime;
+mod meta_schema;
+pub use date_time::{parse_timezone, DateKind, DateTime};
 pub use field_type::{FieldType, InvalidFieldError};
 pub use field_value::{FieldValue, ObjectValues};
 pub use file::File;
+#[cfg(feature = "syntax-highlighting")]
+pub use highlight::theme_css;
+pub use location::{DistanceUnit, Location, LocationError};
+pub use markdown::{MarkdownContext, MarkdownOptions, TocEntry, MORE_MARKER};
 pub use meta::MetaValue;
+pub use meta_schema::{MetaSchema, MetaType};
 use once_cell::sync::Lazy;
 use std::sync::{Mutex, MutexGuard};
+use time::UtcOffset;
 
 use crate::constants::UPLOADS_URL;
 
@@ -18,6 +30,10 @@ static CONFIG: Lazy<Mutex<FieldConfig>> = Lazy::new(|| Mutex::new(FieldConfig::d
 #[derive(Debug, Clone)]
 pub struct FieldConfig {
     pub uploads_url: String,
+    /// The offset that dates and times without an explicit offset are in.
+    pub timezone: UtcOffset,
+    /// The site-wide markdown options, which fields may override.
+    pub markdown: MarkdownOptions,
 }
 
 impl Default for FieldConfig {
@@ -30,8 +46,18 @@ impl FieldConfig {
     pub fn new(uploads_url: Option<String>) -> Self {
         Self {
             uploads_url: uploads_url.unwrap_or_else(|| UPLOADS_URL.to_owned()),
+            timezone: UtcOffset::UTC,
+            markdown: MarkdownOptions::default(),
         }
     }
+    pub fn with_timezone(mut self, timezone: UtcOffset) -> Self {
+        self.timezone = timezone;
+        self
+    }
+    pub fn with_markdown(mut self, markdown: MarkdownOptions) -> Self {
+        self.markdown = markdown;
+        self
+    }
     pub(crate) fn get<'a>() -> MutexGuard<'a, FieldConfig> {
         CONFIG.lock().expect("Invalid FieldConfig::get access")
     }
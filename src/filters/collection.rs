@@ -0,0 +1,230 @@
+use liquid_core::model::{ScalarCow, ValueCow};
+use liquid_core::Expression;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{
+    Display_filter, Filter, FilterParameters, FilterReflection, FromFilterParameters, ParseFilter,
+};
+use liquid_core::{Error, Object, Value, ValueView};
+use std::cmp::Ordering;
+
+fn array<'a>(input: &'a dyn ValueView, name: &str) -> Result<Vec<&'a dyn ValueView>> {
+    if input.is_nil() {
+        return Ok(vec![]);
+    }
+    let array = input.as_array().ok_or_else(|| {
+        Error::with_msg(format!("{} expects an array", name))
+            .context("input", format!("{}", input.source()))
+    })?;
+    Ok(array.values().collect())
+}
+
+fn property<'a>(item: &'a dyn ValueView, property: &str) -> Option<ValueCow<'a>> {
+    item.as_object()
+        .and_then(|o| o.get(property))
+        .map(ValueCow::Borrowed)
+}
+
+fn compare_scalars(a: &ScalarCow, b: &ScalarCow) -> Ordering {
+    let is_date = |s: &ScalarCow| matches!(s.type_name(), "date time" | "date");
+    if is_date(a) || is_date(b) {
+        if let (Some(a), Some(b)) = (a.to_date_time(), b.to_date_time()) {
+            return a.cmp(&b);
+        }
+    }
+    let is_number = |s: &ScalarCow| matches!(s.type_name(), "whole number" | "fractional number");
+    if is_number(a) && is_number(b) {
+        if let (Some(a), Some(b)) = (a.to_float(), b.to_float()) {
+            return a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+        }
+    }
+    a.to_kstr().as_str().cmp(b.to_kstr().as_str())
+}
+
+/// Orders values by their type, so dates sort by time rather than by their
+/// formatted strings. Missing and nil values sort last.
+fn compare(a: Option<&dyn ValueView>, b: Option<&dyn ValueView>) -> Ordering {
+    let a = a.filter(|v| !v.is_nil()).and_then(|v| v.as_scalar());
+    let b = b.filter(|v| !v.is_nil()).and_then(|v| v.as_scalar());
+    match (a, b) {
+        (Some(a), Some(b)) => compare_scalars(&a, &b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct SortByArgs {
+    #[parameter(description = "The field to sort by.", arg_type = "str")]
+    field: Expression,
+    #[parameter(
+        description = "The order to sort in, \"asc\" (default) or \"desc\".",
+        arg_type = "str"
+    )]
+    order: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "sort_by",
+    description = "Sorts objects by a field, comparing dates and numbers by value.",
+    parameters(SortByArgs),
+    parsed(SortByFilter)
+)]
+pub struct SortByFilterParser;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "sort_by"]
+struct SortByFilter {
+    #[parameters]
+    args: SortByArgs,
+}
+
+impl Filter for SortByFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let descending = match args.order.as_deref() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(order) => {
+                return Err(Error::with_msg(format!(
+                    "invalid order {}, expected \"asc\" or \"desc\"",
+                    order
+                )))
+            }
+        };
+        let mut items: Vec<(&dyn ValueView, Option<ValueCow>)> = array(input, "sort_by")?
+            .into_iter()
+            .map(|item| (item, property(item, &args.field)))
+            .collect();
+        items.sort_by(|(_, a), (_, b)| {
+            let a = a.as_ref().map(|v| v.as_view());
+            let b = b.as_ref().map(|v| v.as_view());
+            let ordering = compare(a, b);
+            // Missing values sort last in either order
+            if descending && a.is_some_and(|v| !v.is_nil()) && b.is_some_and(|v| !v.is_nil()) {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        Ok(Value::array(
+            items.into_iter().map(|(item, _)| item.to_value()),
+        ))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct GroupByArgs {
+    #[parameter(description = "The field to group by.", arg_type = "str")]
+    field: Expression,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "group_by",
+    description = "Groups objects by a field, into a list of groups with a name, items and size.",
+    parameters(GroupByArgs),
+    parsed(GroupByFilter)
+)]
+pub struct GroupByFilterParser;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "group_by"]
+struct GroupByFilter {
+    #[parameters]
+    args: GroupByArgs,
+}
+
+impl Filter for GroupByFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        // Groups are in the order their first item appears
+        let mut groups: Vec<(String, Vec<Value>)> = vec![];
+        for item in array(input, "group_by")? {
+            let name = property(item, &args.field)
+                .filter(|v| !v.is_nil())
+                .map(|v| v.to_kstr().to_string())
+                .unwrap_or_default();
+            match groups.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, items)) => items.push(item.to_value()),
+                None => groups.push((name, vec![item.to_value()])),
+            }
+        }
+        Ok(Value::array(groups.into_iter().map(|(name, items)| {
+            let mut group = Object::new();
+            group.insert("name".into(), Value::scalar(name));
+            group.insert("size".into(), Value::scalar(items.len() as i64));
+            group.insert("items".into(), Value::Array(items));
+            Value::Object(group)
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields::{DateTime, FieldValue};
+    use liquid::ParserBuilder;
+    use std::error::Error;
+
+    fn render(template: &str) -> Result<String, Box<dyn Error>> {
+        let parser = ParserBuilder::with_stdlib()
+            .filter(SortByFilterParser)
+            .filter(GroupByFilterParser)
+            .build()?;
+        let post = |title: &str, date: Option<&str>, rank: i64, category: &str| {
+            let mut post = liquid::object!({
+                "title": title,
+                "rank": rank,
+                "category": category,
+            });
+            // object! would serialize dates to strings
+            if let Some(date) = date {
+                post.insert(
+                    "date".into(),
+                    FieldValue::Date(DateTime::from(date).unwrap()).to_value(),
+                );
+            }
+            Value::Object(post)
+        };
+        let mut globals = Object::new();
+        globals.insert(
+            "posts".into(),
+            Value::array([
+                // Sorting these as strings would put b first
+                post("b", Some("2024-01-01T03:00:00-05:00"), 10, "news"),
+                post("a", Some("2024-01-01T06:00:00Z"), 9, "blog"),
+                post("c", None, 100, "news"),
+            ]),
+        );
+        Ok(parser.parse(template)?.render(&globals)?)
+    }
+
+    #[test]
+    fn sort_by() -> Result<(), Box<dyn Error>> {
+        let titles = |field: &str, order: &str| {
+            render(&format!(
+                "{{% assign s = posts | sort_by: '{}'{} %}}{{% for p in s %}}{{{{p.title}}}}{{% endfor %}}",
+                field, order
+            ))
+        };
+        assert_eq!(titles("date", "")?, "abc");
+        assert_eq!(titles("date", ", 'desc'")?, "bac");
+        assert_eq!(titles("rank", "")?, "abc");
+        assert!(titles("rank", ", 'sideways'").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn group_by() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            render(
+                "{% assign g = posts | group_by: 'category' %}{% for group in g %}{{group.name}}:{{group.size}}:{% for p in group.items %}{{p.title}}{% endfor %};{% endfor %}"
+            )?,
+            "news:2:bc;blog:1:a;"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use crate::fields::formats::color_components;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::ValueView;
+use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+use liquid_core::{Error, Object, Value};
+
+fn components(input: &dyn ValueView) -> Result<(u8, u8, u8, u8)> {
+    color_components(&input.to_kstr()).ok_or_else(|| {
+        Error::with_msg("invalid color").context("input", format!("{}", input.source()))
+    })
+}
+
+fn alpha(a: u8) -> f64 {
+    (a as f64 / 255.0 * 100.0).round() / 100.0
+}
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "rgb"]
+pub struct RgbFilter;
+
+impl Filter for RgbFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        if input.is_nil() || input.to_kstr().is_empty() {
+            return Ok(Value::Nil);
+        }
+        let (r, g, b, a) = components(input)?;
+        Ok(Value::scalar(if a == 255 {
+            format!("rgb({}, {}, {})", r, g, b)
+        } else {
+            format!("rgba({}, {}, {}, {})", r, g, b, alpha(a))
+        }))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "rgb",
+    description = "Converts a hex color to a css rgb() or rgba() color.",
+    parsed(RgbFilter)
+)]
+pub struct RgbFilterParser;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "color_components"]
+pub struct ColorComponentsFilter;
+
+impl Filter for ColorComponentsFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        if input.is_nil() || input.to_kstr().is_empty() {
+            return Ok(Value::Nil);
+        }
+        let (r, g, b, a) = components(input)?;
+        let mut color = Object::new();
+        color.insert("r".into(), Value::scalar(r as i64));
+        color.insert("g".into(), Value::scalar(g as i64));
+        color.insert("b".into(), Value::scalar(b as i64));
+        color.insert("a".into(), Value::scalar(alpha(a)));
+        Ok(Value::Object(color))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "color_components",
+    description = "Splits a hex color into its r, g, b (0-255) and a (0-1) components.",
+    parsed(ColorComponentsFilter)
+)]
+pub struct ColorComponentsFilterParser;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use liquid::ParserBuilder;
+    use std::error::Error;
+
+    fn render(template: &str, color: &str) -> Result<String, Box<dyn Error>> {
+        let parser = ParserBuilder::with_stdlib()
+            .filter(RgbFilterParser)
+            .filter(ColorComponentsFilterParser)
+            .build()?;
+        let globals = liquid::object!({ "color": color });
+        Ok(parser.parse(template)?.render(&globals)?)
+    }
+
+    #[test]
+    fn rgb() -> Result<(), Box<dyn Error>> {
+        assert_eq!(render("{{ color | rgb }}", "#ff8800")?, "rgb(255, 136, 0)");
+        assert_eq!(
+            render("{{ color | rgb }}", "#ff880080")?,
+            "rgba(255, 136, 0, 0.5)"
+        );
+        assert_eq!(render("{{ color | rgb }}", "")?, "");
+        assert!(render("{{ color | rgb }}", "red").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn components() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            render(
+                "{% assign c = color | color_components %}{{ c.r }} {{ c.g }} {{ c.b }} {{ c.a }}",
+                "#0a0"
+            )?,
+            "0 170 0 1"
+        );
+        Ok(())
+    }
+}
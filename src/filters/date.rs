@@ -0,0 +1,71 @@
+use crate::fields::DateTime;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+use liquid_core::{Error, Value, ValueView};
+use time::format_description::well_known::Rfc3339;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "date_to_rfc3339"]
+pub struct DateToRfc3339Filter;
+
+impl Filter for DateToRfc3339Filter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        if input.is_nil() {
+            return Ok(Value::Nil);
+        }
+        let invalid =
+            || Error::with_msg("invalid date").context("input", format!("{}", input.source()));
+        let date = match input.as_scalar().and_then(|s| s.to_date_time()) {
+            Some(date) => date,
+            // Strings in the formats date fields accept, interpreted in the
+            // site timezone
+            None => DateTime::from(&input.to_kstr())
+                .map_err(|_| invalid())?
+                .as_liquid_datetime(),
+        };
+        Ok(Value::scalar(
+            (*date).format(&Rfc3339).map_err(|_| invalid())?,
+        ))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "date_to_rfc3339",
+    description = "Formats a date as RFC 3339, e.g. for Atom feeds and sitemaps.",
+    parsed(DateToRfc3339Filter)
+)]
+pub struct DateToRfc3339FilterParser;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields::FieldValue;
+    use liquid::ParserBuilder;
+    use std::error::Error;
+
+    fn render(template: &str) -> Result<String, Box<dyn Error>> {
+        let parser = ParserBuilder::with_stdlib()
+            .filter(DateToRfc3339FilterParser)
+            .build()?;
+        let globals = liquid::object!({
+            "date": FieldValue::Date(DateTime::from("2024-01-31T10:00:00+02:00")?).to_value(),
+        });
+        Ok(parser.parse(template)?.render(&globals)?)
+    }
+
+    #[test]
+    fn date_to_rfc3339() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            render("{{ date | date_to_rfc3339 }}")?,
+            "2024-01-31T10:00:00+02:00"
+        );
+        assert_eq!(
+            render("{{ '2024-01-31 10:00:00 +0000' | date_to_rfc3339 }}")?,
+            "2024-01-31T10:00:00Z"
+        );
+        assert!(render("{{ 'yesterday' | date_to_rfc3339 }}").is_err());
+        Ok(())
+    }
+}
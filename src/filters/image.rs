@@ -0,0 +1,391 @@
+#[cfg(feature = "image-processing")]
+use crate::image_processing::ImageVariants;
+use crate::images::{ImageSource, ImageTransform, ImageUrls};
+use liquid_core::parser::FilterArguments;
+use liquid_core::Expression;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{Display_filter, Filter, FilterParameters, FilterReflection, ParseFilter};
+use liquid_core::{Error, Value, ValueView};
+use std::sync::Arc;
+
+/// The image a filter was applied to, either a file field or a url.
+fn image_source(input: &dyn ValueView, name: &str) -> Result<ImageSource> {
+    let invalid = || {
+        Error::with_msg(format!("{} expects a file or a url", name))
+            .context("input", format!("{}", input.source()))
+    };
+    if let Some(file) = input.as_object() {
+        let get = |key: &str| {
+            file.get(key)
+                .filter(|v| !v.is_nil())
+                .map(|v| v.to_kstr().to_string())
+        };
+        let url = get("url").ok_or_else(invalid)?;
+        let mut image = ImageSource::from_url(&url);
+        image.sha = get("sha").unwrap_or_default();
+        if let Some(filename) = get("filename") {
+            image.filename = filename;
+        }
+        Ok(image)
+    } else if input.as_scalar().is_some() {
+        Ok(ImageSource::from_url(&input.to_kstr()))
+    } else {
+        Err(invalid())
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct TransformArgs {
+    #[parameter(
+        description = "The width, in pixels.",
+        arg_type = "integer",
+        mode = "keyword"
+    )]
+    width: Option<Expression>,
+    #[parameter(
+        description = "The height, in pixels.",
+        arg_type = "integer",
+        mode = "keyword"
+    )]
+    height: Option<Expression>,
+    #[parameter(
+        description = "How the image fits the width and height, e.g. \"cover\".",
+        arg_type = "str",
+        mode = "keyword"
+    )]
+    fit: Option<Expression>,
+    #[parameter(
+        description = "The quality, from 1 to 100.",
+        arg_type = "integer",
+        mode = "keyword"
+    )]
+    quality: Option<Expression>,
+    #[parameter(
+        description = "The image format, e.g. \"webp\".",
+        arg_type = "str",
+        mode = "keyword"
+    )]
+    format: Option<Expression>,
+}
+
+impl TransformArgs {
+    fn transform(&self, runtime: &dyn Runtime) -> Result<ImageTransform> {
+        let args = self.evaluate(runtime)?;
+        Ok(ImageTransform {
+            width: args.width,
+            height: args.height,
+            fit: args.fit.map(|f| f.to_string()),
+            quality: args.quality,
+            format: args.format.map(|f| f.to_string()),
+        })
+    }
+}
+
+#[derive(Debug, Display_filter)]
+#[name = "image_url"]
+struct ImageUrlFilter {
+    #[parameters]
+    args: TransformArgs,
+    urls: Arc<dyn ImageUrls>,
+}
+
+impl Filter for ImageUrlFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        if input.is_nil() {
+            return Ok(Value::Nil);
+        }
+        let image = image_source(input, "image_url")?;
+        let transform = self.args.transform(runtime)?;
+        Ok(Value::scalar(self.urls.transform(&image, &transform)))
+    }
+}
+
+/// Generates the url of a transformed image, using the site's image urls.
+#[derive(Debug, Clone, FilterReflection)]
+#[filter(
+    name = "image_url",
+    description = "The url of an image, resized or converted by the image CDN.",
+    parameters(TransformArgs)
+)]
+pub struct ImageUrlFilterParser {
+    pub urls: Arc<dyn ImageUrls>,
+}
+
+impl ParseFilter for ImageUrlFilterParser {
+    fn parse(&self, arguments: FilterArguments) -> Result<Box<dyn Filter>> {
+        Ok(Box::new(ImageUrlFilter {
+            args: TransformArgs::from_args(arguments)?,
+            urls: self.urls.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn FilterReflection {
+        self
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct SrcsetArgs {
+    #[parameter(
+        description = "The widths to include, as a list or a comma separated string.",
+        arg_type = "any"
+    )]
+    widths: Expression,
+    #[parameter(
+        description = "The height, in pixels.",
+        arg_type = "integer",
+        mode = "keyword"
+    )]
+    height: Option<Expression>,
+    #[parameter(
+        description = "How the image fits the width and height, e.g. \"cover\".",
+        arg_type = "str",
+        mode = "keyword"
+    )]
+    fit: Option<Expression>,
+    #[parameter(
+        description = "The quality, from 1 to 100.",
+        arg_type = "integer",
+        mode = "keyword"
+    )]
+    quality: Option<Expression>,
+    #[parameter(
+        description = "The image format, e.g. \"webp\".",
+        arg_type = "str",
+        mode = "keyword"
+    )]
+    format: Option<Expression>,
+}
+
+#[derive(Debug, Display_filter)]
+#[name = "srcset"]
+struct SrcsetFilter {
+    #[parameters]
+    args: SrcsetArgs,
+    urls: Arc<dyn ImageUrls>,
+}
+
+fn widths(value: &dyn ValueView) -> Result<Vec<i64>> {
+    let invalid = |w: &str| Error::with_msg(format!("invalid srcset width {}", w));
+    let parse = |w: &str| w.trim().parse::<i64>().map_err(|_| invalid(w));
+    if let Some(array) = value.as_array() {
+        array
+            .values()
+            .map(|w| match w.as_scalar().and_then(|s| s.to_integer()) {
+                Some(w) => Ok(w),
+                None => parse(&w.to_kstr()),
+            })
+            .collect()
+    } else {
+        value.to_kstr().split(',').map(parse).collect()
+    }
+}
+
+impl Filter for SrcsetFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        if input.is_nil() {
+            return Ok(Value::Nil);
+        }
+        let image = image_source(input, "srcset")?;
+        let args = self.args.evaluate(runtime)?;
+        // Without a CDN, every width would be the original image
+        if !self.urls.transforms() {
+            return Ok(Value::scalar(image.url));
+        }
+        let srcset = widths(args.widths.as_view())?
+            .into_iter()
+            .map(|width| {
+                let transform = ImageTransform {
+                    width: Some(width),
+                    height: args.height,
+                    fit: args.fit.as_ref().map(|f| f.to_string()),
+                    quality: args.quality,
+                    format: args.format.as_ref().map(|f| f.to_string()),
+                };
+                format!("{} {}w", self.urls.transform(&image, &transform), width)
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        Ok(Value::scalar(srcset))
+    }
+}
+
+/// Generates a `srcset` of transformed images, using the site's image urls.
+#[derive(Debug, Clone, FilterReflection)]
+#[filter(
+    name = "srcset",
+    description = "A srcset of an image at each of the given widths, resized by the image CDN.",
+    parameters(SrcsetArgs)
+)]
+pub struct SrcsetFilterParser {
+    pub urls: Arc<dyn ImageUrls>,
+}
+
+impl ParseFilter for SrcsetFilterParser {
+    fn parse(&self, arguments: FilterArguments) -> Result<Box<dyn Filter>> {
+        Ok(Box::new(SrcsetFilter {
+            args: SrcsetArgs::from_args(arguments)?,
+            urls: self.urls.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn FilterReflection {
+        self
+    }
+}
+
+#[cfg(feature = "image-processing")]
+#[derive(Debug, Display_filter)]
+#[name = "image_variants"]
+struct ImageVariantsFilter {
+    variants: ImageVariants,
+}
+
+#[cfg(feature = "image-processing")]
+impl Filter for ImageVariantsFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        if input.is_nil() {
+            return Ok(Value::Nil);
+        }
+        let path = input.to_kstr();
+        let file = std::path::Path::new(path.trim_start_matches('/'));
+        Ok(Value::array(
+            self.variants.get(file).iter().map(|v| v.to_liquid()),
+        ))
+    }
+}
+
+/// Lists the generated variants of an image in the static dir.
+#[cfg(feature = "image-processing")]
+#[derive(Debug, Clone, FilterReflection)]
+#[filter(
+    name = "image_variants",
+    description = "The variants of a static image, with their path, width, height and format."
+)]
+pub struct ImageVariantsFilterParser {
+    pub variants: ImageVariants,
+}
+
+#[cfg(feature = "image-processing")]
+impl ParseFilter for ImageVariantsFilterParser {
+    fn parse(&self, mut arguments: FilterArguments) -> Result<Box<dyn Filter>> {
+        if arguments.positional.next().is_some() || arguments.keyword.next().is_some() {
+            return Err(Error::with_msg("image_variants takes no arguments"));
+        }
+        Ok(Box::new(ImageVariantsFilter {
+            variants: self.variants.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn FilterReflection {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::ImageOptions;
+    use liquid::ParserBuilder;
+    use std::error::Error;
+
+    fn render(template: &str, images: &str) -> Result<String, Box<dyn Error>> {
+        let urls = ImageOptions::from_toml(&toml::from_str(images)?)?.image_urls();
+        let parser = ParserBuilder::with_stdlib()
+            .filter(ImageUrlFilterParser { urls: urls.clone() })
+            .filter(SrcsetFilterParser { urls })
+            .build()?;
+        let globals = liquid::object!({
+            "post": {
+                "cover": {
+                    "sha": "abc",
+                    "filename": "cover.jpg",
+                    "url": "https://uploads.archival.dev/abc",
+                },
+            },
+        });
+        Ok(parser.parse(template)?.render(&globals)?)
+    }
+
+    const IMGIX: &str = "transform_url = \"https://example.imgix.net/{sha}?{options}\"
+        option_names = { width = \"w\", format = \"fm\" }";
+
+    #[test]
+    fn image_url() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            render(
+                "{{ post.cover | image_url: width: 800, format: \"webp\" }}",
+                IMGIX
+            )?,
+            "https://example.imgix.net/abc?w=800&fm=webp"
+        );
+        assert_eq!(
+            render(
+                "{{ '/images/a.png' | image_url: width: 800 }}",
+                "transform_url = \"https://example.com/cdn-cgi/image/{options}{url}\""
+            )?,
+            "https://example.com/cdn-cgi/image/width=800/images/a.png"
+        );
+        assert_eq!(
+            render("{{ post.cover | image_url: width: 800 }}", "")?,
+            "https://uploads.archival.dev/abc"
+        );
+        assert!(render("{{ post | image_url: width: 800 }}", IMGIX).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn srcset() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            render("{{ post.cover | srcset: '400, 800', format: 'webp' }}", IMGIX)?,
+            "https://example.imgix.net/abc?w=400&fm=webp 400w, https://example.imgix.net/abc?w=800&fm=webp 800w"
+        );
+        assert_eq!(
+            render("{{ post.cover | srcset: '400,800' }}", "")?,
+            "https://uploads.archival.dev/abc"
+        );
+        assert!(render("{{ post.cover | srcset: 'big' }}", IMGIX).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "image-processing")]
+    #[test]
+    fn image_variants() -> Result<(), Box<dyn Error>> {
+        use crate::images::ImageVariantOptions;
+        use crate::MemoryFileSystem;
+        use std::{io::Cursor, path::Path};
+
+        let mut png = vec![];
+        image::DynamicImage::new_rgb8(200, 100)
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)?;
+        let variants = ImageVariants::default();
+        variants.process(
+            Path::new("images/a.png"),
+            &png,
+            1,
+            &ImageVariantOptions {
+                widths: vec![100],
+                formats: vec!["webp".to_string()],
+                quality: None,
+            },
+            Path::new("dist"),
+            &mut MemoryFileSystem::default(),
+        )?;
+        let parser = ParserBuilder::with_stdlib()
+            .filter(ImageVariantsFilterParser { variants })
+            .build()?;
+        let template = "{% assign vs = '/images/a.png' | image_variants %}{% for v in vs %}{{ v.path }} {{ v.width }}x{{ v.height }} {{ v.format }}{% endfor %}";
+        assert_eq!(
+            parser.parse(template)?.render(&liquid::object!({}))?,
+            "/images/a-png-100w.webp 100x50 webp"
+        );
+        assert_eq!(
+            parser
+                .parse("{{ '/images/b.png' | image_variants | size }}")?
+                .render(&liquid::object!({}))?,
+            "0"
+        );
+        Ok(())
+    }
+}
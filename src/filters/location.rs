@@ -0,0 +1,131 @@
+use crate::fields::{DistanceUnit, Location};
+use liquid_core::Expression;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{
+    Display_filter, Filter, FilterParameters, FilterReflection, FromFilterParameters, ParseFilter,
+};
+use liquid_core::{Error, Value, ValueView};
+
+fn location(input: &dyn ValueView, name: &str) -> Result<Location> {
+    Location::from_liquid(input).ok_or_else(|| {
+        Error::with_msg("invalid location").context(name.to_string(), format!("{}", input.source()))
+    })
+}
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "geojson"]
+pub struct GeoJsonFilter;
+
+impl Filter for GeoJsonFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        let geojson = if let Some(locations) = input.as_array() {
+            serde_json::json!({
+                "type": "FeatureCollection",
+                "features": locations
+                    .values()
+                    .filter(|l| !l.is_nil())
+                    .map(|l| location(l, "input").map(|l| l.to_geojson()))
+                    .collect::<Result<Vec<_>>>()?,
+            })
+        } else {
+            location(input, "input")?.to_geojson()
+        };
+        Ok(Value::scalar(geojson.to_string()))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "geojson",
+    description = "Converts a location, or a list of locations, to a GeoJSON Feature or FeatureCollection.",
+    parsed(GeoJsonFilter)
+)]
+pub struct GeoJsonFilterParser;
+
+#[derive(Debug, FilterParameters)]
+struct DistanceArgs {
+    #[parameter(description = "The location to measure the distance to.")]
+    to: Expression,
+    #[parameter(
+        description = "The unit of the distance, \"km\" (default) or \"mi\".",
+        arg_type = "str"
+    )]
+    unit: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "distance",
+    description = "Calculates the distance between two locations.",
+    parameters(DistanceArgs),
+    parsed(DistanceFilter)
+)]
+pub struct DistanceFilterParser;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "distance"]
+struct DistanceFilter {
+    #[parameters]
+    args: DistanceArgs,
+}
+
+impl Filter for DistanceFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let unit = match &args.unit {
+            Some(unit) => unit
+                .parse::<DistanceUnit>()
+                .map_err(|e| Error::with_msg(e.to_string()))?,
+            None => DistanceUnit::Kilometers,
+        };
+        let from = location(input, "input")?;
+        let to = location(args.to.as_view(), "to")?;
+        Ok(Value::scalar(from.distance_to(&to, unit)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use liquid::ParserBuilder;
+    use std::error::Error;
+
+    fn render(template: &str) -> Result<String, Box<dyn Error>> {
+        let parser = ParserBuilder::with_stdlib()
+            .filter(GeoJsonFilterParser)
+            .filter(DistanceFilterParser)
+            .build()?;
+        let globals = liquid::object!({
+            "portland": { "lat": 45.5152, "lng": -122.6784, "name": "Portland" },
+            "seattle": { "lat": 47.6062, "lng": -122.3321 },
+            "places": [
+                { "lat": 45.5152, "lng": -122.6784, "name": "Portland" },
+                { "lat": 47.6062, "lng": -122.3321 },
+            ],
+        });
+        Ok(parser.parse(template)?.render(&globals)?)
+    }
+
+    #[test]
+    fn geojson() -> Result<(), Box<dyn Error>> {
+        let feature: serde_json::Value = serde_json::from_str(&render("{{ seattle | geojson }}")?)?;
+        assert_eq!(feature["geometry"]["coordinates"][0], -122.3321);
+        let collection: serde_json::Value =
+            serde_json::from_str(&render("{{ places | geojson }}")?)?;
+        assert_eq!(collection["type"], "FeatureCollection");
+        assert_eq!(collection["features"][0]["properties"]["name"], "Portland");
+        Ok(())
+    }
+
+    #[test]
+    fn distance() -> Result<(), Box<dyn Error>> {
+        assert_eq!(render("{{ portland | distance: seattle | round }}")?, "234");
+        assert_eq!(
+            render("{{ portland | distance: seattle, 'mi' | round }}")?,
+            "145"
+        );
+        assert!(render("{{ portland | distance: 'nowhere' }}").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,340 @@
+use crate::{
+    fields::MORE_MARKER,
+    output_format::{is_rendered_html, mark_rendered_html},
+};
+use liquid_core::Expression;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{
+    Display_filter, Filter, FilterParameters, FilterReflection, FromFilterParameters, ParseFilter,
+};
+use liquid_core::{Error, Value, ValueView};
+
+/// Elements that have no closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+/// Elements that separate words, so that `<p>a</p><p>b</p>` is two words.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+const DEFAULT_WORDS_PER_MINUTE: i64 = 200;
+
+enum Token<'a> {
+    Text(&'a str),
+    /// A tag, with its lowercased name and whether it is a closing tag.
+    Tag(&'a str, String, bool),
+    Comment(&'a str),
+}
+
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut rest = html;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            tokens.push(Token::Text(rest));
+            break;
+        };
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        rest = &rest[start..];
+        if rest.starts_with("<!--") {
+            let end = rest.find("-->").map_or(rest.len(), |e| e + 3);
+            tokens.push(Token::Comment(&rest[..end]));
+            rest = &rest[end..];
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            tokens.push(Token::Text(rest));
+            break;
+        };
+        let tag = &rest[..=end];
+        let closing = tag.starts_with("</");
+        let name = tag
+            .trim_start_matches(['<', '/'])
+            .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        tokens.push(Token::Tag(tag, name, closing));
+        rest = &rest[end + 1..];
+    }
+    tokens
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// The text of html, with markup removed and whitespace collapsed.
+pub fn plain_text(html: &str) -> String {
+    let mut text = String::new();
+    for token in tokenize(html) {
+        match token {
+            Token::Text(t) => text.push_str(&decode_entities(t)),
+            Token::Tag(_, name, _) if BLOCK_ELEMENTS.contains(&name.as_str()) => text.push(' '),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn word_count(html: &str) -> usize {
+    plain_text(html).split_whitespace().count()
+}
+
+/// The html before the `<!-- more -->` marker. Without a marker, this is the
+/// first `words` words, with any open tags closed, or the first paragraph.
+pub fn excerpt(html: &str, words: Option<usize>) -> String {
+    if let Some(idx) = html.find(MORE_MARKER) {
+        return html[..idx].trim_end().to_string();
+    }
+    let Some(words) = words else {
+        return match html.find("</p>") {
+            Some(idx) => html[..idx + 4].to_string(),
+            None => html.to_string(),
+        };
+    };
+    let mut output = String::new();
+    let mut open_tags: Vec<String> = vec![];
+    let mut count = 0;
+    for token in tokenize(html) {
+        match token {
+            Token::Text(text) => {
+                let mut in_word = false;
+                for (idx, c) in text.char_indices() {
+                    if c.is_whitespace() {
+                        in_word = false;
+                    } else if !in_word {
+                        in_word = true;
+                        if count == words {
+                            output.push_str(text[..idx].trim_end());
+                            output.push('…');
+                            for tag in open_tags.iter().rev() {
+                                output.push_str(&format!("</{}>", tag));
+                            }
+                            return output;
+                        }
+                        count += 1;
+                    }
+                }
+                output.push_str(text);
+            }
+            Token::Tag(tag, name, closing) => {
+                if closing {
+                    if let Some(idx) = open_tags.iter().rposition(|t| *t == name) {
+                        open_tags.truncate(idx);
+                    }
+                } else if !VOID_ELEMENTS.contains(&name.as_str()) && !tag.ends_with("/>") {
+                    open_tags.push(name);
+                }
+                output.push_str(tag);
+            }
+            Token::Comment(comment) => output.push_str(comment),
+        }
+    }
+    output
+}
+
+#[derive(Debug, FilterParameters)]
+struct ExcerptArgs {
+    #[parameter(
+        description = "The number of words to truncate to when there is no `<!-- more -->` marker. Defaults to the first paragraph.",
+        arg_type = "integer"
+    )]
+    words: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "excerpt",
+    description = "The html of a markdown field before its `<!-- more -->` marker, or its first paragraph or words.",
+    parameters(ExcerptArgs),
+    parsed(ExcerptFilter)
+)]
+pub struct ExcerptFilterParser;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "excerpt"]
+struct ExcerptFilter {
+    #[parameters]
+    args: ExcerptArgs,
+}
+
+impl Filter for ExcerptFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let words = match args.words {
+            Some(words) if words < 0 => {
+                return Err(Error::with_msg("words must be positive"));
+            }
+            words => words.map(|w| w as usize),
+        };
+        let html = input.to_kstr();
+        let excerpt = excerpt(&html, words);
+        // The excerpt of a markdown field is as safe as the field's html
+        if is_rendered_html(&html) {
+            mark_rendered_html(&excerpt);
+        }
+        Ok(Value::scalar(excerpt))
+    }
+}
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "plain_text"]
+pub struct PlainTextFilter;
+
+impl Filter for PlainTextFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(plain_text(&input.to_kstr())))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "plain_text",
+    description = "The text of a markdown field, with markup removed and whitespace collapsed.",
+    parsed(PlainTextFilter)
+)]
+pub struct PlainTextFilterParser;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "word_count"]
+pub struct WordCountFilter;
+
+impl Filter for WordCountFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(word_count(&input.to_kstr()) as i64))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "word_count",
+    description = "The number of words in a markdown field.",
+    parsed(WordCountFilter)
+)]
+pub struct WordCountFilterParser;
+
+#[derive(Debug, FilterParameters)]
+struct ReadingTimeArgs {
+    #[parameter(
+        description = "The words read per minute, 200 by default.",
+        arg_type = "integer"
+    )]
+    words_per_minute: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "reading_time",
+    description = "The minutes it takes to read a markdown field, rounded up.",
+    parameters(ReadingTimeArgs),
+    parsed(ReadingTimeFilter)
+)]
+pub struct ReadingTimeFilterParser;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "reading_time"]
+struct ReadingTimeFilter {
+    #[parameters]
+    args: ReadingTimeArgs,
+}
+
+impl Filter for ReadingTimeFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let wpm = args.words_per_minute.unwrap_or(DEFAULT_WORDS_PER_MINUTE);
+        if wpm <= 0 {
+            return Err(Error::with_msg("words_per_minute must be positive"));
+        }
+        let words = word_count(&input.to_kstr()) as i64;
+        Ok(Value::scalar(((words + wpm - 1) / wpm).max(1)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use liquid::ParserBuilder;
+    use std::error::Error;
+
+    const HTML: &str = "<h1 id=\"title\">Title</h1>\n<p>One <em>two three</em> four &amp; five.</p>\n<p>Six<br />\nseven</p>\n";
+
+    fn render(template: &str, content: &str) -> Result<String, Box<dyn Error>> {
+        let parser = ParserBuilder::with_stdlib()
+            .filter(ExcerptFilterParser)
+            .filter(PlainTextFilterParser)
+            .filter(WordCountFilterParser)
+            .filter(ReadingTimeFilterParser)
+            .build()?;
+        let globals = liquid::object!({ "content": content });
+        Ok(parser.parse(template)?.render(&globals)?)
+    }
+
+    #[test]
+    fn plain_text_and_counts() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            render("{{ content | plain_text }}", HTML)?,
+            "Title One two three four & five. Six seven"
+        );
+        assert_eq!(render("{{ content | word_count }}", HTML)?, "9");
+        assert_eq!(render("{{ content | reading_time }}", HTML)?, "1");
+        assert_eq!(render("{{ content | reading_time: 4 }}", HTML)?, "3");
+        assert!(render("{{ content | reading_time: 0 }}", HTML).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn excerpts() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            render("{{ content | excerpt }}", HTML)?,
+            "<h1 id=\"title\">Title</h1>\n<p>One <em>two three</em> four &amp; five.</p>"
+        );
+        assert_eq!(
+            render("{{ content | excerpt: 3 }}", HTML)?,
+            "<h1 id=\"title\">Title</h1>\n<p>One <em>two…</em></p>"
+        );
+        assert_eq!(render("{{ content | excerpt: 100 }}", HTML)?, HTML);
+        let more = format!("<p>Intro</p>\n{}\n<p>Rest</p>\n", MORE_MARKER);
+        assert_eq!(render("{{ content | excerpt: 1 }}", &more)?, "<p>Intro</p>");
+        Ok(())
+    }
+}
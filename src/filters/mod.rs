@@ -1,3 +1,11 @@
+pub mod collection;
+pub mod color;
+pub mod date;
+pub mod image;
+pub mod location;
+pub mod markdown;
 mod size;
+pub mod text;
+pub mod url;
 
 // pub use size::SizeFilterParser;
@@ -0,0 +1,366 @@
+use super::url::no_arguments;
+use crate::{
+    output_format::{Escaping, OutputFormats},
+    page::TemplateType,
+    taxonomy::slugify,
+};
+use liquid_core::model::ScalarCow;
+use liquid_core::parser::{FilterArguments, ParameterReflection};
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+use liquid_core::{Value, ValueView};
+use time::format_description::well_known::Rfc3339;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "slugify"]
+pub struct SlugifyFilter;
+
+impl Filter for SlugifyFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(slugify(&input.to_kstr())))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "slugify",
+    description = "Converts a string to a lowercase, hyphenated slug, the same way taxonomy terms are.",
+    parsed(SlugifyFilter)
+)]
+pub struct SlugifyFilterParser;
+
+pub fn xml_escape(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "raw"]
+pub struct RawFilter;
+
+impl Filter for RawFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(input.to_value())
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "raw",
+    description = "Outputs a value without escaping it for the page's format.",
+    parsed(RawFilter)
+)]
+pub struct RawFilterParser;
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "safe",
+    description = "Outputs a value without escaping it for the page's format, like `raw`.",
+    parsed(RawFilter)
+)]
+pub struct SafeFilterParser;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "xml_escape"]
+pub struct XmlEscapeFilter;
+
+impl Filter for XmlEscapeFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        if input.is_nil() {
+            return Ok(Value::Nil);
+        }
+        Ok(Value::scalar(xml_escape(&input.to_kstr())))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "xml_escape",
+    description = "Escapes a string for use in xml, e.g. an RSS feed.",
+    parsed(XmlEscapeFilter)
+)]
+pub struct XmlEscapeFilterParser;
+
+pub fn html_escape(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Quotes a csv field when it contains a comma, quote or newline, doubling
+/// any quotes in it.
+pub fn csv_escape(input: &str) -> String {
+    if input.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", input.replace('"', "\"\""))
+    } else {
+        input.to_string()
+    }
+}
+
+/// Escapes a string for use inside a json string, without the quotes.
+pub fn json_escape(input: &str) -> String {
+    let quoted = serde_json::Value::from(input).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "csv_escape"]
+pub struct CsvEscapeFilter;
+
+impl Filter for CsvEscapeFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        if input.is_nil() {
+            return Ok(Value::Nil);
+        }
+        Ok(Value::scalar(csv_escape(&input.to_kstr())))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "csv_escape",
+    description = "Quotes a value for use as a csv field, when it needs to be.",
+    parsed(CsvEscapeFilter)
+)]
+pub struct CsvEscapeFilterParser;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "json_escape"]
+pub struct JsonEscapeFilter;
+
+impl Filter for JsonEscapeFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        if input.is_nil() {
+            return Ok(Value::Nil);
+        }
+        Ok(Value::scalar(json_escape(&input.to_kstr())))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "json_escape",
+    description = "Escapes a string for use inside a json string.",
+    parsed(JsonEscapeFilter)
+)]
+pub struct JsonEscapeFilterParser;
+
+#[derive(Debug, Clone, Display_filter)]
+#[name = "escape_output"]
+struct EscapeOutputFilter {
+    formats: OutputFormats,
+}
+
+impl Filter for EscapeOutputFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        if input.is_nil() {
+            return Ok(Value::Nil);
+        }
+        let escaping = runtime
+            .try_get(&[ScalarCow::new("output_format")])
+            .map(|format| TemplateType::from_ext(&format.to_kstr()))
+            .and_then(|t| t.format(&self.formats))
+            .map_or(Escaping::None, |f| f.escape);
+        Ok(Value::scalar(escaping.escape(&input.to_kstr())))
+    }
+}
+
+/// Escapes values for the output format of the page being rendered, e.g.
+/// html escaping on html pages and csv quoting on csv pages. Pages add it to
+/// their outputs, see Escaping.
+#[derive(Clone)]
+pub struct EscapeOutputFilterParser {
+    pub formats: OutputFormats,
+}
+
+impl ParseFilter for EscapeOutputFilterParser {
+    fn parse(&self, arguments: FilterArguments) -> Result<Box<dyn Filter>> {
+        no_arguments("escape_output", arguments)?;
+        Ok(Box::new(EscapeOutputFilter {
+            formats: self.formats.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn FilterReflection {
+        self
+    }
+}
+
+impl FilterReflection for EscapeOutputFilterParser {
+    fn name(&self) -> &str {
+        "escape_output"
+    }
+    fn description(&self) -> &str {
+        "Escapes a value for the output format of the page, e.g. html or csv."
+    }
+    fn positional_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+    fn keyword_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+}
+
+fn scalar_to_json(scalar: ScalarCow) -> serde_json::Value {
+    match scalar.type_name() {
+        "whole number" => scalar.to_integer().into(),
+        "fractional number" => scalar.to_float().into(),
+        "boolean" => scalar.to_bool().into(),
+        "date time" | "date" => match scalar.to_date_time() {
+            Some(date) => (*date).format(&Rfc3339).ok().into(),
+            None => scalar.to_kstr().as_str().into(),
+        },
+        _ => scalar.to_kstr().as_str().into(),
+    }
+}
+
+/// Converts a liquid value to json, keeping the types of field values, so
+/// numbers and booleans are not quoted and dates are RFC 3339 strings.
+pub fn to_json(value: &dyn ValueView) -> serde_json::Value {
+    if let Some(scalar) = value.as_scalar() {
+        scalar_to_json(scalar)
+    } else if let Some(array) = value.as_array() {
+        serde_json::Value::Array(array.values().map(to_json).collect())
+    } else if let Some(object) = value.as_object() {
+        serde_json::Value::Object(
+            object
+                .iter()
+                .map(|(k, v)| (k.to_string(), to_json(v)))
+                .collect(),
+        )
+    } else {
+        serde_json::Value::Null
+    }
+}
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "jsonify"]
+pub struct JsonifyFilter;
+
+impl Filter for JsonifyFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(to_json(input).to_string()))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "jsonify",
+    description = "Converts a value to json.",
+    parsed(JsonifyFilter)
+)]
+pub struct JsonifyFilterParser;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields::{DateTime, FieldValue};
+    use liquid::ParserBuilder;
+    use std::error::Error;
+
+    fn render(template: &str) -> Result<String, Box<dyn Error>> {
+        let parser = ParserBuilder::with_stdlib()
+            .filter(SlugifyFilterParser)
+            .filter(XmlEscapeFilterParser)
+            .filter(CsvEscapeFilterParser)
+            .filter(JsonEscapeFilterParser)
+            .filter(JsonifyFilterParser)
+            .build()?;
+        let mut post = liquid::object!({
+            "title": "Tom & Jerry's <Show>",
+            "views": 12,
+            "rating": 4.5,
+            "draft": false,
+            "tags": ["a", "b"],
+            "missing": liquid::model::Value::Nil,
+        });
+        // object! serializes values, which would turn dates into strings
+        post.insert(
+            "date".into(),
+            FieldValue::Date(DateTime::from("2024-01-31T10:00:00Z")?).to_value(),
+        );
+        let mut globals = liquid::Object::new();
+        globals.insert("post".into(), liquid::model::Value::Object(post));
+        Ok(parser.parse(template)?.render(&globals)?)
+    }
+
+    #[test]
+    fn slugify_and_escape() -> Result<(), Box<dyn Error>> {
+        assert_eq!(render("{{ post.title | slugify }}")?, "tom-jerry-s-show");
+        assert_eq!(
+            render("{{ post.title | xml_escape }}")?,
+            "Tom &amp; Jerry&apos;s &lt;Show&gt;"
+        );
+        assert_eq!(
+            render("{{ post.title | csv_escape }}")?,
+            "Tom & Jerry's <Show>"
+        );
+        assert_eq!(render("{{ 'a, \"b\"' | csv_escape }}")?, "\"a, \"\"b\"\"\"");
+        assert_eq!(render("{{ 'a \"b\"\n' | json_escape }}")?, "a \\\"b\\\"\\n");
+        Ok(())
+    }
+
+    #[test]
+    fn escape_output() -> Result<(), Box<dyn Error>> {
+        let mut formats = OutputFormats::new();
+        formats.insert(
+            "ics".to_string(),
+            crate::OutputFormat::new("text/calendar", Escaping::None),
+        );
+        let parser = ParserBuilder::with_stdlib()
+            .filter(EscapeOutputFilterParser { formats })
+            .build()?;
+        let template = parser.parse("{{ value | escape_output }}")?;
+        let render = |format: &str| {
+            template.render(&liquid::object!({
+                "value": "<a, \"b\">",
+                "output_format": format,
+            }))
+        };
+        assert_eq!(render("html")?, "&lt;a, &quot;b&quot;&gt;");
+        assert_eq!(render("csv")?, "\"<a, \"\"b\"\">\"");
+        assert_eq!(render("json")?, "<a, \\\"b\\\">");
+        assert_eq!(render("rss")?, "&lt;a, &quot;b&quot;&gt;");
+        assert_eq!(render("ics")?, "<a, \"b\">");
+        assert_eq!(render("txt")?, "<a, \"b\">");
+        Ok(())
+    }
+
+    #[test]
+    fn jsonify() -> Result<(), Box<dyn Error>> {
+        let json: serde_json::Value = serde_json::from_str(&render("{{ post | jsonify }}")?)?;
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "title": "Tom & Jerry's <Show>",
+                "views": 12,
+                "rating": 4.5,
+                "draft": false,
+                "date": "2024-01-31T10:00:00Z",
+                "tags": ["a", "b"],
+                "missing": null,
+            })
+        );
+        Ok(())
+    }
+}
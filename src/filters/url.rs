@@ -0,0 +1,303 @@
+use crate::assets::AssetManifest;
+use liquid_core::parser::{FilterArguments, ParameterReflection};
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+use liquid_core::{Error, Value, ValueView};
+
+/// The urls of a site, from its `site_url`. Sites hosted at a path, e.g.
+/// `https://example.com/blog`, have that path as their base path.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SiteUrls {
+    origin: Option<String>,
+    base_path: String,
+}
+
+impl SiteUrls {
+    pub fn new(site_url: Option<&str>) -> Self {
+        let Some(site_url) = site_url else {
+            return Self::default();
+        };
+        match url::Url::parse(site_url) {
+            Ok(url) if url.has_host() => Self {
+                origin: Some(url.origin().ascii_serialization()),
+                base_path: url.path().trim_end_matches('/').to_string(),
+            },
+            // Site urls without a scheme, e.g. `example.com/blog`
+            _ => match site_url.trim_end_matches('/').split_once('/') {
+                Some((host, path)) => Self {
+                    origin: Some(format!("https://{}", host)),
+                    base_path: format!("/{}", path),
+                },
+                None => Self {
+                    origin: Some(format!("https://{}", site_url.trim_end_matches('/'))),
+                    base_path: String::new(),
+                },
+            },
+        }
+    }
+
+    fn is_absolute(path: &str) -> bool {
+        path.starts_with("//") || url::Url::parse(path).is_ok()
+    }
+
+    /// The path from the root of the domain, including the base path.
+    pub fn relative_url(&self, path: &str) -> String {
+        if Self::is_absolute(path) {
+            return path.to_string();
+        }
+        format!("{}/{}", self.base_path, path.trim_start_matches('/'))
+    }
+
+    /// The path of a url within the site, without the base path, or None for
+    /// urls on other sites. Relative urls are returned as they are.
+    pub fn site_path(&self, url: &str) -> Option<String> {
+        let path = if Self::is_absolute(url) {
+            let parsed = url::Url::parse(url)
+                .or_else(|_| url::Url::parse(&format!("https:{}", url)))
+                .ok()?;
+            if Some(parsed.origin().ascii_serialization()) != self.origin {
+                return None;
+            }
+            let mut path = parsed.path().to_string();
+            if let Some(query) = parsed.query() {
+                path = format!("{}?{}", path, query);
+            }
+            if let Some(fragment) = parsed.fragment() {
+                path = format!("{}#{}", path, fragment);
+            }
+            path
+        } else {
+            url.to_string()
+        };
+        if !self.base_path.is_empty() {
+            if let Some(rest) = path.strip_prefix(&self.base_path) {
+                if rest.is_empty() || rest.starts_with(['/', '?', '#']) {
+                    return Some(match rest.starts_with('/') {
+                        true => rest.to_string(),
+                        false => format!("/{}", rest),
+                    });
+                }
+            }
+        }
+        Some(path)
+    }
+
+    /// The full url, or the relative url when the site has no url.
+    pub fn absolute_url(&self, path: &str) -> String {
+        if Self::is_absolute(path) {
+            return path.to_string();
+        }
+        format!(
+            "{}{}",
+            self.origin.as_deref().unwrap_or_default(),
+            self.relative_url(path)
+        )
+    }
+}
+
+pub(super) fn no_arguments(name: &str, mut arguments: FilterArguments) -> Result<()> {
+    if arguments.positional.next().is_some() || arguments.keyword.next().is_some() {
+        return Err(Error::with_msg(format!("{} takes no arguments", name)));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Display_filter)]
+#[name = "relative_url"]
+struct RelativeUrlFilter {
+    urls: SiteUrls,
+}
+
+impl Filter for RelativeUrlFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(self.urls.relative_url(&input.to_kstr())))
+    }
+}
+
+/// Prefixes a path with the base path of the site.
+#[derive(Debug, Clone)]
+pub struct RelativeUrlFilterParser {
+    pub urls: SiteUrls,
+}
+
+impl ParseFilter for RelativeUrlFilterParser {
+    fn parse(&self, arguments: FilterArguments) -> Result<Box<dyn Filter>> {
+        no_arguments("relative_url", arguments)?;
+        Ok(Box::new(RelativeUrlFilter {
+            urls: self.urls.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn FilterReflection {
+        self
+    }
+}
+
+impl FilterReflection for RelativeUrlFilterParser {
+    fn name(&self) -> &str {
+        "relative_url"
+    }
+    fn description(&self) -> &str {
+        "Prefixes a path with the base path of the site_url."
+    }
+    fn positional_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+    fn keyword_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+}
+
+#[derive(Debug, Clone, Display_filter)]
+#[name = "absolute_url"]
+struct AbsoluteUrlFilter {
+    urls: SiteUrls,
+}
+
+impl Filter for AbsoluteUrlFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(self.urls.absolute_url(&input.to_kstr())))
+    }
+}
+
+/// Prefixes a path with the site_url.
+#[derive(Debug, Clone)]
+pub struct AbsoluteUrlFilterParser {
+    pub urls: SiteUrls,
+}
+
+impl ParseFilter for AbsoluteUrlFilterParser {
+    fn parse(&self, arguments: FilterArguments) -> Result<Box<dyn Filter>> {
+        no_arguments("absolute_url", arguments)?;
+        Ok(Box::new(AbsoluteUrlFilter {
+            urls: self.urls.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn FilterReflection {
+        self
+    }
+}
+
+impl FilterReflection for AbsoluteUrlFilterParser {
+    fn name(&self) -> &str {
+        "absolute_url"
+    }
+    fn description(&self) -> &str {
+        "Prefixes a path with the site_url."
+    }
+    fn positional_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+    fn keyword_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+}
+
+#[derive(Debug, Clone, Display_filter)]
+#[name = "asset_url"]
+struct AssetUrlFilter {
+    assets: AssetManifest,
+}
+
+impl Filter for AssetUrlFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(self.assets.asset_url(&input.to_kstr())))
+    }
+}
+
+/// Maps a static file to its fingerprinted path.
+#[derive(Debug, Clone)]
+pub struct AssetUrlFilterParser {
+    pub assets: AssetManifest,
+}
+
+impl ParseFilter for AssetUrlFilterParser {
+    fn parse(&self, arguments: FilterArguments) -> Result<Box<dyn Filter>> {
+        no_arguments("asset_url", arguments)?;
+        Ok(Box::new(AssetUrlFilter {
+            assets: self.assets.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn FilterReflection {
+        self
+    }
+}
+
+impl FilterReflection for AssetUrlFilterParser {
+    fn name(&self) -> &str {
+        "asset_url"
+    }
+    fn description(&self) -> &str {
+        "Maps the path of a static file to its fingerprinted path, when fingerprint_assets is set."
+    }
+    fn positional_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+    fn keyword_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use liquid::ParserBuilder;
+    use std::error::Error;
+
+    fn render(template: &str, site_url: Option<&str>) -> Result<String, Box<dyn Error>> {
+        let urls = SiteUrls::new(site_url);
+        let parser = ParserBuilder::with_stdlib()
+            .filter(RelativeUrlFilterParser { urls: urls.clone() })
+            .filter(AbsoluteUrlFilterParser { urls })
+            .build()?;
+        Ok(parser.parse(template)?.render(&liquid::object!({}))?)
+    }
+
+    #[test]
+    fn urls() -> Result<(), Box<dyn Error>> {
+        let template = "{{ '/post/a.html' | relative_url }} {{ 'post/a.html' | absolute_url }}";
+        assert_eq!(
+            render(template, Some("https://example.com/blog/"))?,
+            "/blog/post/a.html https://example.com/blog/post/a.html"
+        );
+        assert_eq!(
+            render(template, Some("example.com"))?,
+            "/post/a.html https://example.com/post/a.html"
+        );
+        assert_eq!(render(template, None)?, "/post/a.html /post/a.html");
+        assert_eq!(
+            render(
+                "{{ 'https://other.com/a' | absolute_url }}",
+                Some("https://example.com")
+            )?,
+            "https://other.com/a"
+        );
+        assert!(render("{{ 'a' | absolute_url: 1 }}", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn asset_urls() -> Result<(), Box<dyn Error>> {
+        let assets = AssetManifest::default();
+        let file = std::path::Path::new("style/theme.css");
+        assets.insert(
+            file.to_path_buf(),
+            AssetManifest::fingerprinted_path(file, 0xabc),
+        );
+        let parser = ParserBuilder::with_stdlib()
+            .filter(AssetUrlFilterParser { assets })
+            .build()?;
+        let render = |template: &str| -> Result<String, Box<dyn Error>> {
+            Ok(parser.parse(template)?.render(&liquid::object!({}))?)
+        };
+        assert_eq!(
+            render("{{ '/style/theme.css' | asset_url }} {{ 'style/theme.css' | asset_url }}")?,
+            "/style/theme.0000000000000abc.css style/theme.0000000000000abc.css"
+        );
+        assert_eq!(render("{{ '/other.css' | asset_url }}")?, "/other.css");
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+use crate::{images::ImageVariantOptions, ArchivalError, FileSystemAPI};
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, ImageFormat};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+use tracing::warn;
+
+/// A resized or converted copy of a static image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageVariant {
+    /// The path of the variant from the root of the site, e.g.
+    /// `/images/a-png-400w.webp`.
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+}
+
+impl ImageVariant {
+    pub fn to_liquid(&self) -> liquid::model::Value {
+        liquid::model::Value::Object(liquid::object!({
+            "path": self.path,
+            "width": self.width,
+            "height": self.height,
+            "format": self.format,
+        }))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ProcessedImage {
+    hash: u64,
+    options: ImageVariantOptions,
+    variants: Vec<ImageVariant>,
+}
+
+/// The variants generated from static images, keyed by the path of the image
+/// in the static dir. Shared with the `image_variants` filter.
+#[derive(Debug, Clone, Default)]
+pub struct ImageVariants {
+    images: Arc<RwLock<HashMap<PathBuf, ProcessedImage>>>,
+}
+
+fn output_format(format: &str) -> Result<ImageFormat, Box<dyn Error>> {
+    match ImageFormat::from_extension(format) {
+        Some(f @ (ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP)) => Ok(f),
+        _ => {
+            Err(ArchivalError::new(&format!("unsupported image variant format {}", format)).into())
+        }
+    }
+}
+
+fn encode(
+    image: &DynamicImage,
+    format: ImageFormat,
+    options: &ImageVariantOptions,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut buf = vec![];
+    match format {
+        // Neither format supports every color type, e.g. jpegs have no alpha
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(
+            JpegEncoder::new_with_quality(&mut buf, options.quality.unwrap_or(80)),
+        )?,
+        ImageFormat::WebP => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_to(&mut Cursor::new(&mut buf), format)?,
+        _ => image.write_to(&mut Cursor::new(&mut buf), format)?,
+    }
+    Ok(buf)
+}
+
+impl ImageVariants {
+    /// Whether a static file is an image that variants are generated from.
+    pub fn is_image(file: &Path) -> bool {
+        file.extension()
+            .and_then(|e| e.to_str())
+            .and_then(ImageFormat::from_extension)
+            .is_some_and(|f| matches!(f, ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP))
+    }
+
+    /// The variants of a static image, from narrowest to widest.
+    pub fn get(&self, file: &Path) -> Vec<ImageVariant> {
+        self.images
+            .read()
+            .unwrap()
+            .get(file)
+            .map(|i| i.variants.clone())
+            .unwrap_or_default()
+    }
+
+    /// Writes the variants of a static image to the build dir, unless the
+    /// image and options are unchanged since they were last generated.
+    ///
+    /// Variants are named after the file name of the image, including its
+    /// extension, e.g. `a-png-400w.webp`, so that `a.png` and `a.jpg` don't
+    /// share variants.
+    pub fn process(
+        &self,
+        file: &Path,
+        content: &[u8],
+        hash: u64,
+        options: &ImageVariantOptions,
+        build_dir: &Path,
+        fs: &mut impl FileSystemAPI,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut images = self.images.write().unwrap();
+        if images
+            .get(file)
+            .is_some_and(|i| i.hash == hash && i.options == *options)
+        {
+            return Ok(());
+        }
+        if let Some(previous) = images.remove(file) {
+            delete_variants(&previous, build_dir, fs)?;
+        }
+        let image = match image::load_from_memory(content) {
+            Ok(image) => image,
+            Err(e) => {
+                warn!("failed reading image {}: {}", file.display(), e);
+                return Ok(());
+            }
+        };
+        let original_format = ImageFormat::from_path(file)?;
+        let formats = if options.formats.is_empty() {
+            vec![original_format]
+        } else {
+            options
+                .formats
+                .iter()
+                .map(|f| output_format(f))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        let mut widths: Vec<u32> = options
+            .widths
+            .iter()
+            .copied()
+            .filter(|w| *w > 0 && *w <= image.width())
+            .collect();
+        if widths.is_empty() {
+            widths.push(image.width());
+        }
+        widths.sort_unstable();
+        widths.dedup();
+        let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+        let source_extension = file.extension().unwrap_or_default().to_string_lossy();
+        let mut variants = vec![];
+        for width in widths {
+            let resized = if width == image.width() {
+                image.clone()
+            } else {
+                image.resize(width, u32::MAX, FilterType::Lanczos3)
+            };
+            for format in &formats {
+                // The original image is already copied
+                if width == image.width() && *format == original_format {
+                    continue;
+                }
+                let extension = format.extensions_str()[0];
+                let variant_file = file.with_file_name(format!(
+                    "{}-{}-{}w.{}",
+                    stem, source_extension, width, extension
+                ));
+                let dest = build_dir.join(&variant_file);
+                if let Some(dir) = dest.parent() {
+                    fs.create_dir_all(dir)?;
+                }
+                fs.write(&dest, encode(&resized, *format, options)?)?;
+                variants.push(ImageVariant {
+                    path: format!("/{}", variant_file.to_string_lossy().replace('\\', "/")),
+                    width: resized.width(),
+                    height: resized.height(),
+                    format: extension.to_string(),
+                });
+            }
+        }
+        images.insert(
+            file.to_path_buf(),
+            ProcessedImage {
+                hash,
+                options: options.clone(),
+                variants,
+            },
+        );
+        Ok(())
+    }
+
+    /// Removes the variants of images that are no longer in the static dir.
+    pub fn retain(
+        &self,
+        files: &HashSet<PathBuf>,
+        build_dir: &Path,
+        fs: &mut impl FileSystemAPI,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut images = self.images.write().unwrap();
+        let removed: Vec<PathBuf> = images
+            .keys()
+            .filter(|f| !files.contains(*f))
+            .cloned()
+            .collect();
+        for file in removed {
+            if let Some(image) = images.remove(&file) {
+                delete_variants(&image, build_dir, fs)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn delete_variants(
+    image: &ProcessedImage,
+    build_dir: &Path,
+    fs: &mut impl FileSystemAPI,
+) -> Result<(), Box<dyn Error>> {
+    for variant in &image.variants {
+        let path = build_dir.join(variant.path.trim_start_matches('/'));
+        if fs.exists(&path)? {
+            fs.delete(&path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryFileSystem;
+    use image::{Rgba, RgbaImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(width, height, Rgba([200, 100, 50, 255]));
+        let mut buf = vec![];
+        DynamicImage::ImageRgba8(image)
+            .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+            .unwrap();
+        buf
+    }
+
+    #[test]
+    fn variants() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let variants = ImageVariants::default();
+        let options = ImageVariantOptions {
+            widths: vec![100, 50, 400],
+            formats: vec!["webp".to_string(), "png".to_string()],
+            quality: None,
+        };
+        let build_dir = Path::new("dist");
+        let file = Path::new("images/a.png");
+        let content = png(200, 100);
+        variants.process(file, &content, 1, &options, build_dir, &mut fs)?;
+        let generated = variants.get(file);
+        assert_eq!(
+            generated
+                .iter()
+                .map(|v| (v.path.as_str(), v.width, v.height))
+                .collect::<Vec<_>>(),
+            vec![
+                ("/images/a-png-50w.webp", 50, 25),
+                ("/images/a-png-50w.png", 50, 25),
+                ("/images/a-png-100w.webp", 100, 50),
+                ("/images/a-png-100w.png", 100, 50),
+            ]
+        );
+        let webp = fs.read(Path::new("dist/images/a-png-50w.webp"))?.unwrap();
+        assert_eq!(image::guess_format(&webp)?, ImageFormat::WebP);
+
+        // Unchanged images are skipped
+        fs.delete(Path::new("dist/images/a-png-50w.webp"))?;
+        variants.process(file, &content, 1, &options, build_dir, &mut fs)?;
+        assert!(!fs.exists(Path::new("dist/images/a-png-50w.webp"))?);
+
+        // Unless their options changed
+        let options = ImageVariantOptions {
+            widths: vec![50],
+            formats: vec!["webp".to_string()],
+            quality: None,
+        };
+        variants.process(file, &content, 1, &options, build_dir, &mut fs)?;
+        assert!(fs.exists(Path::new("dist/images/a-png-50w.webp"))?);
+        assert!(!fs.exists(Path::new("dist/images/a-png-100w.png"))?);
+        assert_eq!(variants.get(file).len(), 1);
+
+        // Images with the same stem have their own variants
+        let jpg = Path::new("images/a.jpg");
+        variants.process(jpg, &content, 2, &options, build_dir, &mut fs)?;
+        assert_eq!(variants.get(jpg)[0].path, "/images/a-jpg-50w.webp");
+        variants.retain(&HashSet::from([jpg.to_path_buf()]), build_dir, &mut fs)?;
+        assert!(variants.get(file).is_empty());
+        assert!(!fs.exists(Path::new("dist/images/a-png-50w.webp"))?);
+        assert!(fs.exists(Path::new("dist/images/a-jpg-50w.webp"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_formats() {
+        let options = ImageVariantOptions {
+            formats: vec!["bmp".to_string()],
+            ..Default::default()
+        };
+        assert!(ImageVariants::default()
+            .process(
+                Path::new("a.png"),
+                &png(10, 10),
+                1,
+                &options,
+                Path::new("dist"),
+                &mut MemoryFileSystem::default(),
+            )
+            .is_err());
+        assert!(ImageVariants::is_image(Path::new("a.JPG")));
+        assert!(!ImageVariants::is_image(Path::new("a.svg")));
+    }
+}
@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Debug, sync::Arc};
+
+/// An image to generate urls for, usually an uploaded `File`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageSource {
+    pub url: String,
+    pub sha: String,
+    pub filename: String,
+}
+
+impl ImageSource {
+    /// An image that is only known by its url, e.g. a static image.
+    pub fn from_url(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            sha: String::new(),
+            filename: url
+                .split(['?', '#'])
+                .next()
+                .and_then(|u| u.rsplit('/').next())
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
+}
+
+/// How an image should be transformed. Unset options are left to the CDN.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageTransform {
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    /// How the image fits its width and height, e.g. `cover`. Values are
+    /// passed through, so they depend on the CDN.
+    pub fit: Option<String>,
+    pub quality: Option<i64>,
+    /// The image format, e.g. `webp` or `avif`.
+    pub format: Option<String>,
+}
+
+impl ImageTransform {
+    /// The set options, as (name, value) pairs.
+    pub fn options(&self) -> Vec<(&'static str, String)> {
+        [
+            ("width", self.width.map(|w| w.to_string())),
+            ("height", self.height.map(|h| h.to_string())),
+            ("fit", self.fit.clone()),
+            ("quality", self.quality.map(|q| q.to_string())),
+            ("format", self.format.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|v| (name, v)))
+        .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options().is_empty()
+    }
+}
+
+/// Generates the urls of transformed images. Sites configure a CDN's url
+/// scheme with ImageOptions, and applications may provide their own, see
+/// LiquidExtensions::image_urls.
+pub trait ImageUrls: Debug + Send + Sync {
+    fn transform(&self, image: &ImageSource, transform: &ImageTransform) -> String;
+
+    /// Whether urls are transformed at all. When they aren't, `srcset` only
+    /// lists the original image.
+    fn transforms(&self) -> bool {
+        true
+    }
+}
+
+/// The fallback when a site has no image CDN, which returns the original url.
+#[derive(Debug, Clone, Default)]
+pub struct OriginalImageUrls;
+
+impl ImageUrls for OriginalImageUrls {
+    fn transform(&self, image: &ImageSource, _transform: &ImageTransform) -> String {
+        image.url.to_string()
+    }
+
+    fn transforms(&self) -> bool {
+        false
+    }
+}
+
+/// The url scheme of an image CDN, set in the `[images]` section of the
+/// manifest, e.g. for Cloudflare:
+///
+/// ```toml
+/// [images]
+/// transform_url = "https://example.com/cdn-cgi/image/{options}/{url}"
+/// option_separator = ","
+/// ```
+///
+/// or imgix:
+///
+/// ```toml
+/// [images]
+/// transform_url = "https://example.imgix.net/{sha}?{options}"
+/// option_names = { width = "w", height = "h", quality = "q", format = "fm" }
+/// ```
+///
+/// `transform_url` may contain `{url}`, `{sha}`, `{filename}` and `{options}`.
+/// Options are written as `option_template`, `{name}={value}` by default, and
+/// joined with `option_separator`, `&` by default.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
+pub struct ImageOptions {
+    pub transform_url: Option<String>,
+    pub option_template: Option<String>,
+    pub option_separator: Option<String>,
+    /// The names the CDN uses for `width`, `height`, `fit`, `quality` and
+    /// `format`, when they differ.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub option_names: BTreeMap<String, String>,
+    /// Variants of the images in the static dir, see ImageVariantOptions.
+    pub variants: Option<ImageVariantOptions>,
+}
+
+/// Resized and converted copies of the images in the static dir, generated
+/// when the `image-processing` feature is enabled, e.g.
+///
+/// ```toml
+/// [images.variants]
+/// widths = [400, 800]
+/// formats = ["webp"]
+/// ```
+///
+/// generates `images/a-jpg-400w.webp` and `images/a-jpg-800w.webp` from
+/// `images/a.jpg`. Images are never enlarged, and variants keep the format of
+/// the original when no formats are set.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
+pub struct ImageVariantOptions {
+    #[serde(default)]
+    pub widths: Vec<u32>,
+    #[serde(default)]
+    pub formats: Vec<String>,
+    /// The quality of jpeg variants, from 1 to 100.
+    pub quality: Option<u8>,
+}
+
+impl ImageOptions {
+    pub fn from_toml(table: &toml::Table) -> Result<Self, toml::de::Error> {
+        toml::Value::Table(table.clone()).try_into()
+    }
+
+    pub fn to_toml(&self) -> toml::Table {
+        match toml::Value::try_from(self) {
+            Ok(toml::Value::Table(t)) => t,
+            _ => toml::Table::new(),
+        }
+    }
+
+    /// The image urls for these options, which return original urls when no
+    /// `transform_url` is set.
+    pub fn image_urls(&self) -> Arc<dyn ImageUrls> {
+        match self.transform_url {
+            Some(_) => Arc::new(self.clone()),
+            None => Arc::new(OriginalImageUrls),
+        }
+    }
+}
+
+impl ImageUrls for ImageOptions {
+    fn transform(&self, image: &ImageSource, transform: &ImageTransform) -> String {
+        let Some(transform_url) = &self.transform_url else {
+            return image.url.to_string();
+        };
+        if transform.is_empty() {
+            return image.url.to_string();
+        }
+        let template = self.option_template.as_deref().unwrap_or("{name}={value}");
+        let options = transform
+            .options()
+            .into_iter()
+            .map(|(name, value)| {
+                let name = self.option_names.get(name).map_or(name, |n| n.as_str());
+                template.replace("{name}", name).replace("{value}", &value)
+            })
+            .collect::<Vec<String>>()
+            .join(self.option_separator.as_deref().unwrap_or("&"));
+        transform_url
+            .replace("{url}", &image.url)
+            .replace("{sha}", &image.sha)
+            .replace("{filename}", &image.filename)
+            .replace("{options}", &options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image() -> ImageSource {
+        ImageSource {
+            url: "https://uploads.archival.dev/abc".to_string(),
+            sha: "abc".to_string(),
+            filename: "cover.jpg".to_string(),
+        }
+    }
+
+    fn transform() -> ImageTransform {
+        ImageTransform {
+            width: Some(800),
+            format: Some("webp".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn url_schemes() -> Result<(), Box<dyn std::error::Error>> {
+        let cloudflare = ImageOptions::from_toml(&toml::from_str(
+            "transform_url = \"https://example.com/cdn-cgi/image/{options}/{url}\"\noption_separator = \",\"",
+        )?)?;
+        assert_eq!(
+            cloudflare.transform(&image(), &transform()),
+            "https://example.com/cdn-cgi/image/width=800,format=webp/https://uploads.archival.dev/abc"
+        );
+        let imgix = ImageOptions::from_toml(&toml::from_str(
+            "transform_url = \"https://example.imgix.net/{sha}?{options}\"\noption_names = { width = \"w\", format = \"fm\" }",
+        )?)?;
+        assert_eq!(
+            imgix.transform(&image(), &transform()),
+            "https://example.imgix.net/abc?w=800&fm=webp"
+        );
+        let cloudinary = ImageOptions::from_toml(&toml::from_str(
+            "transform_url = \"https://res.cloudinary.com/demo/image/upload/{options}/{filename}\"\noption_template = \"{name}_{value}\"\noption_separator = \",\"\noption_names = { width = \"w\", format = \"f\" }",
+        )?)?;
+        assert_eq!(
+            cloudinary.transform(&image(), &transform()),
+            "https://res.cloudinary.com/demo/image/upload/w_800,f_webp/cover.jpg"
+        );
+        assert_eq!(
+            imgix.transform(&image(), &ImageTransform::default()),
+            image().url
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn original_urls() {
+        let urls = ImageOptions::default().image_urls();
+        assert!(!urls.transforms());
+        assert_eq!(urls.transform(&image(), &transform()), image().url);
+        assert_eq!(ImageSource::from_url("/images/a.png?v=1").filename, "a.png");
+    }
+}
@@ -202,4 +202,154 @@ pub mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn json_schema_list_fields() -> Result<(), Box<dyn Error>> {
+        let table: Table = toml::from_str(
+            "[post]
+            tags = \"string[]\"
+            scores = \"number[]\"",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        let schema = generate_json_schema(
+            "post",
+            defs.get("post").unwrap(),
+            ObjectSchemaOptions::default(),
+        );
+        let schema_value = &schema.into();
+        assert!(jsonschema::is_valid(
+            schema_value,
+            &json!({"tags": ["a", "b"], "scores": [1, 2.5]})
+        ));
+        assert!(!jsonschema::is_valid(
+            schema_value,
+            &json!({"tags": [1], "scores": []})
+        ));
+        assert!(!jsonschema::is_valid(schema_value, &json!({"tags": "a"})));
+        Ok(())
+    }
+
+    #[test]
+    fn json_schema_format_fields() -> Result<(), Box<dyn Error>> {
+        let table: Table = toml::from_str(
+            "[post]
+            accent = \"color\"
+            website = \"url\"
+            contact = \"email\"",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        let schema = generate_json_schema(
+            "post",
+            defs.get("post").unwrap(),
+            ObjectSchemaOptions::default(),
+        );
+        assert_eq!(schema["properties"]["website"]["format"], "uri");
+        assert_eq!(schema["properties"]["contact"]["format"], "email");
+        let schema_value = &schema.into();
+        assert!(jsonschema::is_valid(
+            schema_value,
+            &json!({"accent": "#ff8800", "website": "https://archival.dev/"})
+        ));
+        assert!(!jsonschema::is_valid(
+            schema_value,
+            &json!({"accent": "#FF8800"})
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn json_schema_location_fields() -> Result<(), Box<dyn Error>> {
+        let table: Table = toml::from_str(
+            "[event]
+            venue = \"location\"",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        let schema = generate_json_schema(
+            "event",
+            defs.get("event").unwrap(),
+            ObjectSchemaOptions::default(),
+        );
+        let schema_value = &schema.into();
+        assert!(jsonschema::is_valid(
+            schema_value,
+            &json!({"venue": {"lat": 45.5, "lng": -122.6, "address": "1332 W Burnside St"}})
+        ));
+        assert!(!jsonschema::is_valid(
+            schema_value,
+            &json!({"venue": {"lat": 95, "lng": -122.6}})
+        ));
+        assert!(!jsonschema::is_valid(
+            schema_value,
+            &json!({"venue": {"lat": 45.5}})
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn json_schema_blocks() -> Result<(), Box<dyn Error>> {
+        let table: Table = toml::from_str(
+            "[landing]
+            [landing.sections._blocks.hero]
+            heading = \"string\"
+            [landing.sections._blocks.quote]
+            text = \"string\"",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        let schema = generate_json_schema(
+            "landing",
+            defs.get("landing").unwrap(),
+            ObjectSchemaOptions::default(),
+        );
+        let schema_value = &schema.into();
+        assert!(jsonschema::is_valid(
+            schema_value,
+            &json!({"sections": [
+                {"type": "hero", "heading": "Welcome"},
+                {"type": "quote", "text": "Hi"}
+            ]})
+        ));
+        // Fields must match the block type
+        assert!(!jsonschema::is_valid(
+            schema_value,
+            &json!({"sections": [{"type": "hero", "text": "Hi"}]})
+        ));
+        assert!(!jsonschema::is_valid(
+            schema_value,
+            &json!({"sections": [{"type": "gallery"}]})
+        ));
+        assert!(!jsonschema::is_valid(
+            schema_value,
+            &json!({"sections": [{"heading": "Welcome"}]})
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn json_schema_meta_schemas() -> Result<(), Box<dyn Error>> {
+        let table: Table = toml::from_str(
+            "[post]
+            details = \"meta\"
+            _meta_schemas = { details = { rating = \"number\", links = [{ url = \"string\" }] } }",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        let schema = generate_json_schema(
+            "post",
+            defs.get("post").unwrap(),
+            ObjectSchemaOptions::default(),
+        );
+        let schema_value = &schema.into();
+        assert!(jsonschema::is_valid(
+            schema_value,
+            &json!({"details": {"rating": 4, "links": [{"url": "https://archival.dev"}]}})
+        ));
+        assert!(!jsonschema::is_valid(
+            schema_value,
+            &json!({"details": {"rating": "four"}})
+        ));
+        assert!(!jsonschema::is_valid(
+            schema_value,
+            &json!({"details": {"links": [{"href": "https://archival.dev"}]}})
+        ));
+        Ok(())
+    }
 }
@@ -1,24 +1,36 @@
 mod archival_error;
+mod assets;
 mod file_system;
 mod file_system_memory;
 mod file_system_mutex;
 #[cfg(test)]
 mod file_system_tests;
 mod filters;
+#[cfg(feature = "image-processing")]
+mod image_processing;
+mod images;
+mod link_check;
+mod links;
 mod liquid_parser;
 pub mod manifest;
+mod minify;
 mod object_definition;
+mod output_format;
 mod page;
 mod read_toml;
 mod reserved_fields;
+mod shortcodes;
 mod site;
 mod tags;
+mod taxonomy;
 #[cfg(test)]
 mod test_utils;
+mod trash;
 mod value_path;
 pub use constants::{MANIFEST_FILE_NAME, MIN_COMPAT_VERSION};
 use events::{
     AddObjectEvent, ArchivalEvent, ChildEvent, DeleteObjectEvent, EditFieldEvent, EditOrderEvent,
+    MoveChildEvent, RestoreObjectEvent,
 };
 use events::{AddRootObjectEvent, ArchivalEventResponse};
 pub use fields::FieldConfig;
@@ -56,9 +68,17 @@ pub use archival_error::ArchivalError;
 pub use file_system::unpack_zip;
 pub use file_system::FileSystemAPI;
 pub use file_system_memory::MemoryFileSystem;
+pub use images::{
+    ImageOptions, ImageSource, ImageTransform, ImageUrls, ImageVariantOptions, OriginalImageUrls,
+};
 #[cfg(feature = "json-schema")]
 pub use json_schema::{ObjectSchema, ObjectSchemaOptions};
-pub use object_definition::ObjectDefinition;
+pub use link_check::{BrokenLink, BrokenLinkKind};
+pub use liquid_parser::LiquidExtensions;
+pub use minify::MinifyStats;
+pub use object_definition::{ObjectDefinition, ObjectTemplate};
+pub use output_format::{Escaping, OutputFormat, OutputFormats};
+pub use trash::{TrashError, TrashedObject};
 
 pub type ArchivalBuildId = u64;
 
@@ -129,6 +149,27 @@ impl<F: FileSystemAPI + Clone + Debug> Archival<F> {
             last_build_id: Cell::new(0),
         })
     }
+    /// Adds a liquid filter that pages may use, see LiquidExtensions.
+    pub fn register_filter(&mut self, filter: impl Into<Box<dyn liquid_core::ParseFilter>>) {
+        self.site.liquid_extensions.add_filter(filter);
+        self.last_build_id.replace(0);
+    }
+    /// Sets how the `image_url` and `srcset` filters generate urls, instead of
+    /// the `[images]` section of the manifest.
+    pub fn set_image_urls(&mut self, urls: impl ImageUrls + 'static) {
+        self.site.liquid_extensions.set_image_urls(urls);
+        self.last_build_id.replace(0);
+    }
+    /// Adds a liquid tag that pages may use, see LiquidExtensions.
+    pub fn register_tag(&mut self, tag: impl Into<Box<dyn liquid_core::ParseTag>>) {
+        self.site.liquid_extensions.add_tag(tag);
+        self.last_build_id.replace(0);
+    }
+    /// Adds a liquid block that pages may use, see LiquidExtensions.
+    pub fn register_block(&mut self, block: impl Into<Box<dyn liquid_core::ParseBlock>>) {
+        self.site.liquid_extensions.add_block(block);
+        self.last_build_id.replace(0);
+    }
     pub fn build(&self, options: BuildOptions) -> Result<ArchivalBuildId, Box<dyn Error>> {
         debug!("build {} {:#?}", self.site, options);
         let build_id = self.fs_mutex.with_fs(|fs| {
@@ -144,6 +185,11 @@ impl<F: FileSystemAPI + Clone + Debug> Archival<F> {
         self.last_build_id.replace(build_id);
         Ok(build_id)
     }
+    /// Checks that internal links in the build output resolve, see
+    /// Site::check_links.
+    pub fn check_links(&self) -> Result<Vec<BrokenLink>, Box<dyn Error>> {
+        self.fs_mutex.with_fs(|fs| self.site.check_links(fs))
+    }
     #[cfg(feature = "json-schema")]
     pub fn dump_schemas(&self) -> Result<(), Box<dyn Error>> {
         debug!("dump schemas {}", self.site);
@@ -219,6 +265,8 @@ impl<F: FileSystemAPI + Clone + Debug> Archival<F> {
         for object in fs.walk_dir(objects_dir, false)? {
             maybe_hash(&objects_dir.join(object))?;
         }
+        // Pages link to fingerprinted assets, which change with static files
+        hasher.write(self.site.assets().to_json().as_bytes());
         Ok(hasher.finish())
     }
     fn object_path_impl(
@@ -317,10 +365,12 @@ impl<F: FileSystemAPI + Clone + Debug> Archival<F> {
             ArchivalEvent::AddObject(event) => self.add_object(event)?,
             ArchivalEvent::AddRootObject(event) => self.add_root_object(event)?,
             ArchivalEvent::DeleteObject(event) => self.delete_object(event)?,
+            ArchivalEvent::RestoreObject(event) => self.restore_object(event)?,
             ArchivalEvent::EditField(event) => self.edit_field(event)?,
             ArchivalEvent::EditOrder(event) => self.edit_order(event)?,
             ArchivalEvent::AddChild(event) => self.add_child(event)?,
             ArchivalEvent::RemoveChild(event) => self.remove_child(event)?,
+            ArchivalEvent::MoveChild(event) => self.move_child(event)?,
         };
         if let Some(build_options) = build_options {
             self.build(build_options)?;
@@ -434,13 +484,58 @@ impl<F: FileSystemAPI + Clone + Debug> Archival<F> {
             )))?;
         self.fs_mutex.with_fs(|fs| {
             let path = self.object_path_impl(&obj_def.name, &event.filename, fs)?;
-            fs.delete(&path)?;
+            if let Some(trash_dir) = &self.site.manifest.trash_dir {
+                let root = self.site.manifest.root();
+                let trashed = TrashedObject::new(
+                    &obj_def.name,
+                    &event.filename,
+                    path.strip_prefix(root).unwrap_or(&path),
+                    event.source,
+                );
+                trash::trash_object(root, trash_dir, &trashed, fs)?;
+            } else {
+                fs.delete(&path)?;
+            }
             self.site.invalidate_file(&path);
             Ok(())
         })?;
         Ok(ArchivalEventResponse::None)
     }
 
+    fn restore_object(
+        &self,
+        event: RestoreObjectEvent,
+    ) -> Result<ArchivalEventResponse, Box<dyn Error>> {
+        let trash_dir = self
+            .site
+            .manifest
+            .trash_dir
+            .as_ref()
+            .ok_or(TrashError::NotEnabled)?;
+        self.fs_mutex.with_fs(|fs| {
+            let root = self.site.manifest.root();
+            let restored =
+                trash::restore_object(root, trash_dir, &event.object, &event.filename, fs)?;
+            self.site
+                .invalidate_file(&root.join(&restored.original_path));
+            Ok(())
+        })?;
+        Ok(ArchivalEventResponse::None)
+    }
+
+    /// Lists objects that have been deleted while trash mode (the manifest's
+    /// `trash_dir`) was enabled and have not yet been purged.
+    pub fn trashed_objects(&self) -> Result<Vec<TrashedObject>, Box<dyn Error>> {
+        self.fs_mutex.with_fs(|fs| self.site.trashed_objects(fs))
+    }
+
+    /// Permanently deletes trashed objects, either all of them or only those
+    /// of the given object type. Returns the number of objects purged.
+    pub fn purge_trash(&self, object: Option<&str>) -> Result<usize, Box<dyn Error>> {
+        self.fs_mutex
+            .with_fs(|fs| self.site.purge_trash(object, fs))
+    }
+
     pub fn manifest_content(&self) -> Result<String, Box<dyn Error>> {
         self.fs_mutex.with_fs(|fs| self.site.manifest_content(fs))
     }
@@ -458,11 +553,41 @@ impl<F: FileSystemAPI + Clone + Debug> Archival<F> {
     }
 
     fn edit_field(&self, event: EditFieldEvent) -> Result<ArchivalEventResponse, Box<dyn Error>> {
+        let manifest = &self.site.manifest;
+        let locale = event
+            .locale
+            .filter(|l| manifest.default_locale() != Some(l.as_str()));
+        if let Some(locale) = &locale {
+            if !manifest.locales.contains(locale) {
+                return Err(ArchivalError::new(&format!("unknown locale: {}", locale)).into());
+            }
+        }
+        let obj_def = self
+            .site
+            .object_definitions
+            .get(&event.object)
+            .ok_or(ArchivalError::new(&format!(
+                "object not found: {}",
+                event.object
+            )))?;
         self.write_object(&event.object, &event.filename, |existing| {
-            event
-                .path
-                .append((&event.field).into())
-                .set_in_object(existing, event.value);
+            let field_def = event.path.get_object_definition(obj_def, existing)?;
+            if locale.is_some() && !field_def.is_translatable(&event.field) {
+                return Err(ArchivalError::new(&format!(
+                    "field {} of {} is not translatable",
+                    event.field, event.object
+                ))
+                .into());
+            }
+            let value = match (event.value, field_def.fields.get(&event.field)) {
+                (Some(value), Some(field_type)) => Some(value.normalized(field_type)?),
+                (value, _) => value,
+            };
+            let path = event.path.append((&event.field).into());
+            match &locale {
+                Some(locale) => path.set_in_translation(existing, locale, value),
+                None => path.set_in_object(existing, value),
+            }
             Ok(existing)
         })?;
         Ok(ArchivalEventResponse::None)
@@ -486,7 +611,9 @@ impl<F: FileSystemAPI + Clone + Debug> Archival<F> {
             )))?;
         let mut added_idx = usize::MAX;
         self.write_object(&event.object, &event.filename, |existing| {
-            added_idx = event.path.add_child(existing, obj_def)?;
+            added_idx = event
+                .path
+                .add_child(existing, obj_def, event.block.as_deref())?;
             Ok(existing)
         })?;
         Ok(ArchivalEventResponse::Index(added_idx))
@@ -499,6 +626,14 @@ impl<F: FileSystemAPI + Clone + Debug> Archival<F> {
         })?;
         Ok(ArchivalEventResponse::None)
     }
+    fn move_child(&self, event: MoveChildEvent) -> Result<ArchivalEventResponse, Box<dyn Error>> {
+        self.write_object(&event.object, &event.filename, move |existing| {
+            let mut path = event.path;
+            path.move_child(existing, event.to)?;
+            Ok(existing)
+        })?;
+        Ok(ArchivalEventResponse::None)
+    }
 
     fn write_object(
         &self,
@@ -699,6 +834,7 @@ mod lib {
                 path: ValuePath::empty(),
                 field: "name".to_string(),
                 value: Some(FieldValue::String("This is the new name".to_string())),
+                locale: None,
                 source: None,
             }),
             Some(BuildOptions::default()),
@@ -744,6 +880,206 @@ mod lib {
         Ok(())
     }
 
+    #[test]
+    fn trash_and_restore_object() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        let mut archival = Archival::new(fs)?;
+        archival.modify_manifest(|m| {
+            m.trash_dir = Some(m.objects_dir.with_file_name("trash"));
+        })?;
+        archival.send_event(
+            ArchivalEvent::DeleteObject(DeleteObjectEvent {
+                object: "section".to_string(),
+                filename: "first".to_string(),
+                source: Some("test".to_string()),
+            }),
+            Some(BuildOptions::default()),
+        )?;
+        let objects = archival.get_objects()?;
+        assert_eq!(objects["section"].into_iter().count(), 1);
+        assert!(!archival.object_exists("section", "first")?);
+        let trashed = archival.trashed_objects()?;
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].object, "section");
+        assert_eq!(trashed[0].filename, "first");
+        assert_eq!(trashed[0].source, Some("test".to_string()));
+        archival.send_event(
+            ArchivalEvent::RestoreObject(RestoreObjectEvent {
+                object: "section".to_string(),
+                filename: "first".to_string(),
+                source: None,
+            }),
+            Some(BuildOptions::default()),
+        )?;
+        let objects = archival.get_objects()?;
+        assert_eq!(objects["section"].into_iter().count(), 2);
+        assert!(archival.object_exists("section", "first")?);
+        assert_eq!(archival.trashed_objects()?.len(), 0);
+        // Restoring something that isn't in the trash fails
+        assert!(archival
+            .send_event(
+                ArchivalEvent::RestoreObject(RestoreObjectEvent {
+                    object: "section".to_string(),
+                    filename: "first".to_string(),
+                    source: None,
+                }),
+                None,
+            )
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn trash_same_filename_twice() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        let mut archival = Archival::new(fs)?;
+        // The trash can't be inside the objects dir, where it would be
+        // loaded as objects
+        assert!(archival
+            .modify_manifest(|m| m.trash_dir = Some(m.objects_dir.join("trash")))
+            .is_err());
+        assert_eq!(archival.site.manifest.trash_dir, None);
+        archival.modify_manifest(|m| {
+            m.trash_dir = Some(m.objects_dir.with_file_name("trash"));
+        })?;
+        let delete = ArchivalEvent::DeleteObject(DeleteObjectEvent {
+            object: "section".to_string(),
+            filename: "first".to_string(),
+            source: None,
+        });
+        archival.send_event(delete.clone(), None)?;
+        archival.send_event(
+            ArchivalEvent::AddObject(AddObjectEvent {
+                object: "section".to_string(),
+                filename: "first".to_string(),
+                order: 1,
+                values: vec![AddObjectValue {
+                    path: ValuePath::from_string("name"),
+                    value: FieldValue::String("second first".to_string()),
+                }],
+            }),
+            None,
+        )?;
+        archival.send_event(delete, None)?;
+        let trashed = archival.trashed_objects()?;
+        assert_eq!(trashed.len(), 2);
+        assert!(trashed
+            .iter()
+            .all(|t| t.original_path == Path::new("objects/section/first.toml")));
+        // The most recently trashed one is restored first
+        let restore = ArchivalEvent::RestoreObject(RestoreObjectEvent {
+            object: "section".to_string(),
+            filename: "first".to_string(),
+            source: None,
+        });
+        archival.send_event(restore.clone(), None)?;
+        let first = archival.get_objects()?["section"]
+            .into_iter()
+            .find(|o| o.filename == "first")
+            .cloned()
+            .unwrap();
+        assert_eq!(
+            first.values.get("name"),
+            Some(&FieldValue::String("second first".to_string()))
+        );
+        // Restoring the older one conflicts with the restored object
+        assert!(archival.send_event(restore, None).is_err());
+        assert_eq!(archival.trashed_objects()?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn trash_filename_with_suffix() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        let mut archival = Archival::new(fs)?;
+        archival.modify_manifest(|m| {
+            m.trash_dir = Some(m.objects_dir.with_file_name("trash"));
+        })?;
+        let add = |filename: &str, name: &str| {
+            ArchivalEvent::AddObject(AddObjectEvent {
+                object: "section".to_string(),
+                filename: filename.to_string(),
+                order: 1,
+                values: vec![AddObjectValue {
+                    path: ValuePath::from_string("name"),
+                    value: FieldValue::String(name.to_string()),
+                }],
+            })
+        };
+        let delete = |filename: &str| {
+            ArchivalEvent::DeleteObject(DeleteObjectEvent {
+                object: "section".to_string(),
+                filename: filename.to_string(),
+                source: None,
+            })
+        };
+        let restore = |filename: &str| {
+            ArchivalEvent::RestoreObject(RestoreObjectEvent {
+                object: "section".to_string(),
+                filename: filename.to_string(),
+                source: None,
+            })
+        };
+        // Trash `first` twice, then an object that is really named `first.1`
+        archival.send_event(delete("first"), None)?;
+        archival.send_event(add("first", "second first"), None)?;
+        archival.send_event(delete("first"), None)?;
+        archival.send_event(add("first.1", "dotted"), None)?;
+        archival.send_event(delete("first.1"), None)?;
+        assert_eq!(archival.trashed_objects()?.len(), 3);
+        let name_of = |archival: &Archival<MemoryFileSystem>, filename: &str| {
+            archival.get_objects().unwrap()["section"]
+                .into_iter()
+                .find(|o| o.filename == filename)
+                .and_then(|o| o.values.get("name").cloned())
+        };
+        archival.send_event(restore("first.1"), None)?;
+        assert_eq!(
+            name_of(&archival, "first.1"),
+            Some(FieldValue::String("dotted".to_string()))
+        );
+        archival.send_event(restore("first"), None)?;
+        assert_eq!(
+            name_of(&archival, "first"),
+            Some(FieldValue::String("second first".to_string()))
+        );
+        assert_eq!(archival.trashed_objects()?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn purge_trash() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        let mut archival = Archival::new(fs)?;
+        assert!(archival.purge_trash(None).is_err());
+        archival.modify_manifest(|m| {
+            m.trash_dir = Some(m.objects_dir.with_file_name("trash"));
+        })?;
+        for filename in ["first", "second"] {
+            archival.send_event(
+                ArchivalEvent::DeleteObject(DeleteObjectEvent {
+                    object: "section".to_string(),
+                    filename: filename.to_string(),
+                    source: None,
+                }),
+                None,
+            )?;
+        }
+        assert_eq!(archival.trashed_objects()?.len(), 2);
+        assert_eq!(archival.purge_trash(Some("post"))?, 0);
+        assert_eq!(archival.purge_trash(None)?, 2);
+        assert_eq!(archival.trashed_objects()?.len(), 0);
+        Ok(())
+    }
+
     #[test]
     #[traced_test]
     fn edit_object_order() -> Result<(), Box<dyn Error>> {
@@ -810,6 +1146,7 @@ mod lib {
                     object: "post".to_string(),
                     filename: "a-post".to_string(),
                     path: ValuePath::default().append(ValuePathComponent::key("links")),
+                    block: None,
                     source: None,
                 }),
                 Some(BuildOptions::default()),
@@ -863,6 +1200,7 @@ mod lib {
                     path: ValuePath::default()
                         .append(ValuePathComponent::key("links"))
                         .append(ValuePathComponent::Index(0)),
+                    block: None,
                     source: None,
                 }),
                 Some(BuildOptions::default()),
@@ -888,6 +1226,497 @@ mod lib {
         Ok(())
     }
 
+    #[test]
+    fn build_localized_site() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        let objects_toml = fs.read_to_string(Path::new("objects.toml"))?.unwrap();
+        fs.write_str(
+            Path::new("objects.toml"),
+            objects_toml.replace(
+                "[section]\n",
+                "[section]\n_translatable = [\"name\", \"body\"]\n",
+            ),
+        )?;
+        let mut archival = Archival::new(fs)?;
+        archival.modify_manifest(|m| {
+            m.locales = vec!["en".to_string(), "es".to_string()];
+        })?;
+        archival.send_event(
+            ArchivalEvent::EditField(EditFieldEvent {
+                object: "section".to_string(),
+                filename: "first".to_string(),
+                path: ValuePath::empty(),
+                field: "name".to_string(),
+                value: Some(FieldValue::String("Algo de Contenido".to_string())),
+                locale: Some("es".to_string()),
+                source: None,
+            }),
+            Some(BuildOptions::default()),
+        )?;
+        // Non-translatable fields cannot be edited per-locale
+        assert!(archival
+            .send_event(
+                ArchivalEvent::EditField(EditFieldEvent {
+                    object: "site".to_string(),
+                    filename: "site".to_string(),
+                    path: ValuePath::empty(),
+                    field: "name".to_string(),
+                    value: Some(FieldValue::String("Sitio".to_string())),
+                    locale: Some("es".to_string()),
+                    source: None,
+                }),
+                None,
+            )
+            .is_err());
+        let build_dir = &archival.site.manifest.build_dir;
+        let en_html = archival
+            .fs_mutex
+            .with_fs(|fs| fs.read_to_string(&build_dir.join("en").join("index.html")))?
+            .unwrap();
+        let es_html = archival
+            .fs_mutex
+            .with_fs(|fs| fs.read_to_string(&build_dir.join("es").join("index.html")))?
+            .unwrap();
+        assert!(en_html.contains("Some Content"));
+        assert!(!en_html.contains("Algo de Contenido"));
+        assert!(es_html.contains("Algo de Contenido"));
+        assert!(es_html.contains("More Content"));
+        assert!(archival.dist_file(&build_dir.join("index.html")).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn edit_and_render_blocks() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        let objects_toml = fs.read_to_string(Path::new("objects.toml"))?.unwrap();
+        fs.write_str(
+            Path::new("objects.toml"),
+            format!(
+                "{}\n[post.sections._blocks.hero]\nheading = \"string\"\n[post.sections._blocks.quote]\ntext = \"string\"\n",
+                objects_toml
+            ),
+        )?;
+        fs.write_str(
+            Path::new("pages/post.liquid"),
+            "{% blocks post.sections %}".to_string(),
+        )?;
+        fs.write_str(
+            Path::new("pages/blocks/_hero.liquid"),
+            "<h1>{{ block.heading }}</h1>".to_string(),
+        )?;
+        fs.write_str(
+            Path::new("pages/blocks/_quote.liquid"),
+            "<q>{{ block.text }}</q>".to_string(),
+        )?;
+        let archival = Archival::new(fs)?;
+        let sections = ValuePath::default().append(ValuePathComponent::key("sections"));
+        for (block, field, value) in [("hero", "heading", "Welcome"), ("quote", "text", "Hi")] {
+            let r = archival.send_event(
+                ArchivalEvent::AddChild(ChildEvent {
+                    object: "post".to_string(),
+                    filename: "a-post".to_string(),
+                    path: sections.clone(),
+                    block: Some(block.to_string()),
+                    source: None,
+                }),
+                None,
+            )?;
+            let ArchivalEventResponse::Index(index) = r else {
+                panic!("no index returned");
+            };
+            archival.send_event(
+                ArchivalEvent::EditField(EditFieldEvent {
+                    object: "post".to_string(),
+                    filename: "a-post".to_string(),
+                    path: sections.clone().append(ValuePathComponent::Index(index)),
+                    field: field.to_string(),
+                    value: Some(FieldValue::String(value.to_string())),
+                    locale: None,
+                    source: None,
+                }),
+                None,
+            )?;
+        }
+        // Adding an unknown block type fails
+        assert!(archival
+            .send_event(
+                ArchivalEvent::AddChild(ChildEvent {
+                    object: "post".to_string(),
+                    filename: "a-post".to_string(),
+                    path: sections.clone(),
+                    block: Some("gallery".to_string()),
+                    source: None,
+                }),
+                None,
+            )
+            .is_err());
+        archival.send_event(
+            ArchivalEvent::MoveChild(MoveChildEvent {
+                object: "post".to_string(),
+                filename: "a-post".to_string(),
+                path: sections.clone().append(ValuePathComponent::Index(1)),
+                to: 0,
+                source: None,
+            }),
+            Some(BuildOptions::default()),
+        )?;
+        let post_html = archival
+            .fs_mutex
+            .with_fs(|fs| {
+                fs.read_to_string(
+                    &archival
+                        .site
+                        .manifest
+                        .build_dir
+                        .join(Path::new("post/a-post.html")),
+                )
+            })?
+            .unwrap();
+        assert_eq!(post_html, "<q>Hi</q><h1>Welcome</h1>");
+        archival.send_event(
+            ArchivalEvent::RemoveChild(ChildEvent {
+                object: "post".to_string(),
+                filename: "a-post".to_string(),
+                path: sections.clone().append(ValuePathComponent::Index(0)),
+                block: None,
+                source: None,
+            }),
+            None,
+        )?;
+        let objects = archival.get_objects()?;
+        let post = objects["post"]
+            .into_iter()
+            .find(|o| o.filename == "a-post")
+            .unwrap();
+        let Some(FieldValue::Objects(blocks)) = post.values.get("sections") else {
+            panic!("sections not found");
+        };
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(
+            blocks[0].get("type"),
+            Some(&FieldValue::String("hero".to_string()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn build_with_multiple_templates() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        let objects_toml = fs.read_to_string(Path::new("objects.toml"))?.unwrap();
+        fs.write_str(
+            Path::new("objects.toml"),
+            objects_toml.replace(
+                "template = \"post\"",
+                "template = { html = \"post\", json = \"post_api\", \"print.html\" = \"post_print\" }",
+            ),
+        )?;
+        fs.write_str(
+            Path::new("pages/post_api.liquid"),
+            "{\"title\": \"{{ post.title | escape_output }}\"}".to_string(),
+        )?;
+        fs.write_str(
+            Path::new("pages/post_print.liquid"),
+            "<h1>{{ post.title }}</h1>".to_string(),
+        )?;
+        let archival = Archival::new(fs)?;
+        archival.build(BuildOptions::default())?;
+        let post_dir = archival.site.manifest.build_dir.join("post");
+        let (html, json, print) = archival.fs_mutex.with_fs(|fs| {
+            Ok((
+                fs.read_to_string(&post_dir.join("a-post.html"))?,
+                fs.read_to_string(&post_dir.join("a-post.json"))?,
+                fs.read_to_string(&post_dir.join("a-post.print.html"))?,
+            ))
+        })?;
+        assert!(html.is_some());
+        let json: serde_json::Value = serde_json::from_str(&json.unwrap())?;
+        assert!(json["title"].is_string());
+        assert!(print.unwrap().starts_with("<h1>"));
+        // Templates are not rendered as pages
+        let pages = archival
+            .fs_mutex
+            .with_fs(|fs| fs.exists(&archival.site.manifest.build_dir.join("post_api.html")))?;
+        assert!(!pages);
+        Ok(())
+    }
+
+    #[test]
+    fn build_taxonomy_pages() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        let objects_toml = fs.read_to_string(Path::new("objects.toml"))?.unwrap();
+        fs.write_str(
+            Path::new("objects.toml"),
+            objects_toml.replace(
+                "[post]\n",
+                "[post]\ncategory = \"string\"\n_taxonomies = { category = { template = \"category\", index_template = \"categories\" } }\n",
+            ),
+        )?;
+        fs.write_str(
+            Path::new("pages/category.liquid"),
+            "{{ term.name }}:{% for post in term.objects %}{{ post.title }};{% endfor %}"
+                .to_string(),
+        )?;
+        fs.write_str(
+            Path::new("pages/categories.liquid"),
+            "{% for term in terms %}<a href=\"/{{ term.path }}.html\">{{ term.name }}</a>{% endfor %}"
+                .to_string(),
+        )?;
+        let archival = Archival::new(fs)?;
+        for (filename, category) in [("a-post", "Big News"), ("sparse-post", "Big News")] {
+            archival.send_event(
+                ArchivalEvent::EditField(EditFieldEvent {
+                    object: "post".to_string(),
+                    filename: filename.to_string(),
+                    path: ValuePath::empty(),
+                    field: "category".to_string(),
+                    value: Some(FieldValue::String(category.to_string())),
+                    locale: None,
+                    source: None,
+                }),
+                None,
+            )?;
+        }
+        archival.build(BuildOptions::default())?;
+        let build_dir = &archival.site.manifest.build_dir;
+        let term_html = archival
+            .fs_mutex
+            .with_fs(|fs| fs.read_to_string(&build_dir.join("category/big-news.html")))?
+            .unwrap();
+        println!("term: {}", term_html);
+        assert!(term_html.starts_with("Big News:"));
+        assert_eq!(term_html.matches(';').count(), 2);
+        let index_html = archival
+            .fs_mutex
+            .with_fs(|fs| fs.read_to_string(&build_dir.join("category/index.html")))?
+            .unwrap();
+        assert!(index_html.contains("<a href=\"/category/big-news.html\">Big News</a>"));
+        // Taxonomy templates are not rendered as regular pages
+        assert!(archival
+            .dist_file(&build_dir.join("category.html"))
+            .is_none());
+        assert!(archival
+            .dist_file(&build_dir.join("categories.html"))
+            .is_none());
+
+        // A term can't have the same page as the index
+        archival.send_event(
+            ArchivalEvent::EditField(EditFieldEvent {
+                object: "post".to_string(),
+                filename: "a-post".to_string(),
+                path: ValuePath::empty(),
+                field: "category".to_string(),
+                value: Some(FieldValue::String("Index".to_string())),
+                locale: None,
+                source: None,
+            }),
+            None,
+        )?;
+        let error = archival.build(BuildOptions::default()).unwrap_err();
+        assert!(error.to_string().contains("overwrite"), "{}", error);
+        Ok(())
+    }
+
+    #[derive(Debug, Default, liquid_core::Display_filter)]
+    #[name = "shout"]
+    struct ShoutFilter;
+
+    impl liquid_core::Filter for ShoutFilter {
+        fn evaluate(
+            &self,
+            input: &dyn liquid_core::ValueView,
+            _runtime: &dyn liquid_core::Runtime,
+        ) -> liquid_core::Result<liquid_core::Value> {
+            Ok(liquid_core::Value::scalar(
+                input.to_kstr().to_uppercase() + "!",
+            ))
+        }
+    }
+
+    #[derive(Clone, liquid_core::ParseFilter, liquid_core::FilterReflection)]
+    #[filter(name = "shout", description = "Shouts.", parsed(ShoutFilter))]
+    struct ShoutFilterParser;
+
+    #[test]
+    fn build_with_custom_filter() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        fs.write_str(
+            Path::new("pages/shout.liquid"),
+            "{{ \"hi\" | shout }} {{ \"#ff0000\" | rgb }}".to_string(),
+        )?;
+        let mut archival = Archival::new(fs)?;
+        assert!(archival.build(BuildOptions::default()).is_err());
+        archival.register_filter(ShoutFilterParser);
+        archival.build(BuildOptions::default())?;
+        let html = archival
+            .fs_mutex
+            .with_fs(|fs| fs.read_to_string(&archival.site.manifest.build_dir.join("shout.html")))?
+            .unwrap();
+        assert_eq!(html, "HI! rgb(255, 0, 0)");
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    struct ShoutImageUrls;
+
+    impl ImageUrls for ShoutImageUrls {
+        fn transform(
+            &self,
+            image: &crate::images::ImageSource,
+            _transform: &crate::images::ImageTransform,
+        ) -> String {
+            image.url.to_uppercase()
+        }
+    }
+
+    #[test]
+    fn build_with_custom_image_urls() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        fs.write_str(
+            Path::new("pages/image.liquid"),
+            "{{ \"/a.png\" | image_url: width: 100 }}".to_string(),
+        )?;
+        let mut archival = Archival::new(fs)?;
+        archival.site.manifest.images.transform_url = Some("https://cdn.test/{url}".to_string());
+        let image_path = archival.site.manifest.build_dir.join("image.html");
+        archival.build(BuildOptions::default())?;
+        let html = archival
+            .fs_mutex
+            .with_fs(|fs| fs.read_to_string(&image_path))?
+            .unwrap();
+        assert_eq!(html, "https://cdn.test//a.png");
+        // Image urls set by the application replace the manifest's
+        archival.set_image_urls(ShoutImageUrls);
+        archival.build(BuildOptions::default())?;
+        let html = archival
+            .fs_mutex
+            .with_fs(|fs| fs.read_to_string(&image_path))?
+            .unwrap();
+        assert_eq!(html, "/A.PNG");
+        Ok(())
+    }
+
+    #[test]
+    fn build_with_fingerprinted_assets() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        fs.write_str(
+            Path::new("pages/assets.liquid"),
+            "{{ '/style/theme.css' | asset_url }} {{ '/missing.css' | asset_url }}".to_string(),
+        )?;
+        let mut archival = Archival::new(fs)?;
+        archival.site.manifest.fingerprint_assets = true;
+        let static_dir = archival.site.manifest.static_dir.clone();
+        let build_dir = archival.site.manifest.build_dir.clone();
+        archival.fs_mutex.with_fs(|fs| {
+            fs.write_str(&static_dir.join("style/theme.css"), "body {}".to_string())
+        })?;
+        archival.build(BuildOptions::default())?;
+        let html = archival
+            .fs_mutex
+            .with_fs(|fs| fs.read_to_string(&build_dir.join("assets.html")))?
+            .unwrap();
+        let (fingerprinted, missing) = html.split_once(' ').unwrap();
+        assert!(fingerprinted.starts_with("/style/theme."));
+        assert!(fingerprinted.ends_with(".css"));
+        assert_eq!(missing, "/missing.css");
+        let asset_manifest = archival.fs_mutex.with_fs(|fs| {
+            fs.read_to_string(&build_dir.join(constants::ASSET_MANIFEST_FILE_NAME))
+        })?;
+        let asset_manifest: serde_json::Value = serde_json::from_str(&asset_manifest.unwrap())?;
+        assert_eq!(
+            asset_manifest["style/theme.css"],
+            fingerprinted.trim_start_matches('/')
+        );
+        // The file is copied to its original name too
+        archival.fs_mutex.with_fs(|fs| {
+            assert!(fs.exists(&build_dir.join(&fingerprinted[1..]))?);
+            assert!(fs.exists(&build_dir.join("style/theme.css"))?);
+            fs.write_str(&static_dir.join("style/theme.css"), "p {}".to_string())
+        })?;
+        archival.build(BuildOptions::default())?;
+        // Pages are rendered again with the new fingerprinted name
+        let html = archival
+            .fs_mutex
+            .with_fs(|fs| fs.read_to_string(&build_dir.join("assets.html")))?
+            .unwrap();
+        let (refingerprinted, _) = html.split_once(' ').unwrap();
+        assert_ne!(refingerprinted, fingerprinted);
+        archival.fs_mutex.with_fs(|fs| {
+            assert!(!fs.exists(&build_dir.join(&fingerprinted[1..]))?);
+            assert!(fs.exists(&build_dir.join(&refingerprinted[1..]))?);
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn build_with_minified_json() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        fs.write_str(
+            Path::new("pages/data.json.liquid"),
+            "{\n  \"name\": \"a b\",\n  \"values\": [1, 2]\n}\n".to_string(),
+        )?;
+        let mut archival = Archival::new(fs)?;
+        archival.site.manifest.minify = vec!["json".to_string()];
+        archival.build(BuildOptions::default())?;
+        let json = archival
+            .fs_mutex
+            .with_fs(|fs| fs.read_to_string(&archival.site.manifest.build_dir.join("data.json")))?
+            .unwrap();
+        assert_eq!(json, "{\"name\":\"a b\",\"values\":[1,2]}");
+        let stats = archival.site.take_minify_stats();
+        assert_eq!(stats.files, 1);
+        assert_eq!(stats.saved_bytes(), 11);
+        assert_eq!(archival.site.take_minify_stats().files, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn build_with_output_formats() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let zip = include_bytes!("../tests/fixtures/archival-website.zip");
+        unpack_zip(zip.to_vec(), &mut fs)?;
+        fs.write_str(
+            Path::new("pages/feed.csv.liquid"),
+            "{{ 'a, b' | escape_output }},{{ output_format }}".to_string(),
+        )?;
+        fs.write_str(
+            Path::new("pages/events.ics.liquid"),
+            "{{ 'a, b' | escape_output }}".to_string(),
+        )?;
+        let mut archival = Archival::new(fs)?;
+        archival.site.manifest.formats.insert(
+            "ics".to_string(),
+            OutputFormat::new("text/calendar", Escaping::None),
+        );
+        archival.build(BuildOptions::default())?;
+        let build_dir = &archival.site.manifest.build_dir;
+        let (csv, ics) = archival.fs_mutex.with_fs(|fs| {
+            Ok((
+                fs.read_to_string(&build_dir.join("feed.csv"))?,
+                fs.read_to_string(&build_dir.join("events.ics"))?,
+            ))
+        })?;
+        assert_eq!(csv.unwrap(), "\"a, b\",csv");
+        assert_eq!(ics.unwrap(), "a, b");
+        Ok(())
+    }
+
     #[test]
     fn modify_manifest() -> Result<(), Box<dyn Error>> {
         let mut fs = MemoryFileSystem::default();
@@ -915,7 +1744,7 @@ mod lib {
         let zip = include_bytes!("../tests/fixtures/archival-website.zip");
         unpack_zip(zip.to_vec(), &mut fs)?;
         let archival = Archival::new(fs)?;
-        archival.delete_objects(vec!["section", "site"].into_iter())?;
+        archival.delete_objects(vec!["section", "site"])?;
         // This should result in the relevant files being missing
         let sections_dir = archival.site.manifest.objects_dir.join("section");
         let sections = archival.fs_mutex.with_fs(|fs| {
@@ -948,6 +1777,7 @@ mod lib {
                 path: ValuePath::empty(),
                 field: "name".to_string(),
                 value: Some(FieldValue::String("This is the new name".to_string())),
+                locale: None,
                 source: None,
             }),
             Some(BuildOptions::default()),
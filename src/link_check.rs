@@ -0,0 +1,264 @@
+use crate::{filters::url::SiteUrls, FileSystemAPI};
+use once_cell::sync::Lazy;
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+use thiserror::Error;
+
+static TAG_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<[a-zA-Z][a-zA-Z0-9-]*(\s[^>]*)?>").unwrap());
+static ATTR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"\s([a-zA-Z:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap()
+});
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BrokenLinkKind {
+    #[error("{0} does not exist")]
+    MissingTarget(String),
+    #[error("{0} has no element with id {1}")]
+    MissingAnchor(String, String),
+    #[error("file url {0} has no sha")]
+    EmptyFileSha(String),
+    #[error("empty {0}")]
+    EmptyUrl(String),
+}
+
+/// A link in the build output that doesn't resolve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    /// The page the link is in, relative to the build dir.
+    pub file: PathBuf,
+    pub line: usize,
+    pub url: String,
+    pub kind: BrokenLinkKind,
+}
+
+impl fmt::Display for BrokenLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file.display(), self.line, self.kind)
+    }
+}
+
+struct Link {
+    attribute: String,
+    url: String,
+    line: usize,
+}
+
+#[derive(Default)]
+struct HtmlPage {
+    links: Vec<Link>,
+    ids: HashSet<String>,
+}
+
+impl HtmlPage {
+    fn parse(html: &str) -> Self {
+        let mut page = Self::default();
+        let mut line = 1;
+        let mut offset = 0;
+        for tag in TAG_RE.find_iter(html) {
+            for attr in ATTR_RE.captures_iter(tag.as_str()) {
+                let (Some(name), Some(value)) =
+                    (attr.get(1), attr.get(2).or(attr.get(3)).or(attr.get(4)))
+                else {
+                    continue;
+                };
+                let position = tag.start() + value.start();
+                line += html[offset..position].matches('\n').count();
+                offset = position;
+                let value = value.as_str().replace("&amp;", "&");
+                match &name.as_str().to_lowercase()[..] {
+                    "href" | "src" => page.links.push(Link {
+                        attribute: name.as_str().to_lowercase(),
+                        url: value,
+                        line,
+                    }),
+                    "id" => {
+                        page.ids.insert(value);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        page
+    }
+}
+
+/// Resolves a path in a link against the page it's in, without leaving the
+/// build dir.
+fn resolve(page: &Path, path: &str) -> PathBuf {
+    let joined = match path.strip_prefix('/') {
+        Some(root_path) => PathBuf::from(root_path),
+        None => page.parent().unwrap_or(Path::new("")).join(path),
+    };
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::Normal(c) => resolved.push(c),
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            _ => {}
+        }
+    }
+    resolved
+}
+
+/// The file a resolved path is served from, trying `.html` and `index.html`
+/// for paths without them, the way static hosts do.
+fn target_file(path: &Path, is_dir: bool, files: &HashSet<PathBuf>) -> Option<PathBuf> {
+    let mut candidates = vec![];
+    if !is_dir && !path.as_os_str().is_empty() {
+        candidates.push(path.to_path_buf());
+        if path.extension().is_none() {
+            candidates.push(path.with_extension("html"));
+        }
+    }
+    candidates.push(path.join("index.html"));
+    candidates.into_iter().find(|c| files.contains(c))
+}
+
+/// Checks that the internal links and sources of html files in the build dir
+/// resolve to files in the build dir, and that their anchors match the id of
+/// an element, e.g. a heading, in the target page. Links to other sites are
+/// not checked.
+pub fn check_links(
+    build_dir: &Path,
+    site_url: Option<&str>,
+    uploads_url: &str,
+    fs: &impl FileSystemAPI,
+) -> Result<Vec<BrokenLink>, Box<dyn Error>> {
+    let urls = SiteUrls::new(site_url);
+    let files: HashSet<PathBuf> = fs.walk_dir(build_dir, false)?.collect();
+    let mut pages: HashMap<PathBuf, HtmlPage> = HashMap::new();
+    for file in &files {
+        if file.extension().is_some_and(|e| e == "html") {
+            if let Some(html) = fs.read_to_string(&build_dir.join(file))? {
+                pages.insert(file.to_path_buf(), HtmlPage::parse(&html));
+            }
+        }
+    }
+    let mut broken = vec![];
+    let mut page_files: Vec<&PathBuf> = pages.keys().collect();
+    page_files.sort();
+    for file in page_files {
+        for link in &pages[file].links {
+            let mut report = |kind| {
+                broken.push(BrokenLink {
+                    file: file.to_path_buf(),
+                    line: link.line,
+                    url: link.url.to_string(),
+                    kind,
+                })
+            };
+            let url = link.url.trim();
+            if url.is_empty() {
+                report(BrokenLinkKind::EmptyUrl(link.attribute.to_string()));
+                continue;
+            }
+            if let Some(sha) = url.strip_prefix(uploads_url) {
+                if sha.trim_matches('/').is_empty() {
+                    report(BrokenLinkKind::EmptyFileSha(url.to_string()));
+                }
+                continue;
+            }
+            // Other schemes, e.g. mailto: or data:
+            if url::Url::parse(url).is_ok_and(|u| !matches!(u.scheme(), "http" | "https")) {
+                continue;
+            }
+            let Some(site_path) = urls.site_path(url) else {
+                continue;
+            };
+            let (path, fragment) = match site_path.split_once('#') {
+                Some((path, fragment)) => (path, Some(fragment)),
+                None => (&site_path[..], None),
+            };
+            let path = path.split('?').next().unwrap_or_default();
+            let path = percent_decode_str(path).decode_utf8_lossy();
+            let target = if path.is_empty() {
+                Some(file.to_path_buf())
+            } else {
+                target_file(&resolve(file, &path), path.ends_with('/'), &files)
+            };
+            let Some(target) = target else {
+                report(BrokenLinkKind::MissingTarget(path.to_string()));
+                continue;
+            };
+            let Some(fragment) = fragment.filter(|f| !f.is_empty() && *f != "top") else {
+                continue;
+            };
+            let fragment = percent_decode_str(fragment).decode_utf8_lossy();
+            if let Some(target_page) = pages.get(&target) {
+                if !target_page.ids.contains(&fragment[..]) {
+                    report(BrokenLinkKind::MissingAnchor(
+                        target.display().to_string(),
+                        fragment.to_string(),
+                    ));
+                }
+            }
+        }
+    }
+    Ok(broken)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryFileSystem;
+
+    #[test]
+    fn broken_links() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let build_dir = Path::new("dist");
+        fs.write_str(
+            &build_dir.join("index.html"),
+            r##"<html>
+<head><link rel="stylesheet" href="/style/theme.css"></head>
+<body>
+<h1 id="intro">Intro</h1>
+<a href="post/a.html">A</a>
+<a href="/post/b">B</a> <a href='/missing.html'>missing</a>
+<a href="#intro">intro</a> <a href="#nope">nope</a>
+<a href="https://example.com/blog/post/a.html#body">absolute</a>
+<a href="https://other.com/missing.html">other site</a>
+<a href="mailto:a@example.com">mail</a>
+<img src="https://uploads.archival.dev/">
+<img src="">
+</body>
+</html>"##
+                .to_string(),
+        )?;
+        fs.write_str(&build_dir.join("style/theme.css"), "".to_string())?;
+        fs.write_str(
+            &build_dir.join("post/a.html"),
+            "<h2 id=\"body\">Body</h2><a href=\"../index.html#intro\">up</a> <a href=\"b.html#missing\">b</a>"
+                .to_string(),
+        )?;
+        fs.write_str(&build_dir.join("post/b.html"), "".to_string())?;
+        let broken = check_links(
+            build_dir,
+            Some("https://example.com/blog"),
+            "https://uploads.archival.dev",
+            &fs,
+        )?;
+        assert_eq!(
+            broken
+                .iter()
+                .map(|b| b.to_string())
+                .collect::<Vec<String>>(),
+            vec![
+                "index.html:6: /missing.html does not exist",
+                "index.html:7: index.html has no element with id nope",
+                "index.html:11: file url https://uploads.archival.dev/ has no sha",
+                "index.html:12: empty src",
+                "post/a.html:1: post/b.html has no element with id missing",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,425 @@
+use crate::{
+    filters::url::SiteUrls,
+    object::{Object, ObjectEntry},
+    object_definition::ObjectDefinitions,
+    FieldValue,
+};
+use comrak::{
+    arena_tree::Node,
+    nodes::{Ast, AstNode, LineColumn, NodeLink, NodeValue},
+    parse_document, Arena, ComrakOptions,
+};
+use liquid::{ObjectView, ValueView};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::{cell::RefCell, collections::HashMap};
+
+/// Matches `[[post/my-post]]`, `[[person:jane]]` and `[[post/my-post|label]]`.
+static WIKI_LINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[\[([^\[\]|]+?)(?:\|([^\[\]]+?))?\]\]").unwrap());
+
+/// A wiki-style link to an object in markdown, written as
+/// `[[object_name/filename]]` or `[[object_name:filename]]`, with an
+/// optional label after a `|`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikiLink {
+    pub object_name: String,
+    pub filename: String,
+    pub label: Option<String>,
+}
+
+impl WikiLink {
+    fn from_captures(captures: &regex::Captures) -> Option<Self> {
+        let target = captures.get(1)?.as_str().trim();
+        let (object_name, filename) = target.split_once(['/', ':'])?;
+        Some(Self {
+            object_name: object_name.trim().to_string(),
+            filename: filename.trim().to_string(),
+            label: captures.get(2).map(|l| l.as_str().trim().to_string()),
+        })
+    }
+
+    /// The object this link points to, as `object_name/filename`.
+    pub fn target(&self) -> String {
+        format!("{}/{}", self.object_name, self.filename)
+    }
+}
+
+/// An object that wiki links may point to, or that links to another object.
+#[derive(Debug, Clone, ObjectView, ValueView, Serialize, PartialEq)]
+pub struct LinkTarget {
+    pub object_name: String,
+    pub filename: String,
+    pub path: String,
+    pub url: String,
+    /// The object's `title` or `name` field, or its filename, which is used as
+    /// the text of links that don't specify a label.
+    pub title: String,
+}
+
+impl LinkTarget {
+    /// A target for an object whose page has this extension, e.g. `html`.
+    fn new(object: &Object, extension: &str, locale: Option<&str>, urls: &SiteUrls) -> Self {
+        let title = ["title", "name"]
+            .iter()
+            .find_map(|k| match object.values.get(*k) {
+                Some(FieldValue::String(s)) if !s.is_empty() => Some(s.to_string()),
+                _ => None,
+            })
+            .unwrap_or_else(|| object.filename.to_string());
+        let page = format!("{}.{}", object.path, extension);
+        Self {
+            object_name: object.object_name.to_string(),
+            filename: object.filename.to_string(),
+            path: object.path.to_string(),
+            url: urls.relative_url(&match locale {
+                Some(locale) => format!("{}/{}", locale, page),
+                None => page,
+            }),
+            title,
+        }
+    }
+}
+
+/// The wiki links between the objects of a site. Only objects with an html
+/// template have a page, so links to any other object are dangling, and only
+/// objects with a page are listed as backlinks.
+#[derive(Debug, Default)]
+pub struct Links {
+    targets: HashMap<String, LinkTarget>,
+    backlinks: HashMap<String, Vec<LinkTarget>>,
+    dangling: Vec<(String, String)>,
+}
+
+impl Links {
+    pub fn new(
+        objects: &std::collections::BTreeMap<String, ObjectEntry>,
+        definitions: &ObjectDefinitions,
+        locale: Option<&str>,
+        urls: &SiteUrls,
+    ) -> Self {
+        let mut links = Self::default();
+        for (name, entry) in objects {
+            if let Some(template) = definitions.get(name).and_then(|d| d.html_template()) {
+                for object in entry {
+                    links.targets.insert(
+                        object.path.to_string(),
+                        LinkTarget::new(object, &template.extension, locale, urls),
+                    );
+                }
+            }
+        }
+        for entry in objects.values() {
+            for object in entry {
+                let mut targets = vec![];
+                for value in object.values.values() {
+                    collect_links(value, &mut targets);
+                }
+                targets.sort();
+                targets.dedup();
+                let source = links.targets.get(&object.path).cloned();
+                for target in targets {
+                    if links.targets.contains_key(&target) {
+                        if let Some(source) = &source {
+                            let backlinks = links.backlinks.entry(target).or_default();
+                            if !backlinks.contains(source) {
+                                backlinks.push(source.clone());
+                            }
+                        }
+                    } else {
+                        links.dangling.push((object.path.to_string(), target));
+                    }
+                }
+            }
+        }
+        links
+    }
+
+    pub fn resolve(&self, link: &WikiLink) -> Option<&LinkTarget> {
+        self.targets.get(&link.target())
+    }
+
+    /// The objects that link to this object.
+    pub fn backlinks(&self, object: &Object) -> Vec<LinkTarget> {
+        self.backlinks
+            .get(&object.path)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Links that don't resolve to a page, as (source path, target) pairs.
+    pub fn dangling(&self) -> &[(String, String)] {
+        &self.dangling
+    }
+}
+
+fn collect_links(value: &FieldValue, targets: &mut Vec<String>) {
+    match value {
+        FieldValue::Markdown(markdown) => {
+            targets.extend(wiki_links(markdown).iter().map(|l| l.target()));
+        }
+        FieldValue::Objects(children) => {
+            for child in children {
+                for value in child.values() {
+                    collect_links(value, targets);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The wiki links in a markdown string, excluding any in code.
+pub fn wiki_links(markdown: &str) -> Vec<WikiLink> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, markdown, &ComrakOptions::default());
+    let mut links = vec![];
+    for node in text_nodes(root) {
+        if let NodeValue::Text(text) = &node.data.borrow().value {
+            links.extend(
+                WIKI_LINK_RE
+                    .captures_iter(text)
+                    .filter_map(|c| WikiLink::from_captures(&c)),
+            );
+        }
+    }
+    links
+}
+
+/// Replaces the wiki links in a parsed document with links to their targets.
+/// Links that don't resolve are left as-is.
+pub fn resolve_wiki_links<'a>(arena: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>, links: &Links) {
+    for node in text_nodes(root) {
+        let text = match &node.data.borrow().value {
+            NodeValue::Text(text) if WIKI_LINK_RE.is_match(text) => text.to_string(),
+            _ => continue,
+        };
+        let mut last = 0;
+        for captures in WIKI_LINK_RE.captures_iter(&text) {
+            let Some(link) = WikiLink::from_captures(&captures) else {
+                continue;
+            };
+            let Some(target) = links.resolve(&link) else {
+                continue;
+            };
+            let matched = captures.get(0).unwrap();
+            node.insert_before(new_node(
+                arena,
+                NodeValue::Text(text[last..matched.start()].to_string()),
+            ));
+            let link_node = new_node(
+                arena,
+                NodeValue::Link(NodeLink {
+                    url: target.url.to_string(),
+                    title: String::new(),
+                }),
+            );
+            link_node.append(new_node(
+                arena,
+                NodeValue::Text(link.label.unwrap_or_else(|| target.title.to_string())),
+            ));
+            node.insert_before(link_node);
+            last = matched.end();
+        }
+        if let NodeValue::Text(t) = &mut node.data.borrow_mut().value {
+            *t = text[last..].to_string();
+        }
+    }
+}
+
+fn new_node<'a>(arena: &'a Arena<AstNode<'a>>, value: NodeValue) -> &'a AstNode<'a> {
+    arena.alloc(Node::new(RefCell::new(Ast::new(
+        value,
+        LineColumn { line: 0, column: 0 },
+    ))))
+}
+
+/// The text nodes of a document, with adjacent text nodes merged. The parser
+/// splits text at brackets, so a wiki link may span several text nodes.
+fn text_nodes<'a>(root: &'a AstNode<'a>) -> Vec<&'a AstNode<'a>> {
+    let mut nodes = vec![];
+    for node in root.descendants() {
+        if !matches!(node.data.borrow().value, NodeValue::Text(_)) {
+            continue;
+        }
+        let is_merged = node
+            .previous_sibling()
+            .is_some_and(|p| matches!(p.data.borrow().value, NodeValue::Text(_)));
+        if !is_merged {
+            nodes.push(node);
+        }
+    }
+    for node in &nodes {
+        while let Some(next) = node.next_sibling() {
+            let next_text = match &next.data.borrow().value {
+                NodeValue::Text(t) => t.to_string(),
+                _ => break,
+            };
+            if let NodeValue::Text(t) = &mut node.data.borrow_mut().value {
+                t.push_str(&next_text);
+            }
+            next.detach();
+        }
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fields::{MarkdownContext, MarkdownOptions},
+        object_definition::ObjectDefinition,
+    };
+    use std::{collections::BTreeMap, error::Error};
+
+    fn post(filename: &str, title: &str, content: &str) -> Object {
+        Object {
+            filename: filename.to_string(),
+            object_name: "post".to_string(),
+            order: 0,
+            path: format!("post/{}", filename),
+            values: BTreeMap::from([
+                ("title".to_string(), FieldValue::String(title.to_string())),
+                (
+                    "content".to_string(),
+                    FieldValue::Markdown(content.to_string()),
+                ),
+            ]),
+            translations: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn parsing() {
+        let links = wiki_links(
+            "See [[post/a]], [[ person:jane | Jane ]] and `[[post/code]]`.\n\n    [[post/indented]]",
+        );
+        assert_eq!(
+            links,
+            vec![
+                WikiLink {
+                    object_name: "post".to_string(),
+                    filename: "a".to_string(),
+                    label: None
+                },
+                WikiLink {
+                    object_name: "person".to_string(),
+                    filename: "jane".to_string(),
+                    label: Some("Jane".to_string())
+                },
+            ]
+        );
+        assert!(wiki_links("[[nope]] [not](a link)").is_empty());
+    }
+
+    #[test]
+    fn backlinks_and_dangling() -> Result<(), Box<dyn Error>> {
+        let definitions = ObjectDefinition::from_table(
+            &toml::from_str(
+                "[post]\ntitle = \"string\"\ncontent = \"markdown\"\ntemplate = \"post\"",
+            )?,
+            &HashMap::new(),
+        )?;
+        let objects = BTreeMap::from([(
+            "post".to_string(),
+            ObjectEntry::from_vec(vec![
+                post("a", "A", "links to [[post/b]] and [[post:b]]"),
+                post(
+                    "b",
+                    "B",
+                    "links to [[post/a|the first]] and [[post/missing]]",
+                ),
+            ]),
+        )]);
+        let urls = SiteUrls::default();
+        let links = Links::new(&objects, &definitions, None, &urls);
+        let a = objects["post"].into_iter().next().unwrap();
+        let b = objects["post"].into_iter().nth(1).unwrap();
+        assert_eq!(links.backlinks(b).len(), 1);
+        assert_eq!(links.backlinks(b)[0].url, "/post/a.html");
+        assert_eq!(links.backlinks(a)[0].title, "B");
+        assert_eq!(
+            links.dangling(),
+            &[("post/b".to_string(), "post/missing".to_string())]
+        );
+        let localized = Links::new(&objects, &definitions, Some("es"), &urls);
+        assert_eq!(localized.backlinks(b)[0].url, "/es/post/a.html");
+        let hosted = Links::new(
+            &objects,
+            &definitions,
+            None,
+            &SiteUrls::new(Some("https://example.com/blog")),
+        );
+        assert_eq!(hosted.backlinks(b)[0].url, "/blog/post/a.html");
+        Ok(())
+    }
+
+    #[test]
+    fn links_use_html_templates() -> Result<(), Box<dyn Error>> {
+        let definitions = ObjectDefinition::from_table(
+            &toml::from_str(
+                "[post]
+                title = \"string\"
+                content = \"markdown\"
+                template = { json = \"post_json\", \"print.html\" = \"post\" }
+                [feed]
+                title = \"string\"
+                content = \"markdown\"
+                template = { json = \"feed\" }",
+            )?,
+            &HashMap::new(),
+        )?;
+        let objects = BTreeMap::from([
+            (
+                "post".to_string(),
+                ObjectEntry::from_vec(vec![post("a", "A", "see [[feed/main]]")]),
+            ),
+            (
+                "feed".to_string(),
+                ObjectEntry::from_vec(vec![Object {
+                    object_name: "feed".to_string(),
+                    path: "feed/main".to_string(),
+                    ..post("main", "Main", "see [[post/a]]")
+                }]),
+            ),
+        ]);
+        let links = Links::new(&objects, &definitions, None, &SiteUrls::default());
+        // Objects without an html template have no page to link to
+        assert_eq!(
+            links.dangling(),
+            &[("post/a".to_string(), "feed/main".to_string())]
+        );
+        let a = objects["post"].into_iter().next().unwrap();
+        assert!(links.backlinks(a).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn rendering() -> Result<(), Box<dyn Error>> {
+        let definitions = ObjectDefinition::from_table(
+            &toml::from_str(
+                "[post]\ntitle = \"string\"\ncontent = \"markdown\"\ntemplate = \"post\"",
+            )?,
+            &HashMap::new(),
+        )?;
+        let objects = BTreeMap::from([(
+            "post".to_string(),
+            ObjectEntry::from_vec(vec![post("a", "A <Post>", "")]),
+        )]);
+        let links = Links::new(&objects, &definitions, None, &SiteUrls::default());
+        let (html, _) = MarkdownOptions::default().render_with_toc(
+            "See [[post/a]], [[post:a|this one]] and [[post/missing]].",
+            &MarkdownContext {
+                links: Some(&links),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            html,
+            "<p>See <a href=\"/post/a.html\">A &lt;Post&gt;</a>, <a href=\"/post/a.html\">this one</a> and [[post/missing]].</p>\n"
+        );
+        Ok(())
+    }
+}
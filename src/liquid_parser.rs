@@ -1,17 +1,149 @@
 use crate::{
+    assets::AssetManifest,
+    filters::{
+        collection::{GroupByFilterParser, SortByFilterParser},
+        color::{ColorComponentsFilterParser, RgbFilterParser},
+        date::DateToRfc3339FilterParser,
+        image::{ImageUrlFilterParser, SrcsetFilterParser},
+        location::{DistanceFilterParser, GeoJsonFilterParser},
+        markdown::{
+            ExcerptFilterParser, PlainTextFilterParser, ReadingTimeFilterParser,
+            WordCountFilterParser,
+        },
+        text::{
+            CsvEscapeFilterParser, EscapeOutputFilterParser, JsonEscapeFilterParser,
+            JsonifyFilterParser, RawFilterParser, SafeFilterParser, SlugifyFilterParser,
+            XmlEscapeFilterParser,
+        },
+        url::{AbsoluteUrlFilterParser, AssetUrlFilterParser, RelativeUrlFilterParser, SiteUrls},
+    },
+    images::{ImageOptions, ImageUrls, OriginalImageUrls},
+    output_format::OutputFormats,
     page::TemplateType,
-    tags::{default::DefaultTag, layout::LayoutTag},
+    tags::{blocks::BlocksTag, default::DefaultTag, layout::LayoutTag},
     FileSystemAPI,
 };
-use liquid_core::partials::{EagerCompiler, PartialSource};
+#[cfg(feature = "image-processing")]
+use crate::{filters::image::ImageVariantsFilterParser, image_processing::ImageVariants};
+use liquid_core::{
+    partials::{EagerCompiler, PartialSource},
+    ParseBlock, ParseFilter, ParseTag,
+};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::{borrow::Cow, collections::HashMap, error::Error, path::Path};
+use std::{borrow::Cow, collections::HashMap, error::Error, fmt, path::Path, sync::Arc};
 #[cfg(feature = "verbose-logging")]
 use tracing::debug;
 use tracing::error;
 
 pub static PARTIAL_FILE_NAME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^_(.+)\.liquid").unwrap());
+static RAW_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\{%-?\s*raw\s*-?%\}$").unwrap());
+static END_RAW_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{%-?\s*endraw\s*-?%\}").unwrap());
+
+/// Outputs that end in one of these filters are not escaped, since they are
+/// either escaped for a format already or meant to be output as they are.
+const UNESCAPED_FILTERS: &[&str] = &[
+    "raw",
+    "safe",
+    "escape",
+    "escape_once",
+    "escape_output",
+    "xml_escape",
+    "csv_escape",
+    "json_escape",
+    "jsonify",
+    "geojson",
+];
+
+/// The length of the `{{ }}` or `{% %}` markup at the start of a template,
+/// skipping over quoted strings, or None if it isn't closed.
+fn markup_len(template: &str) -> Option<usize> {
+    let close = if template.starts_with("{{") {
+        "}}"
+    } else {
+        "%}"
+    };
+    let mut quote = None;
+    for (idx, c) in template.char_indices().skip(2) {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if template[idx..].starts_with(close) => return Some(idx + close.len()),
+            None => {}
+        }
+    }
+    None
+}
+
+/// Adds `escape_output` to an output, unless its last filter is one of
+/// UNESCAPED_FILTERS.
+fn escape_output(output: &str) -> String {
+    let inner = &output[2..output.len() - 2];
+    let (open, inner) = match inner.strip_prefix('-') {
+        Some(inner) => ("{{-", inner),
+        None => ("{{", inner),
+    };
+    let (inner, close) = match inner.strip_suffix('-') {
+        Some(inner) => (inner, "-}}"),
+        None => (inner, "}}"),
+    };
+    if inner.trim().is_empty() {
+        return output.to_string();
+    }
+    let mut quote = None;
+    let mut last_filter = None;
+    for (idx, c) in inner.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '|' => last_filter = Some(idx + 1),
+            None => {}
+        }
+    }
+    let filter_name = last_filter.map(|idx| {
+        inner[idx..]
+            .trim_start()
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default()
+    });
+    if filter_name.is_some_and(|name| UNESCAPED_FILTERS.contains(&name)) {
+        return output.to_string();
+    }
+    format!("{}{} | escape_output {}", open, inner.trim_end(), close)
+}
+
+/// Escapes the `{{ }}` outputs of a template for the output format of the
+/// page it renders, by adding the `escape_output` filter to them. See
+/// Escaping for which outputs are left as they are. `{% raw %}` blocks are
+/// not changed.
+pub fn escape_outputs(template: &str) -> String {
+    let mut escaped = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{").into_iter().chain(rest.find("{%")).min() {
+        escaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(len) = markup_len(rest) else {
+            break;
+        };
+        let (markup, after) = rest.split_at(len);
+        rest = after;
+        if markup.starts_with("{{") {
+            escaped.push_str(&escape_output(markup));
+            continue;
+        }
+        escaped.push_str(markup);
+        if RAW_TAG_RE.is_match(markup) {
+            let end = END_RAW_TAG_RE.find(rest).map_or(rest.len(), |m| m.end());
+            escaped.push_str(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+    escaped.push_str(rest);
+    escaped
+}
 
 #[derive(Default, Debug, Clone)]
 struct ArchivalPartialSource {
@@ -33,7 +165,7 @@ impl ArchivalPartialSource {
                         #[cfg(feature = "verbose-logging")]
                         debug!("adding layout {} ({})", template_name, _t.extension());
                         if let Some(contents) = fs.read_to_string(&path.join(&file))? {
-                            partials.insert(template_name.to_string(), contents);
+                            partials.insert(template_name.to_string(), escape_outputs(&contents));
                         } else {
                             error!("Failed reading layout {}", file.display());
                         }
@@ -59,7 +191,7 @@ impl ArchivalPartialSource {
                         #[cfg(feature = "verbose-logging")]
                         debug!("adding partial {} ({})", partial_name, _t.extension());
                         if let Some(contents) = fs.read_to_string(&path.join(&file))? {
-                            partials.insert(partial_name.to_string(), contents);
+                            partials.insert(partial_name.to_string(), escape_outputs(&contents));
                         } else {
                             error!("Failed reading partial {}", file.display());
                         }
@@ -89,15 +221,209 @@ impl PartialSource for ArchivalPartialSource {
     }
 }
 
+/// The filters, tags and blocks that pages are rendered with, in addition to
+/// the liquid stdlib. The default extensions are archival's own, and
+/// applications may add their own, e.g.
+///
+/// ```ignore
+/// archival.register_filter(ShoutFilterParser);
+/// ```
+///
+/// Extensions registered with the name of an existing one replace it.
+#[derive(Clone)]
+pub struct LiquidExtensions {
+    filters: Vec<Box<dyn ParseFilter>>,
+    tags: Vec<Box<dyn ParseTag>>,
+    blocks: Vec<Box<dyn ParseBlock>>,
+}
+
+impl Default for LiquidExtensions {
+    fn default() -> Self {
+        Self::empty()
+            .tag(LayoutTag)
+            .tag(DefaultTag)
+            .tag(BlocksTag)
+            .filter(RgbFilterParser)
+            .filter(ColorComponentsFilterParser)
+            .filter(GeoJsonFilterParser)
+            .filter(DistanceFilterParser)
+            .filter(ExcerptFilterParser)
+            .filter(PlainTextFilterParser)
+            .filter(WordCountFilterParser)
+            .filter(ReadingTimeFilterParser)
+            .filter(SlugifyFilterParser)
+            .filter(XmlEscapeFilterParser)
+            .filter(CsvEscapeFilterParser)
+            .filter(JsonEscapeFilterParser)
+            .filter(JsonifyFilterParser)
+            .filter(SortByFilterParser)
+            .filter(GroupByFilterParser)
+            .filter(DateToRfc3339FilterParser)
+            .filter(RawFilterParser)
+            .filter(SafeFilterParser)
+            .site_url(None)
+            .image_urls(OriginalImageUrls)
+            .assets(AssetManifest::default())
+            .output_formats(OutputFormats::default())
+    }
+}
+
+impl fmt::Debug for LiquidExtensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LiquidExtensions")
+            .field(
+                "filters",
+                &self
+                    .filters
+                    .iter()
+                    .map(|p| p.reflection().name())
+                    .collect::<Vec<_>>(),
+            )
+            .field(
+                "tags",
+                &self
+                    .tags
+                    .iter()
+                    .map(|p| p.reflection().tag())
+                    .collect::<Vec<_>>(),
+            )
+            .field(
+                "blocks",
+                &self
+                    .blocks
+                    .iter()
+                    .map(|p| p.reflection().start_tag())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl LiquidExtensions {
+    /// No extensions, only the liquid stdlib.
+    pub fn empty() -> Self {
+        Self {
+            filters: vec![],
+            tags: vec![],
+            blocks: vec![],
+        }
+    }
+    /// Adds the `absolute_url` and `relative_url` filters for a site url.
+    pub fn site_url(self, site_url: Option<&str>) -> Self {
+        let urls = SiteUrls::new(site_url);
+        self.filter(RelativeUrlFilterParser { urls: urls.clone() })
+            .filter(AbsoluteUrlFilterParser { urls })
+    }
+    /// Generates image urls for the `image_url` and `srcset` filters, e.g. for
+    /// a CDN that ImageOptions can't describe.
+    pub fn image_urls(mut self, urls: impl ImageUrls + 'static) -> Self {
+        self.set_image_urls(urls);
+        self
+    }
+    /// Uses image options from a manifest for the `image_url` and `srcset`
+    /// filters.
+    pub(crate) fn image_options(mut self, options: &ImageOptions) -> Self {
+        self.add_image_urls(options.image_urls());
+        self
+    }
+    /// Adds the `image_variants` filter for the variants of a site's images.
+    #[cfg(feature = "image-processing")]
+    pub(crate) fn image_variants(self, variants: ImageVariants) -> Self {
+        self.filter(ImageVariantsFilterParser { variants })
+    }
+    /// Adds the `asset_url` filter for the fingerprinted assets of a site.
+    pub(crate) fn assets(self, assets: AssetManifest) -> Self {
+        self.filter(AssetUrlFilterParser { assets })
+    }
+    /// Adds the `escape_output` filter for the formats a site registers.
+    pub(crate) fn output_formats(self, formats: OutputFormats) -> Self {
+        self.filter(EscapeOutputFilterParser { formats })
+    }
+    /// Adds the extensions of another set after these, replacing any with
+    /// the same name.
+    pub(crate) fn extend(mut self, other: &LiquidExtensions) -> Self {
+        self.filters.extend(other.filters.iter().cloned());
+        self.tags.extend(other.tags.iter().cloned());
+        self.blocks.extend(other.blocks.iter().cloned());
+        self
+    }
+    pub fn filter(mut self, filter: impl Into<Box<dyn ParseFilter>>) -> Self {
+        self.add_filter(filter);
+        self
+    }
+    pub fn tag(mut self, tag: impl Into<Box<dyn ParseTag>>) -> Self {
+        self.add_tag(tag);
+        self
+    }
+    pub fn block(mut self, block: impl Into<Box<dyn ParseBlock>>) -> Self {
+        self.add_block(block);
+        self
+    }
+    pub fn set_image_urls(&mut self, urls: impl ImageUrls + 'static) {
+        self.add_image_urls(Arc::new(urls));
+    }
+    fn add_image_urls(&mut self, urls: Arc<dyn ImageUrls>) {
+        self.add_filter(ImageUrlFilterParser { urls: urls.clone() });
+        self.add_filter(SrcsetFilterParser { urls });
+    }
+    pub fn add_filter(&mut self, filter: impl Into<Box<dyn ParseFilter>>) {
+        self.filters.push(filter.into());
+    }
+    pub fn add_tag(&mut self, tag: impl Into<Box<dyn ParseTag>>) {
+        self.tags.push(tag.into());
+    }
+    pub fn add_block(&mut self, block: impl Into<Box<dyn ParseBlock>>) {
+        self.blocks.push(block.into());
+    }
+
+    fn register(&self, mut parser: liquid::ParserBuilder) -> liquid::ParserBuilder {
+        for filter in &self.filters {
+            parser = parser.filter(filter.clone());
+        }
+        for tag in &self.tags {
+            parser = parser.tag(tag.clone());
+        }
+        for block in &self.blocks {
+            parser = parser.block(block.clone());
+        }
+        parser
+    }
+}
+
 pub fn get(
     pages_path: Option<&Path>,
     layout_path: Option<&Path>,
+    extensions: &LiquidExtensions,
     fs: &impl FileSystemAPI,
 ) -> Result<liquid::Parser, Box<dyn Error>> {
     let partials = EagerCompiler::new(ArchivalPartialSource::new(pages_path, layout_path, fs)?);
-    let parser = liquid::ParserBuilder::with_stdlib()
-        .tag(LayoutTag)
-        .tag(DefaultTag)
+    let parser = extensions
+        .register(liquid::ParserBuilder::with_stdlib())
         .partials(partials);
     Ok(parser.build()?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping_outputs() {
+        assert_eq!(
+            escape_outputs("<p>{{ post.title }}</p>"),
+            "<p>{{ post.title | escape_output }}</p>"
+        );
+        assert_eq!(
+            escape_outputs("{{- post.date | date: '%Y | %m }}' -}}"),
+            "{{- post.date | date: '%Y | %m }}' | escape_output -}}"
+        );
+        assert_eq!(
+            escape_outputs("{{ post.body | raw }}{{ post | jsonify }}"),
+            "{{ post.body | raw }}{{ post | jsonify }}"
+        );
+        assert_eq!(
+            escape_outputs("{% if a %}{{a}}{% endif %}{% raw %}{{ b }}{% endraw %}{{ c"),
+            "{% if a %}{{a | escape_output }}{% endif %}{% raw %}{{ b }}{% endraw %}{{ c"
+        );
+    }
+}
@@ -7,12 +7,16 @@ use std::{
     ops::Deref,
     path::{Path, PathBuf},
 };
+use time::UtcOffset;
 use toml::{Table, Value};
 
 use crate::{
-    constants::{LAYOUT_DIR_NAME, NESTED_TYPES, SCHEMAS_DIR_NAME},
+    constants::{LAYOUT_DIR_NAME, MINIFY_OUTPUTS, NESTED_TYPES, SCHEMAS_DIR_NAME},
+    fields::{parse_timezone, MarkdownOptions, MetaSchema},
     file_system::FileSystemAPI,
+    images::ImageOptions,
     object::ValuePath,
+    output_format::{formats_from_toml, formats_to_toml, OutputFormats},
     FieldConfig,
 };
 
@@ -40,6 +44,12 @@ pub enum InvalidManifestError {
     InvalidNestedValidator(String, String),
     #[error("Invalid Manifest value '{1}' for field {0}.")]
     InvalidField(Value, String),
+    #[error("Timezone '{0}' is not supported, use UTC or a fixed offset (±HH:MM, e.g. -08:00).")]
+    UnsupportedTimezone(String),
+    #[error("trash_dir {0} cannot be inside the objects dir {1}.")]
+    TrashInObjectsDir(String, String),
+    #[error("Manifest Field {0} cannot be set from a string.")]
+    NotSettable(String),
 }
 
 #[derive(Debug, Clone)]
@@ -148,6 +158,12 @@ pub struct ManifestEditorType {
     pub alias_of: String,
     pub validate: Vec<ManifestEditorTypeValidator>,
     pub editor_url: String,
+    /// The structure of values of this type, for aliases of meta.
+    #[serde(default)]
+    pub schema: Option<MetaSchema>,
+    /// Markdown options for values of this type, for aliases of markdown.
+    #[serde(default)]
+    pub markdown: Option<MarkdownOptions>,
 }
 
 impl From<&ManifestEditorType> for toml::Value {
@@ -158,6 +174,12 @@ impl From<&ManifestEditorType> for toml::Value {
             toml::Value::Array(value.validate.iter().map(|v| v.into()).collect()),
         );
         map.insert("editor_url".into(), value.editor_url.to_string().into());
+        if let Some(schema) = &value.schema {
+            map.insert("schema".into(), toml::Value::Table(schema.to_toml()));
+        }
+        if let Some(markdown) = &value.markdown {
+            map.insert("markdown".into(), toml::Value::Table(markdown.to_toml()));
+        }
         map.into()
     }
 }
@@ -181,6 +203,31 @@ pub struct Manifest {
     pub static_dir: PathBuf,
     pub layout_dir: PathBuf,
     pub uploads_url: Option<String>,
+    pub trash_dir: Option<PathBuf>,
+    pub locales: Vec<String>,
+    /// The UTC offset of dates and times that don't specify one, e.g.
+    /// `-08:00`. Defaults to UTC. Only `UTC` and fixed `±HH:MM` offsets are
+    /// supported; named timezones such as `America/Los_Angeles` are rejected,
+    /// since their offset changes with daylight saving time.
+    pub timezone: Option<String>,
+    /// How markdown fields are rendered, see MarkdownOptions.
+    #[serde(default)]
+    pub markdown: MarkdownOptions,
+    /// How the `image_url` and `srcset` filters generate urls, see
+    /// ImageOptions.
+    #[serde(default)]
+    pub images: ImageOptions,
+    /// Copies static files to content-hashed names as well, e.g.
+    /// `style/theme.1a2b3c4d5e6f7a8b.css`, for the `asset_url` filter.
+    #[serde(default)]
+    pub fingerprint_assets: bool,
+    /// The outputs to minify when the `minify` feature is enabled: `html`,
+    /// `css` and `json` pages, and `css` and `js` static files.
+    #[serde(default)]
+    pub minify: Vec<String>,
+    /// Output formats in addition to the built in ones, see OutputFormats.
+    #[serde(default)]
+    pub formats: OutputFormats,
     pub editor_types: EditorTypes,
 }
 
@@ -200,6 +247,14 @@ pub enum ManifestField {
     SchemasDir,
     LayoutDir,
     CdnUrl,
+    TrashDir,
+    Locales,
+    Timezone,
+    Markdown,
+    Images,
+    FingerprintAssets,
+    Minify,
+    Formats,
     EditorTypes,
 }
 
@@ -218,11 +273,78 @@ impl ManifestField {
             ManifestField::SchemasDir => "schemas_dir",
             ManifestField::LayoutDir => "layout_dir",
             ManifestField::CdnUrl => "uploads_url",
+            ManifestField::TrashDir => "trash_dir",
+            ManifestField::Locales => "locales",
+            ManifestField::Timezone => "timezone",
+            ManifestField::Markdown => "markdown",
+            ManifestField::Images => "images",
+            ManifestField::FingerprintAssets => "fingerprint_assets",
+            ManifestField::Minify => "minify",
+            ManifestField::Formats => "formats",
             ManifestField::EditorTypes => "editor_types",
         }
     }
 }
 
+fn table_value(field: &ManifestField, value: String) -> Result<Value, InvalidManifestError> {
+    match toml::from_str::<Table>(&value) {
+        Ok(table) => Ok(Value::Table(table)),
+        Err(_) => Err(InvalidManifestError::InvalidField(
+            Value::String(value),
+            field.field_name().to_string(),
+        )),
+    }
+}
+
+fn validate_timezone(timezone: &str) -> Result<String, InvalidManifestError> {
+    match parse_timezone(timezone) {
+        Some(_) => Ok(timezone.to_string()),
+        None => Err(InvalidManifestError::UnsupportedTimezone(
+            timezone.to_string(),
+        )),
+    }
+}
+
+fn minify_from_toml(value: &Value) -> Result<Vec<String>, InvalidManifestError> {
+    let invalid = || InvalidManifestError::InvalidField(value.clone(), "minify".into());
+    value
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|v| match v.as_str() {
+            Some(output) if MINIFY_OUTPUTS.contains(&output) => Ok(output.to_string()),
+            _ => Err(invalid()),
+        })
+        .collect()
+}
+
+fn markdown_from_toml(value: &Value) -> Result<MarkdownOptions, InvalidManifestError> {
+    let invalid = || InvalidManifestError::InvalidField(value.clone(), "markdown".into());
+    let markdown = value
+        .as_table()
+        .and_then(|t| MarkdownOptions::from_toml(t).ok())
+        .ok_or_else(invalid)?;
+    #[cfg(feature = "syntax-highlighting")]
+    if let Some(theme) = &markdown.highlight_theme {
+        crate::fields::theme_css(theme).ok_or_else(invalid)?;
+    }
+    Ok(markdown)
+}
+
+fn images_from_toml(value: &Value) -> Result<ImageOptions, InvalidManifestError> {
+    value
+        .as_table()
+        .and_then(|t| ImageOptions::from_toml(t).ok())
+        .ok_or_else(|| InvalidManifestError::InvalidField(value.clone(), "images".into()))
+}
+
+fn output_formats_from_toml(value: &Value) -> Result<OutputFormats, InvalidManifestError> {
+    value
+        .as_table()
+        .and_then(|t| formats_from_toml(t).ok())
+        .ok_or_else(|| InvalidManifestError::InvalidField(value.clone(), "formats".into()))
+}
+
 impl fmt::Display for Manifest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -237,6 +359,9 @@ impl fmt::Display for Manifest {
         static files: {}
         layout dir: {}
         build dir: {}
+        trash dir: {}
+        locales: {}
+        timezone: {}
         {}
         "#,
             self.archival_version
@@ -252,6 +377,15 @@ impl fmt::Display for Manifest {
             self.static_dir.display(),
             self.layout_dir.display(),
             self.build_dir.display(),
+            self.trash_dir
+                .as_ref()
+                .map_or("none".to_string(), |t| t.display().to_string()),
+            if self.locales.is_empty() {
+                "none".to_string()
+            } else {
+                self.locales.join(", ")
+            },
+            self.timezone.as_deref().unwrap_or("UTC"),
             if !self.editor_types.is_empty() {
                 format!(
                     "editor types:\n{}",
@@ -287,6 +421,25 @@ impl fmt::Display for Manifest {
 }
 
 impl Manifest {
+    /// The directory the manifest is in, which paths are relative to.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Checks settings that depend on each other, which can't be checked
+    /// when they are parsed.
+    pub fn validate(&self) -> Result<(), InvalidManifestError> {
+        if let Some(trash_dir) = &self.trash_dir {
+            if trash_dir.starts_with(&self.objects_dir) {
+                return Err(InvalidManifestError::TrashInObjectsDir(
+                    trash_dir.display().to_string(),
+                    self.objects_dir.display().to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub fn default(root: &Path) -> Manifest {
         Manifest {
             root: root.to_owned(),
@@ -302,6 +455,14 @@ impl Manifest {
             build_dir: root.join(BUILD_DIR_NAME),
             static_dir: root.join(STATIC_DIR_NAME),
             layout_dir: root.join(LAYOUT_DIR_NAME),
+            trash_dir: None,
+            locales: vec![],
+            timezone: None,
+            markdown: MarkdownOptions::default(),
+            images: ImageOptions::default(),
+            fingerprint_assets: false,
+            minify: vec![],
+            formats: OutputFormats::default(),
             editor_types: HashMap::new(),
         }
     }
@@ -358,12 +519,38 @@ impl Manifest {
                             .collect()
                     })
                 }
+                "locales" => {
+                    manifest.locales = value.as_array().map_or(vec![], |v| {
+                        v.iter()
+                            .filter_map(|s| s.as_str().map(|s| s.to_string()))
+                            .collect()
+                    })
+                }
                 "pages" => manifest.pages_dir = path_or_err(value, "pages")?,
                 "objects" => manifest.objects_dir = path_or_err(value, "objects")?,
                 "build_dir" => manifest.build_dir = path_or_err(value, "build_dir")?,
                 "static_dir" => manifest.static_dir = path_or_err(value, "static_dir")?,
                 "schemas_dir" => manifest.schemas_dir = path_or_err(value, "schemas_dir")?,
                 "layout_dir" => manifest.layout_dir = path_or_err(value, "layout_dir")?,
+                "trash_dir" => manifest.trash_dir = Some(path_or_err(value, "trash_dir")?),
+                "timezone" => {
+                    let tz = value.as_str().ok_or_else(|| {
+                        InvalidManifestError::InvalidField(value.clone(), "timezone".into())
+                    })?;
+                    manifest.timezone = Some(validate_timezone(tz)?)
+                }
+                "markdown" => manifest.markdown = markdown_from_toml(&value)?,
+                "images" => manifest.images = images_from_toml(&value)?,
+                "formats" => manifest.formats = output_formats_from_toml(&value)?,
+                "minify" => manifest.minify = minify_from_toml(&value)?,
+                "fingerprint_assets" => {
+                    manifest.fingerprint_assets = value.as_bool().ok_or_else(|| {
+                        InvalidManifestError::InvalidField(
+                            value.clone(),
+                            "fingerprint_assets".into(),
+                        )
+                    })?
+                }
                 "object_file" => {
                     manifest.object_definition_file = path_or_err(value, "object_file")?
                 }
@@ -371,6 +558,7 @@ impl Manifest {
                 _ => {}
             }
         }
+        manifest.validate()?;
         Ok(manifest)
     }
 
@@ -424,6 +612,41 @@ impl Manifest {
             ManifestField::LayoutDir => {
                 Some(Value::String(self.layout_dir.to_string_lossy().to_string()))
             }
+            ManifestField::Locales => {
+                if self.locales.is_empty() {
+                    None
+                } else {
+                    Some(Value::Array(
+                        self.locales
+                            .iter()
+                            .map(|v| Value::String(v.to_string()))
+                            .collect(),
+                    ))
+                }
+            }
+            ManifestField::Timezone => self.timezone.to_owned().map(Value::String),
+            ManifestField::Markdown => Some(Value::Table(self.markdown.to_toml())),
+            ManifestField::Images => Some(Value::Table(self.images.to_toml())),
+            ManifestField::Formats => Some(Value::Table(formats_to_toml(&self.formats))),
+            ManifestField::FingerprintAssets => {
+                self.fingerprint_assets.then_some(Value::Boolean(true))
+            }
+            ManifestField::Minify => {
+                if self.minify.is_empty() {
+                    None
+                } else {
+                    Some(Value::Array(
+                        self.minify
+                            .iter()
+                            .map(|v| Value::String(v.to_string()))
+                            .collect(),
+                    ))
+                }
+            }
+            ManifestField::TrashDir => self
+                .trash_dir
+                .as_ref()
+                .map(|t| Value::String(t.to_string_lossy().to_string())),
             ManifestField::EditorTypes => {
                 let mut map = toml::map::Map::new();
                 for (type_name, type_val) in &self.editor_types {
@@ -465,6 +688,40 @@ impl Manifest {
                     })?
                     .to_string();
             }
+            if let Some(schema) = info_map.get("schema") {
+                let invalid = || {
+                    InvalidManifestError::InvalidField(
+                        schema.to_owned(),
+                        format!("{type_name}.schema"),
+                    )
+                };
+                if editor_type.alias_of != "meta" {
+                    return Err(invalid());
+                }
+                editor_type.schema = Some(
+                    schema
+                        .as_table()
+                        .and_then(|t| MetaSchema::from_toml(t).ok())
+                        .ok_or_else(invalid)?,
+                );
+            }
+            if let Some(markdown) = info_map.get("markdown") {
+                let invalid = || {
+                    InvalidManifestError::InvalidField(
+                        markdown.to_owned(),
+                        format!("{type_name}.markdown"),
+                    )
+                };
+                if editor_type.alias_of != "markdown" {
+                    return Err(invalid());
+                }
+                editor_type.markdown = Some(
+                    markdown
+                        .as_table()
+                        .and_then(|t| MarkdownOptions::from_toml(t).ok())
+                        .ok_or_else(invalid)?,
+                );
+            }
             if let Some(validator_val) = info_map.get("validate") {
                 let is_nested_type = NESTED_TYPES.contains(&&editor_type.alias_of[..]);
                 editor_type.validate = match validator_val {
@@ -528,7 +785,13 @@ impl Manifest {
         Ok(())
     }
 
-    pub fn set(&mut self, field: &ManifestField, value: String) {
+    /// Sets a field from its string form, see field_as_string. Tables, like
+    /// markdown options, are set from their toml.
+    pub fn set(
+        &mut self,
+        field: &ManifestField,
+        value: String,
+    ) -> Result<(), InvalidManifestError> {
         match field {
             ManifestField::ArchivalVersion => self.archival_version = Some(value),
             ManifestField::ObjectDefinitionFile => {
@@ -538,7 +801,9 @@ impl Manifest {
             ManifestField::SiteName => self.site_name = Some(value),
             ManifestField::CdnUrl => self.uploads_url = Some(value),
             ManifestField::Prebuild => {
-                todo!("Prebuild is not modifiable via events")
+                return Err(InvalidManifestError::NotSettable(
+                    field.field_name().to_string(),
+                ))
             }
             ManifestField::ObjectsDir => self.objects_dir = PathBuf::from(value),
             ManifestField::PagesDir => self.pages_dir = PathBuf::from(value),
@@ -546,10 +811,36 @@ impl Manifest {
             ManifestField::StaticDir => self.static_dir = PathBuf::from(value),
             ManifestField::SchemasDir => self.schemas_dir = PathBuf::from(value),
             ManifestField::LayoutDir => self.layout_dir = PathBuf::from(value),
+            ManifestField::TrashDir => self.trash_dir = Some(PathBuf::from(value)),
+            ManifestField::Timezone => self.timezone = Some(validate_timezone(&value)?),
+            ManifestField::Markdown => {
+                self.markdown = markdown_from_toml(&table_value(field, value)?)?
+            }
+            ManifestField::Images => self.images = images_from_toml(&table_value(field, value)?)?,
+            ManifestField::Formats => {
+                self.formats = output_formats_from_toml(&table_value(field, value)?)?
+            }
+            ManifestField::FingerprintAssets => self.fingerprint_assets = value == "true",
+            ManifestField::Minify => {
+                self.minify = minify_from_toml(&Value::Array(
+                    value
+                        .split(',')
+                        .map(|o| o.trim())
+                        .filter(|o| !o.is_empty())
+                        .map(|o| Value::String(o.to_string()))
+                        .collect(),
+                ))?
+            }
+            ManifestField::Locales => {
+                self.locales = value.split(',').map(|l| l.trim().to_string()).collect()
+            }
             ManifestField::EditorTypes => {
-                todo!("EditorTypes are not modifiable via events")
+                return Err(InvalidManifestError::NotSettable(
+                    field.field_name().to_string(),
+                ))
             }
         }
+        Ok(())
     }
 
     pub fn field_as_string(&self, field: &ManifestField) -> String {
@@ -558,6 +849,7 @@ impl Manifest {
                 Value::Array(a) => toml::to_string(&a).unwrap_or_default(),
                 Value::String(s) => s,
                 Value::Table(t) => toml::to_string(&t).unwrap_or_default(),
+                Value::Boolean(b) => b.to_string(),
                 _ => panic!("unsupported manifest field type"),
             },
             None => String::default(),
@@ -576,6 +868,14 @@ impl Manifest {
             ManifestField::BuildDir,
             ManifestField::StaticDir,
             ManifestField::ObjectsDir,
+            ManifestField::TrashDir,
+            ManifestField::Locales,
+            ManifestField::Timezone,
+            ManifestField::Markdown,
+            ManifestField::Images,
+            ManifestField::FingerprintAssets,
+            ManifestField::Minify,
+            ManifestField::Formats,
             ManifestField::EditorTypes,
         ]
     }
@@ -593,6 +893,19 @@ impl Manifest {
         toml::to_string_pretty(&write_obj)
     }
 
+    pub fn timezone_offset(&self) -> UtcOffset {
+        self.timezone
+            .as_deref()
+            .and_then(parse_timezone)
+            .unwrap_or(UtcOffset::UTC)
+    }
+
+    /// The first configured locale is the default, and is the locale of
+    /// the values stored directly on objects.
+    pub fn default_locale(&self) -> Option<&str> {
+        self.locales.first().map(|l| l.as_str())
+    }
+
     pub fn watched_paths(&self) -> Vec<String> {
         [
             &self.object_definition_file,
@@ -611,6 +924,7 @@ impl Manifest {
 mod tests {
 
     use super::*;
+    use crate::fields::MetaType;
 
     fn full_manifest_content() -> &'static str {
         "archival_version = '0.8.0'
@@ -623,6 +937,22 @@ mod tests {
         static_dir = 'm_public'
         layout_dir = 'm_layout'
         uploads_url = 'https://uploads.archival.dev'
+        trash_dir = 'm_trash'
+        locales = ['en', 'es']
+        timezone = '-08:00'
+        fingerprint_assets = true
+        minify = ['html', 'css']
+        [markdown]
+        tables = true
+        raw_html = true
+        [images]
+        transform_url = 'https://example.imgix.net/{sha}?{options}'
+        option_names = { width = 'w' }
+        [images.variants]
+        widths = [400, 800]
+        formats = ['webp']
+        [formats.ics]
+        mime_type = 'text/calendar'
         [editor_types.day]
         type = 'date'
         validate = ['\\d{2}/\\d{2}/\\d{4}']
@@ -635,6 +965,13 @@ mod tests {
         [[editor_types.custom.validate]]
         path = 'field_b'
         validate = '.+'
+        [editor_types.custom.schema]
+        field_a = 'string'
+        field_b = 'string'
+        extra = { count = 'number' }
+        [editor_types.comment]
+        type = 'markdown'
+        markdown = { raw_html = false }
         "
     }
 
@@ -657,6 +994,32 @@ mod tests {
             m.uploads_url,
             Some("https://uploads.archival.dev".to_string())
         );
+        assert_eq!(m.trash_dir, Some(Path::new("m_trash").to_path_buf()));
+        assert_eq!(m.locales, vec!["en", "es"]);
+        assert_eq!(m.default_locale(), Some("en"));
+        assert_eq!(m.timezone, Some("-08:00".to_string()));
+        assert_eq!(m.timezone_offset().whole_hours(), -8);
+        assert!(m.fingerprint_assets);
+        assert_eq!(m.minify, vec!["html", "css"]);
+        assert_eq!(m.markdown.tables, Some(true));
+        assert_eq!(m.markdown.raw_html, Some(true));
+        assert_eq!(m.markdown.footnotes, None);
+        assert_eq!(
+            m.images.transform_url,
+            Some("https://example.imgix.net/{sha}?{options}".to_string())
+        );
+        assert_eq!(m.images.option_names["width"], "w");
+        assert_eq!(m.images.variants.as_ref().unwrap().widths, vec![400, 800]);
+        assert_eq!(m.formats["ics"].mime_type, "text/calendar");
+        assert_eq!(m.formats["ics"].escape, crate::Escaping::None);
+        assert_eq!(
+            m.editor_types["comment"]
+                .markdown
+                .as_ref()
+                .unwrap()
+                .raw_html,
+            Some(false)
+        );
         assert_eq!(m.prebuild.len(), 1);
         let t1 = &m.editor_types["day"];
         assert_eq!(t1.alias_of, "date");
@@ -675,6 +1038,10 @@ mod tests {
             "https://editor.archival.dev/editors/json/editor.html"
         );
         assert_eq!(t2.validate.len(), 2);
+        let schema = t2.schema.as_ref().unwrap();
+        assert_eq!(schema.0.len(), 3);
+        assert!(matches!(schema.0["extra"], MetaType::Map(_)));
+        assert!(m.editor_types["day"].schema.is_none());
         assert!(matches!(
             t2.validate[0],
             ManifestEditorTypeValidator::Path(_)
@@ -697,6 +1064,62 @@ mod tests {
         assert!(manifest_output.contains("[editor_types.custom]"));
         assert!(manifest_output.contains("editor_url = \""));
         assert!(manifest_output.contains("[[editor_types.custom.validate]]"));
+        assert!(manifest_output.contains("[editor_types.custom.schema]"));
+        Ok(())
+    }
+
+    #[test]
+    fn setting_fields() -> Result<(), Box<dyn Error>> {
+        let mut m = Manifest::default(Path::new(""));
+        m.set(&ManifestField::SiteName, "My Site".to_string())?;
+        assert_eq!(m.site_name, Some("My Site".to_string()));
+        m.set(
+            &ManifestField::Markdown,
+            "tables = true\nraw_html = false".to_string(),
+        )?;
+        assert_eq!(m.markdown.tables, Some(true));
+        let markdown = m.field_as_string(&ManifestField::Markdown);
+        let mut reset = Manifest::default(Path::new(""));
+        reset.set(&ManifestField::Markdown, markdown)?;
+        assert_eq!(reset.markdown, m.markdown);
+        m.set(
+            &ManifestField::Formats,
+            "[ics]\nmime_type = \"text/calendar\"".to_string(),
+        )?;
+        assert_eq!(m.formats["ics"].mime_type, "text/calendar");
+        m.set(
+            &ManifestField::Images,
+            "transform_url = \"https://img.test/{sha}\"".to_string(),
+        )?;
+        assert!(m.images.transform_url.is_some());
+        assert!(m
+            .set(&ManifestField::Markdown, "tablez = true".to_string())
+            .is_err());
+        m.set(&ManifestField::Minify, "html, css".to_string())?;
+        assert_eq!(m.minify, vec!["html", "css"]);
+        assert!(m
+            .set(&ManifestField::Minify, "html,xml".to_string())
+            .is_err());
+        assert_eq!(m.minify, vec!["html", "css"]);
+        m.set(&ManifestField::Minify, String::new())?;
+        assert!(m.minify.is_empty());
+        assert!(m.set(&ManifestField::EditorTypes, String::new()).is_err());
+        assert!(m.set(&ManifestField::Prebuild, String::new()).is_err());
+        m.set(&ManifestField::Timezone, "+05:30".to_string())?;
+        assert_eq!(m.timezone_offset().whole_minutes(), 330);
+        assert!(matches!(
+            m.set(&ManifestField::Timezone, "America/Los_Angeles".to_string()),
+            Err(InvalidManifestError::UnsupportedTimezone(_))
+        ));
+        assert_eq!(m.timezone, Some("+05:30".to_string()));
+        let named = Manifest::from_string(
+            Path::new(""),
+            "timezone = 'America/Los_Angeles'".to_string(),
+        );
+        assert!(named
+            .unwrap_err()
+            .to_string()
+            .contains("use UTC or a fixed offset"));
         Ok(())
     }
 }
@@ -0,0 +1,227 @@
+use crate::page::TemplateType;
+use std::{cell::RefCell, fmt, path::Path};
+
+/// How much minification saved, for the build summary.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MinifyStats {
+    pub files: usize,
+    pub original_bytes: usize,
+    pub minified_bytes: usize,
+}
+
+impl MinifyStats {
+    pub fn add(&mut self, other: &MinifyStats) {
+        self.files += other.files;
+        self.original_bytes += other.original_bytes;
+        self.minified_bytes += other.minified_bytes;
+    }
+
+    pub fn saved_bytes(&self) -> usize {
+        self.original_bytes - self.minified_bytes
+    }
+}
+
+impl fmt::Display for MinifyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "minified {} file{}, saving {} bytes ({:.1}%)",
+            self.files,
+            if self.files == 1 { "" } else { "s" },
+            self.saved_bytes(),
+            if self.original_bytes == 0 {
+                0.0
+            } else {
+                self.saved_bytes() as f64 / self.original_bytes as f64 * 100.0
+            }
+        )
+    }
+}
+
+/// Minifies build output, for the outputs listed in the manifest's `minify`.
+/// Without the `minify` feature, outputs are written as they are.
+#[derive(Debug, Default)]
+pub struct Minifier {
+    outputs: Vec<String>,
+    stats: RefCell<MinifyStats>,
+}
+
+impl Minifier {
+    pub fn new(outputs: &[String]) -> Self {
+        Self {
+            outputs: outputs.to_vec(),
+            stats: RefCell::new(MinifyStats::default()),
+        }
+    }
+
+    pub fn stats(&self) -> MinifyStats {
+        *self.stats.borrow()
+    }
+
+    /// Minifies a rendered page, after layout post-processing.
+    pub fn page(&self, template_type: &TemplateType, rendered: String) -> String {
+        let output = match template_type {
+            TemplateType::Default | TemplateType::Html => "html",
+            TemplateType::Css => "css",
+            TemplateType::Json => "json",
+            _ => return rendered,
+        };
+        self.minify(output, &rendered).unwrap_or(rendered)
+    }
+
+    /// Minifies a static css or js file.
+    pub fn static_file(&self, file: &Path, content: Vec<u8>) -> Vec<u8> {
+        let output = match file.extension().and_then(|e| e.to_str()) {
+            Some("css") => "css",
+            Some("js") => "js",
+            _ => return content,
+        };
+        match std::str::from_utf8(&content)
+            .ok()
+            .and_then(|c| self.minify(output, c))
+        {
+            Some(minified) => minified.into_bytes(),
+            None => content,
+        }
+    }
+
+    /// The minified content, when it is smaller.
+    fn minify(&self, output: &str, content: &str) -> Option<String> {
+        if !self.outputs.iter().any(|o| o == output) {
+            return None;
+        }
+        let minified = minify(output, content)?;
+        if minified.len() >= content.len() {
+            return None;
+        }
+        let mut stats = self.stats.borrow_mut();
+        stats.files += 1;
+        stats.original_bytes += content.len();
+        stats.minified_bytes += minified.len();
+        Some(minified)
+    }
+}
+
+/// Removes whitespace outside of strings, keeping the order of keys.
+fn minify_json(content: &str) -> Option<String> {
+    serde_json::from_str::<serde::de::IgnoredAny>(content).ok()?;
+    let mut minified = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in content.chars() {
+        if in_string {
+            minified.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if !c.is_whitespace() {
+            in_string = c == '"';
+            minified.push(c);
+        }
+    }
+    Some(minified)
+}
+
+#[cfg(feature = "minify")]
+fn minify(output: &str, content: &str) -> Option<String> {
+    use lightningcss::stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet};
+    match output {
+        "html" => {
+            let cfg = minify_html::Cfg {
+                minify_css: true,
+                minify_js: true,
+                ..minify_html::Cfg::spec_compliant()
+            };
+            String::from_utf8(minify_html::minify(content.as_bytes(), &cfg)).ok()
+        }
+        "css" => {
+            let mut stylesheet = StyleSheet::parse(content, ParserOptions::default()).ok()?;
+            stylesheet.minify(MinifyOptions::default()).ok()?;
+            let printed = stylesheet
+                .to_css(PrinterOptions {
+                    minify: true,
+                    ..PrinterOptions::default()
+                })
+                .ok()?;
+            Some(printed.code)
+        }
+        "js" => {
+            let mut minified = vec![];
+            minify_js::minify(
+                &minify_js::Session::new(),
+                minify_js::TopLevelMode::Global,
+                content.as_bytes(),
+                &mut minified,
+            )
+            .ok()?;
+            String::from_utf8(minified).ok()
+        }
+        "json" => minify_json(content),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "minify"))]
+fn minify(output: &str, content: &str) -> Option<String> {
+    match output {
+        // json needs no dependencies
+        "json" => minify_json(content),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json() {
+        let minifier = Minifier::new(&["json".to_string()]);
+        assert_eq!(
+            minifier.page(
+                &TemplateType::Json,
+                "{\n  \"b\": \"a \\\" b\",\n  \"a\": [1, 2]\n}\n".to_string()
+            ),
+            "{\"b\":\"a \\\" b\",\"a\":[1,2]}"
+        );
+        // Invalid json is left alone
+        assert_eq!(
+            minifier.page(&TemplateType::Json, "{ a }".to_string()),
+            "{ a }"
+        );
+        // As are outputs that aren't configured
+        assert_eq!(
+            minifier.page(&TemplateType::Html, "<p> a </p>  ".to_string()),
+            "<p> a </p>  "
+        );
+        assert_eq!(minifier.stats().files, 1);
+    }
+
+    #[cfg(feature = "minify")]
+    #[test]
+    fn html_css_and_js() {
+        let minifier = Minifier::new(&["html".to_string(), "css".to_string(), "js".to_string()]);
+        let html = minifier.page(
+            &TemplateType::Html,
+            "<html>\n  <body>\n    <p>  Hello  </p>\n    <style>\n      p { color: #ff0000; }\n    </style>\n  </body>\n</html>\n"
+                .to_string(),
+        );
+        assert_eq!(html, "<body><p>Hello</p><style>p{color:red}</style>");
+        let css = minifier.static_file(
+            Path::new("style/theme.css"),
+            b"body {\n  margin: 0px;\n}\n".to_vec(),
+        );
+        assert_eq!(css, b"body{margin:0}");
+        let js = minifier.static_file(
+            Path::new("app.js"),
+            b"function greet(name) {\n  return 'hi ' + name;\n}\n".to_vec(),
+        );
+        assert!(js.len() < 40);
+        let stats = minifier.stats();
+        assert_eq!(stats.files, 3);
+        assert!(stats.to_string().starts_with("minified 3 files, saving "));
+    }
+}
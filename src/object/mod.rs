@@ -1,9 +1,11 @@
 pub use crate::value_path::{ValuePath, ValuePathComponent};
 use crate::{
     events::AddObjectValue,
-    fields::{FieldType, FieldValue, InvalidFieldError, ObjectValues},
+    fields::{
+        FieldConfig, FieldType, FieldValue, InvalidFieldError, MarkdownContext, ObjectValues,
+    },
     manifest::{EditorTypes, ManifestEditorTypeValidator},
-    object_definition::ObjectDefinition,
+    object_definition::{ObjectDefinition, BLOCK_TYPE},
     reserved_fields::{self, is_reserved_field},
 };
 use liquid::{
@@ -11,7 +13,12 @@ use liquid::{
     ObjectView, ValueView,
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, error::Error, fmt::Debug, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::Debug,
+    path::Path,
+};
 use toml::Table;
 use tracing::{instrument, warn};
 mod object_entry;
@@ -25,6 +32,9 @@ pub struct Object {
     pub order: i32,
     pub path: String,
     pub values: ObjectValues,
+    /// Per-locale values for translatable fields, keyed by locale.
+    #[serde(default)]
+    pub translations: BTreeMap<String, ObjectValues>,
 }
 
 impl Object {
@@ -72,6 +82,9 @@ impl Object {
                 }
             }
         }
+        if let (Some(schema), FieldValue::Meta(meta)) = (field_type.meta_schema(), field_value) {
+            schema.validate(meta)?;
+        }
         Ok(())
     }
 
@@ -117,13 +130,24 @@ impl Object {
                                 index,
                                 child: value.to_string(),
                             })?;
-                    let object = Object::values_from_table(
-                        file,
-                        table,
-                        child_def,
-                        custom_types,
-                        skip_validation,
-                    )?;
+                    let object = if child_def.is_blocks() {
+                        Object::block_values_from_table(
+                            file,
+                            def_key,
+                            table,
+                            child_def,
+                            custom_types,
+                            skip_validation,
+                        )?
+                    } else {
+                        Object::values_from_table(
+                            file,
+                            table,
+                            child_def,
+                            custom_types,
+                            skip_validation,
+                        )?
+                    };
                     objects.push(object);
                 }
                 let field_value = FieldValue::Objects(objects);
@@ -136,6 +160,34 @@ impl Object {
         Ok(values)
     }
 
+    /// Parses an item of a blocks field, using the definition selected by
+    /// its `type` key.
+    fn block_values_from_table(
+        file: &Path,
+        key: &str,
+        table: &Table,
+        blocks_def: &ObjectDefinition,
+        custom_types: &EditorTypes,
+        skip_validation: bool,
+    ) -> Result<ObjectValues, Box<dyn Error>> {
+        let block_type = table
+            .get(BLOCK_TYPE)
+            .and_then(|t| t.as_str())
+            .unwrap_or_default();
+        let block_def = blocks_def.blocks.get(block_type).ok_or_else(|| {
+            InvalidFieldError::InvalidBlockType(key.to_string(), block_type.to_string())
+        })?;
+        let mut table = table.clone();
+        table.remove(BLOCK_TYPE);
+        let mut values =
+            Object::values_from_table(file, &table, block_def, custom_types, skip_validation)?;
+        values.insert(
+            BLOCK_TYPE.to_string(),
+            FieldValue::String(block_type.to_string()),
+        );
+        Ok(values)
+    }
+
     #[instrument(skip(definition, table))]
     pub fn from_table(
         definition: &ObjectDefinition,
@@ -146,6 +198,12 @@ impl Object {
     ) -> Result<Object, Box<dyn Error>> {
         let values =
             Object::values_from_table(file, table, definition, custom_types, skip_validation)?;
+        let translations = match table.get(reserved_fields::TRANSLATIONS) {
+            Some(translations) => {
+                Object::translations_from_toml(file, translations, definition, &values)?
+            }
+            None => BTreeMap::new(),
+        };
         let mut order = -1;
         if let Some(t_order) = table.get(reserved_fields::ORDER) {
             if let Some(int_order) = t_order.as_integer() {
@@ -164,10 +222,161 @@ impl Object {
             object_name: definition.name.clone(),
             order,
             values,
+            translations,
         };
         Ok(object)
     }
 
+    #[instrument(skip(definition, value, values))]
+    fn translations_from_toml(
+        file: &Path,
+        value: &toml::Value,
+        definition: &ObjectDefinition,
+        values: &ObjectValues,
+    ) -> Result<BTreeMap<String, ObjectValues>, Box<dyn Error>> {
+        let mut translations = BTreeMap::new();
+        let locales = value
+            .as_table()
+            .ok_or_else(|| InvalidFieldError::TypeMismatch {
+                field: reserved_fields::TRANSLATIONS.to_string(),
+                field_type: "table".to_string(),
+                value: value.to_string(),
+            })?;
+        for (locale, locale_values) in locales {
+            let table =
+                locale_values
+                    .as_table()
+                    .ok_or_else(|| InvalidFieldError::TypeMismatch {
+                        field: format!("{}.{}", reserved_fields::TRANSLATIONS, locale),
+                        field_type: "table".to_string(),
+                        value: locale_values.to_string(),
+                    })?;
+            translations.insert(
+                locale.to_string(),
+                Object::translated_values_from_table(file, table, definition, values)?,
+            );
+        }
+        Ok(translations)
+    }
+
+    /// Parses the values for a single locale. Unlike `values_from_table`,
+    /// this only includes values that are present, and ignores any fields
+    /// that are not marked as translatable. `values` are the default values
+    /// at the same level, which translated children are matched to by index.
+    fn translated_values_from_table(
+        file: &Path,
+        table: &Table,
+        definition: &ObjectDefinition,
+        values: &ObjectValues,
+    ) -> Result<ObjectValues, Box<dyn Error>> {
+        let mut translated = ObjectValues::new();
+        for (key, value) in table {
+            if let Some(field_type) = definition.fields.get(key) {
+                if !definition.is_translatable(key) {
+                    warn!("{}: field {} is not translatable", file.display(), key);
+                    continue;
+                }
+                translated.insert(
+                    key.to_string(),
+                    FieldValue::from_toml(key, field_type, value)?,
+                );
+            } else if let Some(child_def) = definition.children.get(key) {
+                let m_objects = value
+                    .as_array()
+                    .ok_or_else(|| InvalidFieldError::NotAnArray {
+                        key: key.to_string(),
+                        value: value.to_string(),
+                    })?;
+                let children = match values.get(key) {
+                    Some(FieldValue::Objects(children)) => &children[..],
+                    _ => &[],
+                };
+                let mut objects: Vec<ObjectValues> = Vec::new();
+                for (index, object) in m_objects.iter().enumerate() {
+                    let table =
+                        object
+                            .as_table()
+                            .ok_or_else(|| InvalidFieldError::InvalidChild {
+                                key: key.to_owned(),
+                                index,
+                                child: value.to_string(),
+                            })?;
+                    let default_child = children.get(index).cloned().unwrap_or_default();
+                    if child_def.is_blocks() {
+                        objects.push(Object::translated_block_values_from_table(
+                            file,
+                            table,
+                            child_def,
+                            &default_child,
+                        )?);
+                    } else {
+                        objects.push(Object::translated_values_from_table(
+                            file,
+                            table,
+                            child_def,
+                            &default_child,
+                        )?);
+                    }
+                }
+                translated.insert(key.to_string(), FieldValue::Objects(objects));
+            } else {
+                warn!("{}: unknown translated field {}", file.display(), key);
+            }
+        }
+        Ok(translated)
+    }
+
+    /// Translated block items may omit their `type`, in which case they use
+    /// the type of the default block at the same index. The type is kept on
+    /// the translated values so they are only applied to a matching block.
+    fn translated_block_values_from_table(
+        file: &Path,
+        table: &Table,
+        blocks_def: &ObjectDefinition,
+        values: &ObjectValues,
+    ) -> Result<ObjectValues, Box<dyn Error>> {
+        let mut table = table.clone();
+        let block_type = match table.remove(BLOCK_TYPE) {
+            Some(toml::Value::String(t)) => Some(t),
+            _ => match values.get(BLOCK_TYPE) {
+                Some(FieldValue::String(t)) => Some(t.to_string()),
+                _ => None,
+            },
+        };
+        match block_type
+            .as_ref()
+            .and_then(|t| blocks_def.blocks.get(t).map(|def| (t, def)))
+        {
+            Some((block_type, block_def)) => {
+                let mut translated =
+                    Object::translated_values_from_table(file, &table, block_def, values)?;
+                translated.insert(
+                    BLOCK_TYPE.to_string(),
+                    FieldValue::String(block_type.to_string()),
+                );
+                Ok(translated)
+            }
+            None => {
+                warn!(
+                    "{}: no block of {} matches translated values",
+                    file.display(),
+                    blocks_def.name
+                );
+                Ok(ObjectValues::new())
+            }
+        }
+    }
+
+    /// Returns a copy of this object with the values for the given locale
+    /// applied over the default values. Children are matched by index.
+    pub fn localized(&self, locale: &str) -> Object {
+        let mut object = self.clone();
+        if let Some(translated) = self.translations.get(locale) {
+            merge_translated(&mut object.values, translated);
+        }
+        object
+    }
+
     pub fn from_def(
         definition: &ObjectDefinition,
         filename: &str,
@@ -183,9 +392,23 @@ impl Object {
             path: path.to_string_lossy().to_string(),
             order,
             values,
+            translations: BTreeMap::new(),
         };
         for default in defaults {
-            default.path.set_in_object(&mut object, Some(default.value));
+            // Values are normalized the same way as when editing a field
+            let mut parent = default.path.clone();
+            let field_type = match ValuePathComponent::as_key(parent.pop()) {
+                Some(key) => parent
+                    .get_object_definition(definition, &object)
+                    .ok()
+                    .and_then(|def| def.fields.get(&key)),
+                None => None,
+            };
+            let value = match field_type {
+                Some(field_type) => default.value.normalized(field_type)?,
+                None => default.value,
+            };
+            default.path.set_in_object(&mut object, Some(value));
         }
         Ok(object)
     }
@@ -198,15 +421,122 @@ impl Object {
                 write_obj.insert(key.to_string(), val);
             }
         }
+        if !self.translations.is_empty() {
+            let mut translations = Table::new();
+            for (locale, values) in &self.translations {
+                let mut locale_values = Table::new();
+                for (key, val) in values {
+                    if let Some(val) = val.into() {
+                        locale_values.insert(key.to_string(), val);
+                    }
+                }
+                translations.insert(locale.to_string(), toml::Value::Table(locale_values));
+            }
+            write_obj.insert(
+                reserved_fields::TRANSLATIONS.to_string(),
+                toml::Value::Table(translations),
+            );
+        }
         toml::to_string_pretty(&write_obj)
     }
 
-    pub fn liquid_object(&self) -> Value {
-        let mut values: liquid::model::Object = self
-            .values
+    /// Converts values to liquid. When a definition is provided, values are
+    /// rendered using their field types, e.g. markdown fields with their own
+    /// markdown options.
+    ///
+    /// Objects with markdown fields also get a `toc` map of field name to the
+    /// headings of that field, see TocEntry. Markdown is rendered in the
+    /// given context, with these values as the shortcode `object`.
+    pub fn liquid_values(
+        values: &ObjectValues,
+        definition: Option<&ObjectDefinition>,
+        context: &MarkdownContext,
+    ) -> liquid::model::Object {
+        let markdown_options = |key: &str| {
+            let site_options = FieldConfig::get().markdown.clone();
+            match definition
+                .and_then(|d| d.fields.get(key))
+                .and_then(|t| t.markdown_options())
+            {
+                Some(options) => site_options.merge(options),
+                None => site_options,
+            }
+        };
+        let shortcode_object: Option<liquid::model::Object> = context.parser.map(|_| {
+            values
+                .iter()
+                .map(|(k, v)| {
+                    let value = match v {
+                        FieldValue::Markdown(markdown) => {
+                            Value::scalar(markdown_options(k).render(markdown))
+                        }
+                        _ => v.to_value(),
+                    };
+                    (KString::from_ref(k.as_index()), value)
+                })
+                .collect()
+        });
+        let context = MarkdownContext {
+            object: shortcode_object.as_ref(),
+            ..*context
+        };
+        let mut toc = liquid::model::Object::new();
+        let mut object: liquid::model::Object = values
             .iter()
-            .map(|(k, v)| (KString::from_ref(k.as_index()), v.to_value()))
+            .map(|(k, v)| {
+                let value = match v {
+                    FieldValue::Markdown(markdown) => {
+                        let (html, entries) =
+                            markdown_options(k).render_with_toc(markdown, &context);
+                        toc.insert(KString::from_ref(k.as_index()), entries.to_value());
+                        Value::scalar(html)
+                    }
+                    FieldValue::Objects(children) => {
+                        let child_def = definition.and_then(|d| d.children.get(k));
+                        Value::Array(
+                            children
+                                .iter()
+                                .map(|child| {
+                                    let child_def = match child_def {
+                                        Some(d) if d.is_blocks() => d.block_definition(child),
+                                        d => d,
+                                    };
+                                    Value::Object(Object::liquid_values(child, child_def, &context))
+                                })
+                                .collect(),
+                        )
+                    }
+                    _ => v.to_value(),
+                };
+                (KString::from_ref(k.as_index()), value)
+            })
             .collect();
+        if !toc.is_empty() && !is_user_field(reserved_fields::TOC, values, definition) {
+            object.insert(
+                KString::from_static(reserved_fields::TOC),
+                Value::Object(toc),
+            );
+        }
+        object
+    }
+
+    /// Converts this object to liquid, see liquid_values. When the context has
+    /// links, objects also get the `backlinks` that point to them.
+    pub fn liquid_object(
+        &self,
+        definition: Option<&ObjectDefinition>,
+        context: &MarkdownContext,
+    ) -> Value {
+        let mut values = Object::liquid_values(&self.values, definition, context);
+        if let Some(links) = context
+            .links
+            .filter(|_| !is_user_field(reserved_fields::BACKLINKS, &self.values, definition))
+        {
+            values.insert(
+                KString::from_static(reserved_fields::BACKLINKS),
+                links.backlinks(self).to_value(),
+            );
+        }
         // Reserved/special
         if values.contains_key("path") {
             panic!("Objects may not define path key.");
@@ -220,6 +550,40 @@ impl Object {
     }
 }
 
+/// Whether an object has its own field with this name, which takes
+/// precedence over keys like `toc` that are added in templates.
+pub(crate) fn is_user_field(
+    key: &str,
+    values: &ObjectValues,
+    definition: Option<&ObjectDefinition>,
+) -> bool {
+    values.contains_key(key)
+        || definition.is_some_and(|d| d.fields.contains_key(key) || d.children.contains_key(key))
+}
+
+fn merge_translated(values: &mut ObjectValues, translated: &ObjectValues) {
+    for (key, value) in translated {
+        match (values.get_mut(key), value) {
+            (Some(FieldValue::Objects(children)), FieldValue::Objects(t_children)) => {
+                for (child, t_child) in children.iter_mut().zip(t_children) {
+                    // Translated blocks only apply to a block of the same
+                    // type.
+                    if t_child
+                        .get(BLOCK_TYPE)
+                        .is_some_and(|t| child.get(BLOCK_TYPE) != Some(t))
+                    {
+                        continue;
+                    }
+                    merge_translated(child, t_child);
+                }
+            }
+            _ => {
+                values.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -245,6 +609,486 @@ mod tests {
       number = 2.57"
     }
 
+    #[test]
+    fn meta_schema_validation() -> Result<(), Box<dyn Error>> {
+        let mut editor_types = HashMap::new();
+        editor_types.insert(
+            "review".to_string(),
+            crate::manifest::ManifestEditorType {
+                alias_of: "meta".to_string(),
+                schema: Some(crate::fields::MetaSchema::from_toml(&toml::from_str(
+                    "rating = \"number\"",
+                )?)?),
+                ..Default::default()
+            },
+        );
+        let defs = ObjectDefinition::from_table(
+            &toml::from_str(
+                "[post]
+                my_review = \"review\"
+                details = \"meta\"
+                _meta_schemas = { details = { author = { name = \"string\" } } }",
+            )?,
+            &editor_types,
+        )?;
+        let post_def = defs.get("post").unwrap();
+        let parse = |toml: &str| -> Result<Object, Box<dyn Error>> {
+            Object::from_table(
+                post_def,
+                Path::new("a-post"),
+                &toml::from_str(toml)?,
+                &editor_types,
+                false,
+            )
+        };
+        assert!(
+            parse("my_review = { rating = 5 }\ndetails = { author = { name = \"A\" } }").is_ok()
+        );
+        assert!(parse("my_review = { rating = \"five\" }").is_err());
+        assert!(parse("details = { author = { name = 1 } }").is_err());
+        assert!(parse("details = { editor = \"A\" }").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn format_field_parsing() -> Result<(), Box<dyn Error>> {
+        let defs = ObjectDefinition::from_table(
+            &toml::from_str(
+                "[post]
+                accent = \"color\"
+                website = \"url\"
+                contact = \"email\"
+                handle = \"slug\"
+                palette = \"color[]\"",
+            )?,
+            &HashMap::new(),
+        )?;
+        let post_def = defs.get("post").unwrap();
+        assert_eq!(post_def.fields.get("accent"), Some(&FieldType::Color));
+        let table: Table = toml::from_str(
+            "accent = \"#FA0\"
+            website = \"HTTPS://Archival.dev\"
+            contact = \"Hi@Archival.DEV\"
+            handle = \"My Post!\"
+            palette = [\"#000\", \"FFFFFF\"]",
+        )?;
+        let obj = Object::from_table(
+            post_def,
+            Path::new("a-post"),
+            &table,
+            &HashMap::new(),
+            false,
+        )?;
+        let string = |s: &str| Some(FieldValue::String(s.to_string()));
+        assert_eq!(obj.values.get("accent").cloned(), string("#ffaa00"));
+        assert_eq!(
+            obj.values.get("website").cloned(),
+            string("https://archival.dev/")
+        );
+        assert_eq!(
+            obj.values.get("contact").cloned(),
+            string("Hi@archival.dev")
+        );
+        assert_eq!(obj.values.get("handle").cloned(), string("my-post"));
+        assert_eq!(
+            obj.values.get("palette"),
+            Some(&FieldValue::List(vec![
+                FieldValue::String("#000000".to_string()),
+                FieldValue::String("#ffffff".to_string()),
+            ]))
+        );
+        let invalid: Table = toml::from_str("contact = \"not an email\"")?;
+        assert!(Object::from_table(
+            post_def,
+            Path::new("a-post"),
+            &invalid,
+            &HashMap::new(),
+            false,
+        )
+        .is_err());
+        // Values of new objects are normalized and validated too
+        let added = Object::from_def(
+            post_def,
+            "new-post",
+            1,
+            vec![AddObjectValue {
+                path: ValuePath::from_string("accent"),
+                value: FieldValue::String("#FA0".to_string()),
+            }],
+        )?;
+        assert_eq!(added.values.get("accent").cloned(), string("#ffaa00"));
+        assert!(Object::from_def(
+            post_def,
+            "new-post",
+            1,
+            vec![AddObjectValue {
+                path: ValuePath::from_string("website"),
+                value: FieldValue::String("javascript:alert(1)".to_string()),
+            }],
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn location_field_parsing() -> Result<(), Box<dyn Error>> {
+        let defs = ObjectDefinition::from_table(
+            &toml::from_str(
+                "[event]
+                venue = \"location\"",
+            )?,
+            &HashMap::new(),
+        )?;
+        let event_def = defs.get("event").unwrap();
+        let table: Table =
+            toml::from_str("venue = { lat = 45.5, lng = -122.6, name = \"Crystal Ballroom\" }")?;
+        let obj = Object::from_table(
+            event_def,
+            Path::new("an-event"),
+            &table,
+            &HashMap::new(),
+            false,
+        )?;
+        let Some(FieldValue::Location(venue)) = obj.values.get("venue") else {
+            panic!("venue is not a location");
+        };
+        assert_eq!((venue.lat, venue.lng), (45.5, -122.6));
+        assert_eq!(venue.name.as_deref(), Some("Crystal Ballroom"));
+        let reparsed = Object::from_table(
+            event_def,
+            Path::new("an-event"),
+            &toml::from_str(&obj.to_toml()?)?,
+            &HashMap::new(),
+            false,
+        )?;
+        assert_eq!(reparsed.values, obj.values);
+        let south = FieldValue::Location(crate::fields::Location::new(10.0, 0.0)?);
+        assert_eq!(
+            obj.values["venue"].compare(&south),
+            Some(std::cmp::Ordering::Greater)
+        );
+        let invalid: Table = toml::from_str("venue = { lat = 91, lng = 0 }")?;
+        assert!(Object::from_table(
+            event_def,
+            Path::new("an-event"),
+            &invalid,
+            &HashMap::new(),
+            false,
+        )
+        .is_err());
+        // Unset locations are left out rather than defaulting to 0, 0
+        let empty = Object::from_def(event_def, "new-event", 1, vec![])?;
+        assert_eq!(empty.values.get("venue"), None);
+        // Json objects only become locations in location fields
+        let json = serde_json::json!({"lat": 45.5, "lng": -122.6});
+        let value = FieldValue::try_from(&json)?;
+        assert!(matches!(value, FieldValue::Meta(_)));
+        assert!(matches!(
+            value.normalized(&FieldType::Location)?,
+            FieldValue::Location(l) if (l.lat, l.lng) == (45.5, -122.6)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn date_and_time_field_parsing() -> Result<(), Box<dyn Error>> {
+        let defs = ObjectDefinition::from_table(
+            &toml::from_str(
+                "[event]
+                day = \"date\"
+                starts_at = \"datetime\"
+                doors = \"time\"",
+            )?,
+            &HashMap::new(),
+        )?;
+        let event_def = defs.get("event").unwrap();
+        let table: Table = toml::from_str(
+            "day = 2024-01-31
+            starts_at = 2024-01-31T19:00:00-08:00
+            doors = \"18:30\"",
+        )?;
+        let obj = Object::from_table(
+            event_def,
+            Path::new("an-event"),
+            &table,
+            &HashMap::new(),
+            false,
+        )?;
+        let output = obj.to_toml()?;
+        assert!(output.contains("day = 2024-01-31\n"), "{}", output);
+        assert!(
+            output.contains("starts_at = 2024-01-31T19:00:00-08:00\n"),
+            "{}",
+            output
+        );
+        assert!(output.contains("doors = 18:30:00\n"), "{}", output);
+        let Some(FieldValue::Date(starts_at)) = obj.values.get("starts_at") else {
+            panic!("starts_at is not a date");
+        };
+        assert_eq!(starts_at.as_liquid_datetime().hour(), 19);
+        let invalid: Table = toml::from_str("doors = 2024-01-31")?;
+        assert!(Object::from_table(
+            event_def,
+            Path::new("an-event"),
+            &invalid,
+            &HashMap::new(),
+            false,
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn list_field_parsing() -> Result<(), Box<dyn Error>> {
+        let defs = ObjectDefinition::from_table(
+            &toml::from_str(
+                "[post]
+                tags = \"string[]\"
+                scores = \"number[]\"",
+            )?,
+            &HashMap::new(),
+        )?;
+        let post_def = defs.get("post").unwrap();
+        assert_eq!(
+            post_def.fields.get("tags"),
+            Some(&FieldType::List(Box::new(FieldType::String)))
+        );
+        let table: Table = toml::from_str(
+            "tags = [\"rust\", \"web\"]
+            scores = [1, 2.5]",
+        )?;
+        let obj = Object::from_table(
+            post_def,
+            Path::new("a-post"),
+            &table,
+            &HashMap::new(),
+            false,
+        )?;
+        assert_eq!(
+            obj.values.get("tags"),
+            Some(&FieldValue::List(vec![
+                FieldValue::String("rust".to_string()),
+                FieldValue::String("web".to_string()),
+            ]))
+        );
+        assert_eq!(
+            obj.values.get("scores"),
+            Some(&FieldValue::List(vec![
+                FieldValue::Number(1.0),
+                FieldValue::Number(2.5),
+            ]))
+        );
+        let output = obj.to_toml()?;
+        let reparsed = Object::from_table(
+            post_def,
+            Path::new("a-post"),
+            &toml::from_str(&output)?,
+            &HashMap::new(),
+            false,
+        )?;
+        assert_eq!(reparsed.values, obj.values);
+        // Invalid item types fail
+        let table: Table = toml::from_str("tags = [1, 2]")?;
+        assert!(Object::from_table(
+            post_def,
+            Path::new("a-post"),
+            &table,
+            &HashMap::new(),
+            false
+        )
+        .is_err());
+        assert_eq!(
+            FieldValue::from_string(
+                &"tags".to_string(),
+                &FieldType::List(Box::new(FieldType::String)),
+                "rust, web,".to_string()
+            )?,
+            obj.values["tags"]
+        );
+        match FieldValue::val_with_type(
+            &"tags".to_string(),
+            &FieldType::List(Box::new(FieldType::Number)),
+            "1, x".to_string(),
+        ) {
+            Err(e) => assert!(e.to_string().contains("tags"), "{}", e),
+            Ok(v) => panic!("parsed invalid list {:?}", v),
+        }
+        assert!(FieldType::from_str("image[]", &HashMap::new()).is_err());
+        assert!(FieldType::list(FieldType::Image).is_err());
+        assert_eq!(
+            FieldType::list(FieldType::Slug)?,
+            FieldType::List(Box::new(FieldType::Slug))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn translated_object_parsing() -> Result<(), Box<dyn Error>> {
+        let defs = ObjectDefinition::from_table(
+            &toml::from_str(
+                "[artist]
+                name = \"string\"
+                genre = \"string\"
+                _translatable = [\"name\"]
+                [artist.tour_dates]
+                venue = \"string\"
+                _translatable = [\"venue\"]",
+            )?,
+            &HashMap::new(),
+        )?;
+        let table: Table = toml::from_str(
+            "name = \"The Band\"
+            genre = \"rock\"
+            [[tour_dates]]
+            venue = \"The Hall\"
+            [[tour_dates]]
+            venue = \"The Park\"
+            [_translations.es]
+            name = \"La Banda\"
+            genre = \"roca\"
+            [[_translations.es.tour_dates]]
+            venue = \"El Salon\"",
+        )?;
+        let obj = Object::from_table(
+            defs.get("artist").unwrap(),
+            Path::new("the-band"),
+            &table,
+            &HashMap::new(),
+            false,
+        )?;
+        assert_eq!(obj.translations.len(), 1);
+        // Untranslatable fields are ignored
+        assert!(!obj.translations["es"].contains_key("genre"));
+        let es = obj.localized("es");
+        assert_eq!(
+            es.values.get("name"),
+            Some(&FieldValue::String("La Banda".to_string()))
+        );
+        assert_eq!(
+            es.values.get("genre"),
+            Some(&FieldValue::String("rock".to_string()))
+        );
+        if let Some(FieldValue::Objects(tour_dates)) = es.values.get("tour_dates") {
+            assert_eq!(
+                tour_dates[0].get("venue"),
+                Some(&FieldValue::String("El Salon".to_string()))
+            );
+            assert_eq!(
+                tour_dates[1].get("venue"),
+                Some(&FieldValue::String("The Park".to_string()))
+            );
+        } else {
+            panic!("missing tour dates");
+        }
+        // Unknown locales use the default values
+        assert_eq!(obj.localized("fr").values, obj.values);
+        let output = obj.to_toml()?;
+        assert!(output.contains("[_translations.es]"));
+        assert!(output.contains("name = \"La Banda\""));
+        Ok(())
+    }
+
+    #[test]
+    fn user_fields_named_toc_and_backlinks() -> Result<(), Box<dyn Error>> {
+        let defs = ObjectDefinition::from_table(
+            &toml::from_str(
+                "[doc]
+                body = \"markdown\"
+                toc = \"string\"
+                backlinks = \"string\"",
+            )?,
+            &HashMap::new(),
+        )?;
+        let doc_def = defs.get("doc").unwrap();
+        let table: Table = toml::from_str(
+            "body = \"# Heading\"
+            toc = \"my toc\"
+            backlinks = \"my links\"",
+        )?;
+        let obj = Object::from_table(doc_def, Path::new("a-doc"), &table, &HashMap::new(), false)?;
+        let links = crate::links::Links::default();
+        let context = MarkdownContext {
+            links: Some(&links),
+            ..Default::default()
+        };
+        let Value::Object(values) = obj.liquid_object(Some(doc_def), &context) else {
+            panic!("not an object");
+        };
+        assert_eq!(values["toc"], Value::scalar("my toc"));
+        assert_eq!(values["backlinks"], Value::scalar("my links"));
+        // Objects without these fields still get them
+        let other: Table = toml::from_str("body = \"# Heading\"")?;
+        let obj = Object::from_table(doc_def, Path::new("b-doc"), &other, &HashMap::new(), false)?;
+        let values = Object::liquid_values(&obj.values, None, &context);
+        assert!(values["toc"].as_object().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn translated_block_parsing() -> Result<(), Box<dyn Error>> {
+        let defs = ObjectDefinition::from_table(
+            &toml::from_str(
+                "[landing]
+                title = \"string\"
+                [landing.sections._blocks.hero]
+                heading = \"string\"
+                _translatable = [\"heading\"]
+                [landing.sections._blocks.quote]
+                heading = \"string\"
+                text = \"string\"
+                _translatable = [\"text\"]",
+            )?,
+            &HashMap::new(),
+        )?;
+        let table: Table = toml::from_str(
+            "title = \"Home\"
+            [[sections]]
+            type = \"quote\"
+            heading = \"Said\"
+            text = \"Words\"
+            [[sections]]
+            type = \"hero\"
+            heading = \"Hi\"
+            [[_translations.es.sections]]
+            text = \"Palabras\"
+            [[_translations.es.sections]]
+            heading = \"Hola\"",
+        )?;
+        let obj = Object::from_table(
+            defs.get("landing").unwrap(),
+            Path::new("home"),
+            &table,
+            &HashMap::new(),
+            false,
+        )?;
+        // Translated blocks take the type of the block at the same index
+        if let Some(FieldValue::Objects(sections)) = obj.translations["es"].get("sections") {
+            assert_eq!(
+                sections[0].get(BLOCK_TYPE),
+                Some(&FieldValue::String("quote".to_string()))
+            );
+            assert_eq!(
+                sections[1].get(BLOCK_TYPE),
+                Some(&FieldValue::String("hero".to_string()))
+            );
+        } else {
+            panic!("missing translated sections");
+        }
+        if let Some(FieldValue::Objects(sections)) = obj.localized("es").values.get("sections") {
+            assert_eq!(
+                sections[0].get("text"),
+                Some(&FieldValue::String("Palabras".to_string()))
+            );
+            assert_eq!(
+                sections[1].get("heading"),
+                Some(&FieldValue::String("Hola".to_string()))
+            );
+        } else {
+            panic!("missing sections");
+        }
+        Ok(())
+    }
+
     #[test]
     fn object_parsing() -> Result<(), Box<dyn Error>> {
         let defs = ObjectDefinition::from_table(
@@ -280,7 +1124,7 @@ mod tests {
             assert!(date.contains_key("ticket_link"));
             assert_eq!(
                 date.get("date").unwrap().liquid_date(),
-                FieldValue::Date(DateTime::from_ymd(2022, 12, 22)).liquid_date()
+                FieldValue::Date(DateTime::from_ymd(2022, 12, 22)?).liquid_date()
             );
             assert_eq!(
                 date.get("ticket_link").unwrap(),
@@ -18,6 +18,12 @@ impl ObjectEntry {
     pub fn from_object(object: Object) -> Self {
         Self::Object(object)
     }
+    pub fn localized(&self, locale: &str) -> Self {
+        match self {
+            Self::List(l) => Self::List(l.iter().map(|o| o.localized(locale)).collect()),
+            Self::Object(o) => Self::Object(o.localized(locale)),
+        }
+    }
 }
 
 pub struct ObjectEntryIterator<'a> {
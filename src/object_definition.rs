@@ -1,7 +1,9 @@
 use crate::{
-    fields::{field_type::InvalidFieldError, FieldType, ObjectValues},
+    fields::{field_type::InvalidFieldError, FieldType, MarkdownOptions, MetaSchema, ObjectValues},
     manifest::EditorTypes,
+    page::TemplateType,
     reserved_fields::{self, is_reserved_field, reserved_field_from_str, ReservedFieldError},
+    taxonomy::Taxonomy,
     FieldValue,
 };
 use serde::{Deserialize, Serialize};
@@ -13,6 +15,9 @@ use tracing::instrument;
 
 pub type ObjectDefinitions = BTreeMap<String, ObjectDefinition>;
 
+/// The key that stores the block type of each item in a blocks field.
+pub const BLOCK_TYPE: &str = "type";
+
 #[cfg(feature = "typescript")]
 mod typedefs {
     use typescript_type_def::{
@@ -27,18 +32,179 @@ mod typedefs {
     }
 }
 
+/// A page template that each object of a type is rendered with, to
+/// `{object name}/{filename}.{extension}`. Types may have several, e.g.
+///
+/// ```toml
+/// [recipe]
+/// template = "recipe"
+/// # or
+/// template = ["recipe", "recipe.json"]
+/// # or
+/// template = { html = "recipe", json = "recipe_api", "print.html" = "recipe_print" }
+/// ```
+///
+/// In a list, the extension is the type of the template, so
+/// `pages/recipe.json.liquid` renders to `recipe/{filename}.json`. In a table,
+/// keys are extensions, and the output format is their last part, so
+/// `print.html` renders html to `recipe/{filename}.print.html`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
+pub struct ObjectTemplate {
+    /// The template in the pages dir, without `.liquid`.
+    pub template: String,
+    pub extension: String,
+}
+
+impl ObjectTemplate {
+    pub fn from_toml(
+        object_name: &str,
+        value: &toml::Value,
+    ) -> Result<Vec<Self>, InvalidFieldError> {
+        let invalid = |template: &str| {
+            InvalidFieldError::InvalidTemplate(object_name.into(), template.into())
+        };
+        let templates = if let Some(template) = value.as_str() {
+            vec![Self::from_name(template)]
+        } else if let Some(templates) = value.as_array() {
+            templates
+                .iter()
+                .map(|t| {
+                    t.as_str()
+                        .map(Self::from_name)
+                        .ok_or_else(|| invalid(&t.to_string()))
+                })
+                .collect::<Result<Vec<Self>, InvalidFieldError>>()?
+        } else if let Some(templates) = value.as_table() {
+            templates
+                .iter()
+                .map(|(extension, t)| {
+                    Ok(Self {
+                        template: t
+                            .as_str()
+                            .ok_or_else(|| invalid(&t.to_string()))?
+                            .to_string(),
+                        extension: extension.to_string(),
+                    })
+                })
+                .collect::<Result<Vec<Self>, InvalidFieldError>>()?
+        } else {
+            return Err(invalid(&value.to_string()));
+        };
+        let mut extensions = vec![];
+        for template in &templates {
+            let extension = &template.extension;
+            if template.template.is_empty()
+                || extension.is_empty()
+                || extension.starts_with('.')
+                || extension.ends_with('.')
+                || extension.contains(['/', '\\'])
+                || extensions.contains(&extension)
+            {
+                return Err(invalid(&template.template));
+            }
+            extensions.push(extension);
+        }
+        Ok(templates)
+    }
+
+    fn from_name(template: &str) -> Self {
+        let template_type = TemplateType::parse_path(&format!("{}.liquid", template))
+            .map(|(_, t)| t)
+            .unwrap_or_default();
+        Self {
+            template: template.to_string(),
+            extension: template_type.extension().to_string(),
+        }
+    }
+
+    /// The output format, from the last part of the extension.
+    pub fn template_type(&self) -> TemplateType {
+        TemplateType::from_ext(self.extension.rsplit('.').next().unwrap_or_default())
+    }
+
+    pub fn is_html(&self) -> bool {
+        self.template_type().extension() == "html"
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SerializedTemplate {
+    Name(String),
+    Template(ObjectTemplate),
+}
+
+/// The forms templates were serialized in, before and after types could have
+/// several: a name, a list, or a table of extensions.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SerializedTemplates {
+    Name(String),
+    List(Vec<SerializedTemplate>),
+    Table(BTreeMap<String, String>),
+}
+
+/// Reads `templates`, or the `template` of definitions serialized before
+/// types could have several templates, which was a single name or null.
+fn deserialize_templates<'de, D>(deserializer: D) -> Result<Vec<ObjectTemplate>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(
+        match Option::<SerializedTemplates>::deserialize(deserializer)? {
+            None => vec![],
+            Some(SerializedTemplates::Name(name)) => vec![ObjectTemplate::from_name(&name)],
+            Some(SerializedTemplates::List(templates)) => templates
+                .into_iter()
+                .map(|t| match t {
+                    SerializedTemplate::Name(name) => ObjectTemplate::from_name(&name),
+                    SerializedTemplate::Template(template) => template,
+                })
+                .collect(),
+            Some(SerializedTemplates::Table(templates)) => templates
+                .into_iter()
+                .map(|(extension, template)| ObjectTemplate {
+                    template,
+                    extension,
+                })
+                .collect(),
+        },
+    )
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
 pub struct ObjectDefinition {
     pub name: String,
     pub fields: BTreeMap<String, FieldType>,
     pub field_order: Vec<String>,
-    pub template: Option<String>,
+    /// The templates objects are rendered with, see ObjectTemplate.
+    #[serde(
+        default,
+        alias = "template",
+        deserialize_with = "deserialize_templates"
+    )]
+    pub templates: Vec<ObjectTemplate>,
+    /// Fields which may have a different value per locale.
+    #[serde(default)]
+    pub translatable: Vec<String>,
+    #[serde(default)]
+    pub taxonomies: Vec<Taxonomy>,
     #[cfg_attr(
         feature = "typescript",
         type_def(type_of = "typedefs::ObjectDefinitionChildrenDef")
     )]
     pub children: BTreeMap<String, ObjectDefinition>,
+    /// When not empty, this is a blocks (union) child: each item has a
+    /// `type` which selects one of these definitions. Declared in `_blocks`,
+    /// e.g. `[page.sections._blocks.hero]`.
+    #[serde(default)]
+    #[cfg_attr(
+        feature = "typescript",
+        type_def(type_of = "typedefs::ObjectDefinitionChildrenDef")
+    )]
+    pub blocks: BTreeMap<String, ObjectDefinition>,
 }
 
 impl ObjectDefinition {
@@ -54,22 +220,75 @@ impl ObjectDefinition {
             name: name.to_string(),
             fields: BTreeMap::new(),
             field_order: vec![],
-            template: None,
+            templates: vec![],
+            translatable: vec![],
+            taxonomies: vec![],
             children: BTreeMap::new(),
+            blocks: BTreeMap::new(),
         };
+        let mut meta_schemas = None;
+        let mut markdown_options = None;
         for (key, m_value) in definition {
             if !is_reserved_field(key) {
                 obj_def.field_order.push(key.to_string());
             }
-            if let Some(child_table) = m_value.as_table() {
+            if key == reserved_fields::META_SCHEMAS {
+                meta_schemas =
+                    Some(m_value.as_table().ok_or_else(|| {
+                        InvalidFieldError::InvalidMetaSchema(m_value.to_string())
+                    })?);
+            } else if key == reserved_fields::MARKDOWN_OPTIONS {
+                markdown_options = Some(m_value.as_table().ok_or_else(|| {
+                    InvalidFieldError::InvalidMarkdownOptions(m_value.to_string())
+                })?);
+            } else if key == reserved_fields::TAXONOMIES {
+                let taxonomies = m_value.as_table().ok_or_else(|| {
+                    InvalidFieldError::InvalidTaxonomy(key.to_string(), name.to_string())
+                })?;
+                for (field, taxonomy) in taxonomies {
+                    obj_def
+                        .taxonomies
+                        .push(Taxonomy::from_toml(name, field, taxonomy)?);
+                }
+            } else if key == reserved_fields::TEMPLATE {
+                obj_def.templates = ObjectTemplate::from_toml(name, m_value)?;
+            } else if key == reserved_fields::BLOCKS {
+                let blocks = m_value.as_table().ok_or_else(|| {
+                    InvalidFieldError::InvalidBlockType(name.to_string(), m_value.to_string())
+                })?;
+                for (block_name, block) in blocks {
+                    let block_table = block.as_table().ok_or_else(|| {
+                        InvalidFieldError::InvalidBlockType(
+                            name.to_string(),
+                            block_name.to_string(),
+                        )
+                    })?;
+                    let block_def = ObjectDefinition::new(block_name, block_table, editor_types)?;
+                    if block_def.fields.contains_key(BLOCK_TYPE)
+                        || block_def.children.contains_key(BLOCK_TYPE)
+                    {
+                        return Err(Box::new(ReservedFieldError { field: BLOCK_TYPE }));
+                    }
+                    obj_def.blocks.insert(block_name.to_string(), block_def);
+                }
+            } else if let Some(child_table) = m_value.as_table() {
                 obj_def.children.insert(
                     key.clone(),
                     ObjectDefinition::new(key, child_table, editor_types)?,
                 );
+            } else if key == reserved_fields::TRANSLATABLE {
+                let fields = m_value
+                    .as_array()
+                    .ok_or_else(|| InvalidFieldError::NotAnArray {
+                        key: key.to_string(),
+                        value: m_value.to_string(),
+                    })?;
+                obj_def.translatable = fields
+                    .iter()
+                    .filter_map(|f| f.as_str().map(|f| f.to_string()))
+                    .collect();
             } else if let Some(value) = m_value.as_str() {
-                if key == reserved_fields::TEMPLATE {
-                    obj_def.template = Some(value.to_string());
-                } else if is_reserved_field(key) {
+                if is_reserved_field(key) {
                     return Err(Box::new(ReservedFieldError {
                         field: reserved_field_from_str(key),
                     }));
@@ -80,8 +299,79 @@ impl ObjectDefinition {
                 }
             }
         }
+        // Schemas are applied once all fields are known, since they may be
+        // declared before the fields they describe.
+        for (field, schema) in meta_schemas.into_iter().flatten() {
+            let invalid = || InvalidFieldError::InvalidMetaSchema(format!("{}.{}", name, field));
+            let schema = MetaSchema::from_toml(schema.as_table().ok_or_else(invalid)?)?;
+            let field_type = obj_def.fields.get_mut(field).ok_or_else(invalid)?;
+            *field_type = field_type.with_meta_schema(schema).ok_or_else(invalid)?;
+        }
+        for (field, options) in markdown_options.into_iter().flatten() {
+            let invalid =
+                || InvalidFieldError::InvalidMarkdownOptions(format!("{}.{}", name, field));
+            let options = MarkdownOptions::from_toml(options.as_table().ok_or_else(invalid)?)?;
+            let field_type = obj_def.fields.get_mut(field).ok_or_else(invalid)?;
+            *field_type = field_type
+                .with_markdown_options(options)
+                .ok_or_else(invalid)?;
+        }
+        if obj_def.is_blocks() && !(obj_def.fields.is_empty() && obj_def.children.is_empty()) {
+            return Err(InvalidFieldError::MixedBlocks(name.to_string()).into());
+        }
+        for taxonomy in &obj_def.taxonomies {
+            if !obj_def
+                .fields
+                .get(&taxonomy.field)
+                .is_some_and(Taxonomy::supports_type)
+            {
+                return Err(InvalidFieldError::InvalidTaxonomy(
+                    taxonomy.field.to_string(),
+                    name.to_string(),
+                )
+                .into());
+            }
+        }
+        for field in &obj_def.translatable {
+            if !obj_def.fields.contains_key(field) {
+                return Err(InvalidFieldError::InvalidTranslatableField(
+                    field.to_string(),
+                    name.to_string(),
+                )
+                .into());
+            }
+        }
         Ok(obj_def)
     }
+
+    pub fn is_translatable(&self, field: &str) -> bool {
+        self.translatable.iter().any(|f| f == field)
+    }
+
+    pub fn is_blocks(&self) -> bool {
+        !self.blocks.is_empty()
+    }
+
+    /// The first template that renders objects of this type to html pages,
+    /// which is where links to them point.
+    pub fn html_template(&self) -> Option<&ObjectTemplate> {
+        self.templates.iter().find(|t| t.is_html())
+    }
+
+    /// The name of the html template, which was the only template before
+    /// types could have several.
+    pub fn template(&self) -> Option<&str> {
+        self.html_template().map(|t| t.template.as_str())
+    }
+
+    /// Returns the definition for a block item, based on its `type` value.
+    pub fn block_definition(&self, values: &ObjectValues) -> Option<&ObjectDefinition> {
+        match values.get(BLOCK_TYPE) {
+            Some(FieldValue::String(block_type)) => self.blocks.get(block_type),
+            _ => None,
+        }
+    }
+
     pub fn from_table(
         table: &Table,
         editor_types: &EditorTypes,
@@ -135,20 +425,50 @@ impl ObjectDefinition {
             let mut child = serde_json::Map::new();
             child.insert("description".into(), name.to_string().into());
             child.insert("type".into(), "array".into());
-            let child_properties = definition.to_json_schema_properties(true, options);
-            let mut child_items_type = serde_json::Map::new();
-            child_items_type.insert("type".into(), "object".into());
-            child_items_type.insert("additionalProperties".into(), false.into());
-            if options.all_fields_required {
-                let keys: Vec<String> = child_properties.keys().map(|k| k.to_string()).collect();
-                child_items_type.insert("required".into(), keys.into());
-            }
-            child_items_type.insert("properties".into(), child_properties.into());
+            let child_items_type = if definition.is_blocks() {
+                let variants: Vec<serde_json::Value> = definition
+                    .blocks
+                    .iter()
+                    .map(|(block_name, block_def)| {
+                        let mut block_properties =
+                            block_def.to_json_schema_properties(true, options);
+                        block_properties.insert(BLOCK_TYPE.into(), json!({ "const": block_name }));
+                        let mut block = Self::json_schema_child_items(block_properties, options);
+                        if !options.all_fields_required {
+                            block.insert("required".into(), json!([BLOCK_TYPE]));
+                        }
+                        block.into()
+                    })
+                    .collect();
+                let mut items = serde_json::Map::new();
+                items.insert("oneOf".into(), variants.into());
+                items
+            } else {
+                Self::json_schema_child_items(
+                    definition.to_json_schema_properties(true, options),
+                    options,
+                )
+            };
             child.insert("items".into(), child_items_type.into());
             properties.insert(name.into(), child.into());
         }
         properties
     }
+
+    fn json_schema_child_items(
+        child_properties: crate::json_schema::ObjectSchema,
+        options: &crate::json_schema::ObjectSchemaOptions,
+    ) -> serde_json::Map<String, serde_json::Value> {
+        let mut child_items_type = serde_json::Map::new();
+        child_items_type.insert("type".into(), "object".into());
+        child_items_type.insert("additionalProperties".into(), false.into());
+        if options.all_fields_required {
+            let keys: Vec<String> = child_properties.keys().map(|k| k.to_string()).collect();
+            child_items_type.insert("required".into(), keys.into());
+        }
+        child_items_type.insert("properties".into(), child_properties.into());
+        child_items_type
+    }
 }
 
 #[cfg(test)]
@@ -201,8 +521,13 @@ pub mod tests {
             !artist.fields.contains_key("template"),
             "did not copy the template reserved field"
         );
-        assert!(artist.template.is_some());
-        assert_eq!(artist.template, Some("artist".to_string()));
+        assert_eq!(
+            artist.templates,
+            vec![ObjectTemplate {
+                template: "artist".to_string(),
+                extension: "html".to_string()
+            }]
+        );
         assert_eq!(artist.children.len(), 3);
         assert!(artist.children.contains_key("tour_dates"));
         assert!(artist.children.contains_key("numbers"));
@@ -232,4 +557,323 @@ pub mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn translatable_fields() -> Result<(), Box<dyn Error>> {
+        let table: Table = toml::from_str(
+            "[post]
+            title = \"string\"
+            slug = \"string\"
+            _translatable = [\"title\"]
+            [post.links]
+            name = \"string\"
+            _translatable = [\"name\"]",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        let post = defs.get("post").unwrap();
+        assert_eq!(post.field_order, vec!["title", "slug", "links"]);
+        assert!(post.is_translatable("title"));
+        assert!(!post.is_translatable("slug"));
+        assert!(post.children["links"].is_translatable("name"));
+
+        let table: Table = toml::from_str(
+            "[post]
+            title = \"string\"
+            _translatable = [\"body\"]",
+        )?;
+        assert!(ObjectDefinition::from_table(&table, &HashMap::new()).is_err());
+
+        // Only the underscored keys are reserved
+        let table: Table = toml::from_str(
+            "[post]
+            locale = \"string\"
+            alternates = \"string\"
+            translations = \"string\"
+            translatable = \"boolean\"",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        assert_eq!(
+            defs["post"].field_order,
+            vec!["locale", "alternates", "translations", "translatable"]
+        );
+        assert_eq!(defs["post"].fields.len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn taxonomies() -> Result<(), Box<dyn Error>> {
+        let table: Table = toml::from_str(
+            "[post]
+            title = \"string\"
+            category = \"string\"
+            author = \"string\"
+            _taxonomies = { category = \"category\", author = { template = \"author\", index_template = \"authors\", path = \"people\" } }",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        let post = defs.get("post").unwrap();
+        assert_eq!(post.field_order, vec!["title", "category", "author"]);
+        assert!(post.children.is_empty());
+        assert_eq!(post.taxonomies.len(), 2);
+        let author = post
+            .taxonomies
+            .iter()
+            .find(|t| t.field == "author")
+            .unwrap();
+        assert_eq!(author.template, "author");
+        assert_eq!(author.index_template, Some("authors".to_string()));
+        assert_eq!(author.path, "people");
+        let category = post
+            .taxonomies
+            .iter()
+            .find(|t| t.field == "category")
+            .unwrap();
+        assert_eq!(category.template, "category");
+        assert_eq!(category.index_template, None);
+        assert_eq!(category.path, "category");
+
+        let table: Table = toml::from_str(
+            "[post]
+            count = \"number\"
+            _taxonomies = { count = \"count\" }",
+        )?;
+        assert!(ObjectDefinition::from_table(&table, &HashMap::new()).is_err());
+
+        // A child named taxonomies is still a child
+        let table: Table = toml::from_str(
+            "[post]
+            title = \"string\"
+            [post.taxonomies]
+            name = \"string\"",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        assert!(defs["post"].taxonomies.is_empty());
+        assert!(defs["post"].children["taxonomies"]
+            .fields
+            .contains_key("name"));
+        Ok(())
+    }
+
+    #[test]
+    fn multiple_templates() -> Result<(), Box<dyn Error>> {
+        let table: Table = toml::from_str(
+            "[recipe]
+            title = \"string\"
+            template = [\"recipe\", \"recipe.json\"]
+            [note]
+            title = \"string\"
+            template = { html = \"note\", json = \"note_api\", \"print.html\" = \"note_print\" }",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        let recipe = defs.get("recipe").unwrap();
+        assert_eq!(recipe.field_order, vec!["title"]);
+        assert!(recipe.children.is_empty());
+        let templates: Vec<(&str, &str, TemplateType)> = recipe
+            .templates
+            .iter()
+            .map(|t| (&t.template[..], &t.extension[..], t.template_type()))
+            .collect();
+        assert_eq!(
+            templates,
+            vec![
+                ("recipe", "html", TemplateType::Html),
+                ("recipe.json", "json", TemplateType::Json)
+            ]
+        );
+        let note = defs.get("note").unwrap();
+        assert!(note.children.is_empty());
+        let templates: Vec<(&str, &str, TemplateType)> = note
+            .templates
+            .iter()
+            .map(|t| (&t.template[..], &t.extension[..], t.template_type()))
+            .collect();
+        assert_eq!(
+            templates,
+            vec![
+                ("note", "html", TemplateType::Html),
+                ("note_api", "json", TemplateType::Json),
+                ("note_print", "print.html", TemplateType::Html)
+            ]
+        );
+        assert_eq!(recipe.template(), Some("recipe"));
+        assert_eq!(note.template(), Some("note"));
+
+        // Two templates may not render to the same files
+        let table: Table = toml::from_str(
+            "[recipe]
+            template = [\"recipe\", \"recipe_print\"]",
+        )?;
+        assert!(ObjectDefinition::from_table(&table, &HashMap::new()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn deserializing_templates() -> Result<(), Box<dyn Error>> {
+        let templates = |key: &str, value: serde_json::Value| {
+            let mut json = serde_json::json!({
+                "name": "recipe",
+                "fields": {},
+                "field_order": [],
+                "children": {},
+            });
+            json[key] = value;
+            serde_json::from_value::<ObjectDefinition>(json).map(|d| d.templates)
+        };
+        let recipe = ObjectTemplate::from_name("recipe");
+        let recipe_json = ObjectTemplate::from_name("recipe.json");
+        // Definitions serialized when types had a single template
+        assert_eq!(
+            templates("template", "recipe".into())?,
+            vec![recipe.clone()]
+        );
+        assert_eq!(templates("template", serde_json::Value::Null)?, vec![]);
+        assert_eq!(
+            templates("template", serde_json::json!(["recipe", "recipe.json"]))?,
+            vec![recipe.clone(), recipe_json.clone()]
+        );
+        assert_eq!(
+            templates(
+                "template",
+                serde_json::json!({ "html": "recipe", "json": "recipe.json" })
+            )?,
+            vec![recipe.clone(), recipe_json.clone()]
+        );
+        let all = vec![recipe, recipe_json];
+        assert_eq!(templates("templates", serde_json::to_value(&all)?)?, all);
+        assert!(templates("template", serde_json::json!(1)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn meta_schemas() -> Result<(), Box<dyn Error>> {
+        let table: Table = toml::from_str(
+            "[post]
+            _meta_schemas = { details = { rating = \"number\", author = { name = \"string\" } } }
+            title = \"string\"
+            details = \"meta\"",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        let post = defs.get("post").unwrap();
+        assert_eq!(post.field_order, vec!["title", "details"]);
+        let schema = post.fields["details"].meta_schema().unwrap();
+        assert_eq!(schema.0.len(), 2);
+        assert_eq!(post.fields["details"].to_str(), "meta");
+
+        let table: Table = toml::from_str(
+            "[post]
+            title = \"string\"
+            _meta_schemas = { title = { rating = \"number\" } }",
+        )?;
+        assert!(ObjectDefinition::from_table(&table, &HashMap::new()).is_err());
+
+        // A field named meta_schemas is still a field
+        let table: Table = toml::from_str(
+            "[post]
+            meta_schemas = \"string\"",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        assert_eq!(defs["post"].fields["meta_schemas"], FieldType::String);
+        Ok(())
+    }
+
+    #[test]
+    fn markdown_options() -> Result<(), Box<dyn Error>> {
+        let table: Table = toml::from_str(
+            "[post]
+            _markdown_options = { comment = { raw_html = false, tables = true } }
+            body = \"markdown\"
+            comment = \"markdown\"",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        let post = defs.get("post").unwrap();
+        assert_eq!(post.field_order, vec!["body", "comment"]);
+        assert_eq!(post.fields["body"].markdown_options(), None);
+        let options = post.fields["comment"].markdown_options().unwrap();
+        assert_eq!(options.raw_html, Some(false));
+        assert_eq!(options.tables, Some(true));
+        assert_eq!(post.fields["comment"].to_str(), "markdown");
+
+        for invalid in [
+            "_markdown_options = { title = { tables = true } }",
+            "_markdown_options = { body = { tablez = true } }",
+        ] {
+            let table: Table = toml::from_str(&format!(
+                "[post]
+                title = \"string\"
+                body = \"markdown\"
+                {}",
+                invalid
+            ))?;
+            assert!(ObjectDefinition::from_table(&table, &HashMap::new()).is_err());
+        }
+
+        // A child named markdown_options is still a child
+        let table: Table = toml::from_str(
+            "[post.markdown_options]
+            tables = \"boolean\"",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        assert!(defs["post"].children["markdown_options"]
+            .fields
+            .contains_key("tables"));
+        Ok(())
+    }
+
+    #[test]
+    fn blocks() -> Result<(), Box<dyn Error>> {
+        let table: Table = toml::from_str(
+            "[landing]
+            title = \"string\"
+            [landing.sections._blocks.hero]
+            heading = \"string\"
+            image = \"image\"
+            [landing.sections._blocks.quote]
+            text = \"markdown\"
+            author = \"string\"",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        let landing = defs.get("landing").unwrap();
+        assert_eq!(landing.field_order, vec!["title", "sections"]);
+        let sections = landing.children.get("sections").unwrap();
+        assert!(sections.is_blocks());
+        assert!(sections.fields.is_empty());
+        assert_eq!(sections.blocks.len(), 2);
+        assert_eq!(
+            sections.blocks["quote"].fields.get("text"),
+            Some(&FieldType::Markdown)
+        );
+        let values = ObjectValues::from([(
+            BLOCK_TYPE.to_string(),
+            FieldValue::String("hero".to_string()),
+        )]);
+        assert_eq!(
+            sections.block_definition(&values).map(|d| d.name.as_str()),
+            Some("hero")
+        );
+
+        // Blocks may not define a type field
+        let table: Table = toml::from_str(
+            "[landing.sections._blocks.hero]
+            type = \"string\"",
+        )?;
+        assert!(ObjectDefinition::from_table(&table, &HashMap::new()).is_err());
+        // Blocks may not be mixed with fields
+        let table: Table = toml::from_str(
+            "[landing.sections]
+            heading = \"string\"
+            [landing.sections._blocks.hero]
+            image = \"image\"",
+        )?;
+        assert!(ObjectDefinition::from_table(&table, &HashMap::new()).is_err());
+        // A child named blocks is still a child
+        let table: Table = toml::from_str(
+            "[landing.blocks]
+            heading = \"string\"",
+        )?;
+        let defs = ObjectDefinition::from_table(&table, &HashMap::new())?;
+        let landing = defs.get("landing").unwrap();
+        assert!(!landing.is_blocks());
+        assert!(!landing.children["blocks"].is_blocks());
+        assert!(landing.children["blocks"].fields.contains_key("heading"));
+        Ok(())
+    }
 }
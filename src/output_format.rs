@@ -0,0 +1,115 @@
+use crate::filters::text::{csv_escape, html_escape, json_escape, xml_escape};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::RandomState, BTreeMap, HashSet},
+    hash::BuildHasher,
+    sync::Mutex,
+};
+
+/// How the `{{ }}` outputs of pages are escaped for an output format.
+///
+/// Every output is escaped, unless it ends in `raw` (or `safe`), in a filter
+/// that escapes for a format itself, like `escape` or `json_escape`, or in
+/// `jsonify`/`geojson`, e.g.
+///
+/// ```liquid
+/// <h1>{{ post.title }}</h1>
+/// <div>{{ post.embed_code | raw }}</div>
+/// ```
+///
+/// The html of markdown fields is not escaped again on html pages. Values
+/// captured with `{% capture %}` are already escaped, so are output with
+/// `raw`.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
+pub enum Escaping {
+    #[default]
+    None,
+    Html,
+    Xml,
+    /// Quotes values that contain commas, quotes or newlines.
+    Csv,
+    /// Escapes values for use inside a json string.
+    Json,
+}
+
+impl Escaping {
+    pub fn escape(&self, input: &str) -> String {
+        match self {
+            Escaping::None => input.to_string(),
+            Escaping::Html if is_rendered_html(input) => input.to_string(),
+            Escaping::Html => html_escape(input),
+            Escaping::Xml => xml_escape(input),
+            Escaping::Csv => csv_escape(input),
+            Escaping::Json => json_escape(input),
+        }
+    }
+}
+
+/// Hashes of the html that markdown fields were rendered to. Pages output
+/// field values as strings, so this is how html escaping tells them apart
+/// from other strings.
+static RENDERED_HTML: Lazy<(RandomState, Mutex<HashSet<u64>>)> =
+    Lazy::new(|| (RandomState::new(), Mutex::new(HashSet::new())));
+
+/// Marks html that archival rendered, which html pages output unescaped.
+pub(crate) fn mark_rendered_html(html: &str) {
+    let (state, hashes) = &*RENDERED_HTML;
+    hashes
+        .lock()
+        .expect("Invalid RENDERED_HTML access")
+        .insert(state.hash_one(html));
+}
+
+pub(crate) fn is_rendered_html(html: &str) -> bool {
+    let (state, hashes) = &*RENDERED_HTML;
+    hashes
+        .lock()
+        .expect("Invalid RENDERED_HTML access")
+        .contains(&state.hash_one(html))
+}
+
+/// The content type of pages with an extension, and how values are escaped
+/// in them.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
+pub struct OutputFormat {
+    pub mime_type: String,
+    #[serde(default)]
+    pub escape: Escaping,
+}
+
+impl OutputFormat {
+    pub fn new(mime_type: &str, escape: Escaping) -> Self {
+        Self {
+            mime_type: mime_type.to_string(),
+            escape,
+        }
+    }
+}
+
+/// Formats registered in the `[formats]` section of a manifest, keyed by
+/// extension, e.g.
+///
+/// ```toml
+/// [formats.ics]
+/// mime_type = "text/calendar"
+/// ```
+///
+/// builds `pages/events.ics.liquid` to `events.ics`, which the dev server
+/// serves as `text/calendar`. Registered formats replace the built in ones.
+pub type OutputFormats = BTreeMap<String, OutputFormat>;
+
+pub fn formats_from_toml(table: &toml::Table) -> Result<OutputFormats, toml::de::Error> {
+    toml::Value::Table(table.clone()).try_into()
+}
+
+pub fn formats_to_toml(formats: &OutputFormats) -> toml::Table {
+    match toml::Value::try_from(formats) {
+        Ok(toml::Value::Table(t)) => t,
+        _ => toml::Table::new(),
+    }
+}
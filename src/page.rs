@@ -1,6 +1,11 @@
 use crate::{
-    object::{Object, ObjectEntry},
-    object_definition::ObjectDefinition,
+    fields::MarkdownContext,
+    links::Links,
+    liquid_parser,
+    object::{is_user_field, Object, ObjectEntry},
+    object_definition::{ObjectDefinition, ObjectDefinitions},
+    output_format::{Escaping, OutputFormat, OutputFormats},
+    reserved_fields,
 };
 use liquid::{model::ScalarCow, ValueView};
 use liquid_core::Value;
@@ -9,23 +14,15 @@ use regex::Regex;
 use std::{
     collections::BTreeMap,
     error::Error,
-    fmt,
     path::{Path, PathBuf},
 };
 
-#[derive(Debug, Clone)]
-struct InvalidPageError;
-impl Error for InvalidPageError {}
-impl fmt::Display for InvalidPageError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "invalid page")
-    }
-}
-
 static TEMPLATE_FILE_NAME_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^(.+?)(\.\w+)?\.liquid").unwrap());
 
-#[derive(Default, Debug, Clone)]
+/// The output format of a page, from the extension before `.liquid` in its
+/// file name, e.g. `feed.rss.liquid`. Pages without one are html.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub enum TemplateType {
     #[default]
     Default,
@@ -67,12 +64,30 @@ impl TemplateType {
             TemplateType::Default => "html",
             TemplateType::Html => "html",
             TemplateType::Css => "css",
-            TemplateType::Csv => "css",
+            TemplateType::Csv => "csv",
             TemplateType::Json => "json",
             TemplateType::Rss => "rss",
             TemplateType::Unknown(r) => r,
         }
     }
+    /// The format of pages of this type, using the formats registered in the
+    /// manifest before the built in ones. Unregistered types have none.
+    pub fn format(&self, formats: &OutputFormats) -> Option<OutputFormat> {
+        if let Some(format) = formats.get(self.extension()) {
+            return Some(format.clone());
+        }
+        let (mime_type, escape) = match self {
+            TemplateType::Default | TemplateType::Html => {
+                ("text/html; charset=utf-8", Escaping::Html)
+            }
+            TemplateType::Css => ("text/css; charset=utf-8", Escaping::None),
+            TemplateType::Csv => ("text/csv; charset=utf-8", Escaping::Csv),
+            TemplateType::Json => ("application/json", Escaping::Json),
+            TemplateType::Rss => ("application/rss+xml; charset=utf-8", Escaping::Xml),
+            TemplateType::Unknown(_) => return None,
+        };
+        Some(OutputFormat::new(mime_type, escape))
+    }
 }
 
 #[derive(Debug)]
@@ -85,11 +100,40 @@ pub struct PageTemplate<'a> {
     pub debug_path: PathBuf,
 }
 
+/// The locale a page is rendered in, and the urls of the same page in each of
+/// the site's locales.
+#[derive(Debug, Clone)]
+pub struct PageLocale {
+    pub locale: String,
+    pub alternates: Vec<(String, String)>,
+}
+
+impl PageLocale {
+    pub fn new(locale: &str, locales: &[String], output_path: &Path) -> Self {
+        Self {
+            locale: locale.to_string(),
+            alternates: locales
+                .iter()
+                .map(|l| {
+                    (
+                        l.to_string(),
+                        format!("/{}/{}", l, output_path.to_string_lossy()),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
 pub struct Page<'a> {
     name: String,
     content: Option<String>,
     template: Option<PageTemplate<'a>>,
     file_type: TemplateType,
+    locale: Option<PageLocale>,
+    context: liquid::Object,
+    definitions: Option<&'a ObjectDefinitions>,
+    links: Option<&'a Links>,
     pub debug_path: Option<PathBuf>,
 }
 
@@ -145,6 +189,10 @@ impl<'a> Page<'a> {
                 debug_path: template_debug_path.to_path_buf(),
             }),
             file_type,
+            locale: None,
+            context: liquid::Object::new(),
+            definitions: None,
+            links: None,
             debug_path: None,
         }
     }
@@ -159,9 +207,34 @@ impl<'a> Page<'a> {
             content: Some(content),
             template: None,
             file_type,
+            locale: None,
+            context: liquid::Object::new(),
+            definitions: None,
+            links: None,
             debug_path: Some(debug_path.to_path_buf()),
         }
     }
+    pub fn with_locale(mut self, locale: Option<PageLocale>) -> Self {
+        self.locale = locale;
+        self
+    }
+    /// Adds additional globals to the page, for instance the term of a
+    /// taxonomy page.
+    pub fn with_context(mut self, context: liquid::Object) -> Self {
+        self.context = context;
+        self
+    }
+    /// Renders objects using their definitions, which is required for
+    /// per-field options such as markdown overrides.
+    pub fn with_definitions(mut self, definitions: &'a ObjectDefinitions) -> Self {
+        self.definitions = Some(definitions);
+        self
+    }
+    /// Resolves wiki links in markdown, and adds backlinks to objects.
+    pub fn with_links(mut self, links: &'a Links) -> Self {
+        self.links = Some(links);
+        self
+    }
     pub fn render(
         &self,
         parser: &liquid::Parser,
@@ -169,26 +242,73 @@ impl<'a> Page<'a> {
     ) -> Result<String, Box<dyn Error>> {
         #[cfg(feature = "verbose-logging")]
         tracing::debug!("rendering {}", self.name);
+        let markdown_context = MarkdownContext {
+            links: self.links,
+            parser: Some(parser),
+            object: None,
+        };
         let mut objects: BTreeMap<String, liquid::model::Value> = BTreeMap::new();
         for (name, obj_entry) in objects_map {
+            let definition = self.definitions.and_then(|d| d.get(name));
             let values = match obj_entry {
-                ObjectEntry::List(l) => Value::array(l.iter().map(|o| o.liquid_object())),
-                ObjectEntry::Object(o) => o.liquid_object(),
+                ObjectEntry::List(l) => Value::array(
+                    l.iter()
+                        .map(|o| o.liquid_object(definition, &markdown_context)),
+                ),
+                ObjectEntry::Object(o) => o.liquid_object(definition, &markdown_context),
             };
             objects.insert(name.to_string(), values);
         }
-        let globals = liquid::object!({ "objects": objects, "page": self.name });
+        let mut globals = liquid::object!({
+            "objects": objects,
+            "page": self.name,
+            "output_format": self.file_type.extension(),
+        });
+        if let Some(locale) = &self.locale {
+            let alternates: Vec<liquid::Object> = locale
+                .alternates
+                .iter()
+                .map(|(locale, url)| liquid::object!({ "locale": locale, "url": url }))
+                .collect();
+            // An object that a template renders takes precedence
+            let object_name = self.template.as_ref().map(|t| t.definition.name.as_str());
+            for (key, value) in [
+                (
+                    reserved_fields::LOCALE,
+                    Value::scalar(locale.locale.clone()),
+                ),
+                (reserved_fields::ALTERNATES, alternates.to_value()),
+            ] {
+                if object_name != Some(key) {
+                    globals.insert(key.into(), value);
+                }
+            }
+        }
+        globals.extend(self.context.clone());
         if let Some(template_info) = &self.template {
-            let template = parser.parse(&template_info.content)?;
-            let mut object_vals = match template_info.object.values.to_value() {
-                liquid::model::Value::Object(v) => Ok(v),
-                _ => Err(InvalidPageError),
-            }?;
+            let template = parser.parse(&liquid_parser::escape_outputs(&template_info.content))?;
+            let mut object_vals = Object::liquid_values(
+                &template_info.object.values,
+                Some(template_info.definition),
+                &markdown_context,
+            );
             object_vals.extend(liquid::object!({
                 "object_name": template_info.object.object_name,
                 "order": template_info.object.order,
                 "path": template_info.object.path,
             }));
+            if let Some(links) = self.links.filter(|_| {
+                !is_user_field(
+                    reserved_fields::BACKLINKS,
+                    &template_info.object.values,
+                    Some(template_info.definition),
+                )
+            }) {
+                object_vals.insert(
+                    reserved_fields::BACKLINKS.into(),
+                    links.backlinks(template_info.object).to_value(),
+                );
+            }
             let mut context = liquid::object!({
               template_info.definition.name.to_owned(): object_vals
             });
@@ -201,7 +321,7 @@ impl<'a> Page<'a> {
                     .into()),
             };
         } else if let Some(content) = &self.content {
-            let template = parser.parse(content)?;
+            let template = parser.parse(&liquid_parser::escape_outputs(content))?;
             return match template.render(&globals) {
                 Ok(v) => Ok(v),
                 Err(error) => Err(error
@@ -226,7 +346,9 @@ mod tests {
 
     use crate::{
         fields::{meta::Meta, DateTime, FieldType, FieldValue, MetaValue, ObjectValues},
-        liquid_parser, MemoryFileSystem,
+        liquid_parser::{self, LiquidExtensions},
+        object_definition::ObjectTemplate,
+        FileSystemAPI, MemoryFileSystem,
     };
 
     use super::*;
@@ -235,7 +357,7 @@ mod tests {
         let tour_dates_objects = vec![ObjectValues::from([
             (
                 "date".to_string(),
-                FieldValue::Date(DateTime::from("12/22/2022 00:00:00").unwrap()),
+                FieldValue::Date(DateTime::from("2022-12-22 00:00:00").unwrap()),
             ),
             (
                 "ticket_link".to_string(),
@@ -276,6 +398,7 @@ mod tests {
             path: "artist/tormenta-rey".to_string(),
             order: 1,
             values: artist_values,
+            translations: BTreeMap::new(),
         };
         let links_objects = vec![ObjectValues::from([(
             "url".to_string(),
@@ -296,6 +419,7 @@ mod tests {
             path: "home".to_string(),
             order: -1,
             values: c_values,
+            translations: BTreeMap::new(),
         };
 
         BTreeMap::from([
@@ -318,8 +442,11 @@ mod tests {
                     name: "tour_dates".to_string(),
                     field_order: vec!["date".to_string(), "ticket_link".to_string()],
                     fields: tour_dates_fields,
-                    template: None,
+                    templates: vec![],
+                    translatable: vec![],
+                    taxonomies: vec![],
                     children: BTreeMap::new(),
+                    blocks: BTreeMap::new(),
                 },
             ),
             (
@@ -328,8 +455,11 @@ mod tests {
                     name: "numbers".to_string(),
                     field_order: vec![],
                     fields: numbers_fields,
-                    template: None,
+                    templates: vec![],
+                    translatable: vec![],
+                    taxonomies: vec![],
                     children: BTreeMap::new(),
+                    blocks: BTreeMap::new(),
                 },
             ),
         ]);
@@ -341,8 +471,14 @@ mod tests {
                 "numbers".to_string(),
             ],
             fields: artist_def_fields,
-            template: Some("artist".to_string()),
+            templates: vec![ObjectTemplate {
+                template: "artist".to_string(),
+                extension: "html".to_string(),
+            }],
+            translatable: vec![],
+            taxonomies: vec![],
             children: artist_children,
+            blocks: BTreeMap::new(),
         }
     }
 
@@ -350,6 +486,9 @@ mod tests {
         "{% assign c = objects.c | where: \"name\", \"home\" | first %}
         name: {{c.name}}
         content: {{c.content}}
+        {% for heading in c.toc.content %}
+          toc: {{heading.level}} {{heading.text}} #{{heading.anchor}}
+        {% endfor %}
         page_path: {{c.path}}
         {% for link in c.links %}
           link: {{link.url}}
@@ -375,7 +514,12 @@ mod tests {
 
     #[test]
     fn regular_page() -> Result<(), Box<dyn Error>> {
-        let liquid_parser = liquid_parser::get(None, None, &MemoryFileSystem::default())?;
+        let liquid_parser = liquid_parser::get(
+            None,
+            None,
+            &LiquidExtensions::default(),
+            &MemoryFileSystem::default(),
+        )?;
         let objects_map = get_objects_map();
         let page = Page::new(
             "home".to_string(),
@@ -387,9 +531,10 @@ mod tests {
         println!("rendered: {}", rendered);
         assert!(rendered.contains("name: home"), "filtered object");
         assert!(
-            rendered.contains("content: <h1>hello</h1>"),
+            rendered.contains("content: <h1 id=\"hello\">hello</h1>"),
             "markdown field"
         );
+        assert!(rendered.contains("toc: 1 hello #hello"), "markdown toc");
         assert!(rendered.contains("link: foo.com"), "child string field");
         assert!(
             rendered.contains("artist: Tormenta Rey"),
@@ -403,8 +548,99 @@ mod tests {
         Ok(())
     }
     #[test]
+    fn output_formats() -> Result<(), Box<dyn Error>> {
+        let (name, csv) = TemplateType::parse_path("feed.csv.liquid").unwrap();
+        assert_eq!(name, "feed");
+        assert_eq!(csv, TemplateType::Csv);
+        assert_eq!(csv.extension(), "csv");
+        let mut formats = OutputFormats::new();
+        assert_eq!(
+            csv.format(&formats).unwrap().mime_type,
+            "text/csv; charset=utf-8"
+        );
+        let ics = TemplateType::from_ext("ics");
+        assert_eq!(ics.format(&formats), None);
+        formats.insert(
+            "ics".to_string(),
+            OutputFormat::new("text/calendar", Escaping::None),
+        );
+        assert_eq!(ics.format(&formats).unwrap().mime_type, "text/calendar");
+        let liquid_parser = liquid_parser::get(
+            None,
+            None,
+            &LiquidExtensions::default(),
+            &MemoryFileSystem::default(),
+        )?;
+        let page = Page::new(
+            "feed".to_string(),
+            "{{ 'Rey, T.' | escape_output }},{{ page }}".to_string(),
+            csv,
+            Path::new("pages/feed.csv.liquid"),
+        );
+        assert_eq!(page.extension(), "csv");
+        assert_eq!(
+            page.render(&liquid_parser, &get_objects_map())?,
+            "\"Rey, T.\",feed"
+        );
+        Ok(())
+    }
+    #[test]
+    fn escapes_outputs() -> Result<(), Box<dyn Error>> {
+        let liquid_parser = liquid_parser::get(
+            None,
+            None,
+            &LiquidExtensions::default(),
+            &MemoryFileSystem::default(),
+        )?;
+        let render = |content: &str, file_type: TemplateType| {
+            Page::new(
+                "page".to_string(),
+                content.to_string(),
+                file_type,
+                Path::new("pages/page.liquid"),
+            )
+            .render(&liquid_parser, &get_objects_map())
+        };
+        assert_eq!(
+            render(
+                "{{ '<b>' }} {{ '<b>' | raw }} {{ '<b>' | safe }} {{ '<b>' | escape }}",
+                TemplateType::Html
+            )?,
+            "&lt;b&gt; <b> <b> &lt;b&gt;"
+        );
+        assert_eq!(
+            render("{% raw %}{{ '<b>' }}{% endraw %}", TemplateType::Html)?,
+            "{{ '<b>' }}"
+        );
+        // Markdown fields are already html
+        assert_eq!(
+            render(
+                "{% assign c = objects.c | first %}{{ c.content }}{{ c.content | excerpt }}",
+                TemplateType::Html
+            )?,
+            "<h1 id=\"hello\">hello</h1>\n<h1 id=\"hello\">hello</h1>\n"
+        );
+        assert_eq!(
+            render("{{ 'Rey, T.' }},{{ page }}", TemplateType::Csv)?,
+            "\"Rey, T.\",page"
+        );
+        assert_eq!(
+            render(
+                "{\"a\": \"{{ 'say \"hi\"' }}\", \"b\": {{ 'x' | jsonify }}}",
+                TemplateType::Json
+            )?,
+            "{\"a\": \"say \\\"hi\\\"\", \"b\": \"x\"}"
+        );
+        Ok(())
+    }
+    #[test]
     fn template_page() -> Result<(), Box<dyn Error>> {
-        let liquid_parser = liquid_parser::get(None, None, &MemoryFileSystem::default())?;
+        let liquid_parser = liquid_parser::get(
+            None,
+            None,
+            &LiquidExtensions::default(),
+            &MemoryFileSystem::default(),
+        )?;
         let objects_map = get_objects_map();
         let object = objects_map["artist"].into_iter().next().unwrap();
         println!("OBJ: {:?}", object);
@@ -430,4 +666,193 @@ mod tests {
         assert!(rendered.contains("link: foo.com"), "child string field");
         Ok(())
     }
+    #[test]
+    fn markdown_field_options() -> Result<(), Box<dyn Error>> {
+        let liquid_parser = liquid_parser::get(
+            None,
+            None,
+            &LiquidExtensions::default(),
+            &MemoryFileSystem::default(),
+        )?;
+        let table: toml::Table = toml::from_str(
+            "[post]
+            body = \"markdown\"
+            trusted = \"markdown\"
+            _markdown_options = { trusted = { raw_html = true } }
+            [post.sections]
+            text = \"markdown\"
+            _markdown_options = { text = { strikethrough = true } }",
+        )?;
+        let definitions = ObjectDefinition::from_table(&table, &Default::default())?;
+        let html = "<b>bold</b> ~~gone~~";
+        let post = Object {
+            filename: "post".to_string(),
+            object_name: "post".to_string(),
+            path: "post/post".to_string(),
+            order: 1,
+            values: ObjectValues::from([
+                ("body".to_string(), FieldValue::Markdown(html.to_string())),
+                (
+                    "trusted".to_string(),
+                    FieldValue::Markdown(html.to_string()),
+                ),
+                (
+                    "sections".to_string(),
+                    FieldValue::Objects(vec![ObjectValues::from([(
+                        "text".to_string(),
+                        FieldValue::Markdown(html.to_string()),
+                    )])]),
+                ),
+            ]),
+            translations: BTreeMap::new(),
+        };
+        let objects_map = BTreeMap::from([("post".to_string(), ObjectEntry::from_vec(vec![post]))]);
+        let page = Page::new(
+            "home".to_string(),
+            "{% assign post = objects.post | first %}
+            body: {{post.body}}
+            trusted: {{post.trusted}}
+            text: {{post.sections[0].text}}"
+                .to_string(),
+            TemplateType::Default,
+            Path::new("pages/home.liquid"),
+        )
+        .with_definitions(&definitions);
+        let rendered = page.render(&liquid_parser, &objects_map)?;
+        println!("rendered: {}", rendered);
+        assert!(
+            rendered.contains("body: <p><!-- raw HTML omitted -->bold"),
+            "default options"
+        );
+        assert!(
+            rendered.contains("trusted: <p><b>bold</b>"),
+            "field options"
+        );
+        assert!(rendered.contains("<del>gone</del>"), "child field options");
+        Ok(())
+    }
+    #[test]
+    fn localized_page() -> Result<(), Box<dyn Error>> {
+        let liquid_parser = liquid_parser::get(
+            None,
+            None,
+            &LiquidExtensions::default(),
+            &MemoryFileSystem::default(),
+        )?;
+        let objects_map = get_objects_map();
+        let page = Page::new(
+            "home".to_string(),
+            "locale: {{locale}}
+            {% for alt in alternates %}
+              alt: {{alt.locale}} {{alt.url}}
+            {% endfor %}"
+                .to_string(),
+            TemplateType::Default,
+            Path::new("pages/home.liquid"),
+        )
+        .with_locale(Some(PageLocale::new(
+            "es",
+            &["en".to_string(), "es".to_string()],
+            Path::new("home.html"),
+        )));
+        let rendered = page.render(&liquid_parser, &objects_map)?;
+        println!("rendered: {}", rendered);
+        assert!(rendered.contains("locale: es"));
+        assert!(rendered.contains("alt: en /en/home.html"));
+        assert!(rendered.contains("alt: es /es/home.html"));
+        Ok(())
+    }
+    #[test]
+    fn localized_object_named_locale() -> Result<(), Box<dyn Error>> {
+        let liquid_parser = liquid_parser::get(
+            None,
+            None,
+            &LiquidExtensions::default(),
+            &MemoryFileSystem::default(),
+        )?;
+        let definition = ObjectDefinition {
+            name: "locale".to_string(),
+            ..artist_definition()
+        };
+        let object = Object {
+            object_name: "locale".to_string(),
+            path: "locale/tormenta-rey".to_string(),
+            ..get_objects_map()["artist"]
+                .into_iter()
+                .next()
+                .unwrap()
+                .clone()
+        };
+        let page = Page::new_with_template(
+            "tormenta-rey".to_string(),
+            &definition,
+            &object,
+            "{{ locale.name }} {{ alternates | size }}".to_string(),
+            TemplateType::Default,
+            Path::new("pages/locale.liquid"),
+        )
+        .with_locale(Some(PageLocale::new(
+            "es",
+            &["en".to_string(), "es".to_string()],
+            Path::new("locale/tormenta-rey.html"),
+        )));
+        // The object keeps its name, other globals are still added
+        let rendered = page.render(&liquid_parser, &get_objects_map())?;
+        assert_eq!(rendered, "Tormenta Rey 2");
+        Ok(())
+    }
+    #[test]
+    fn markdown_shortcodes() -> Result<(), Box<dyn Error>> {
+        let mut fs = MemoryFileSystem::default();
+        let pages_dir = Path::new("pages");
+        fs.write_str(
+            &pages_dir.join("_youtube.liquid"),
+            "<iframe src=\"https://youtube.com/embed/{{id}}\"></iframe>".to_string(),
+        )?;
+        fs.write_str(
+            &pages_dir.join("_callout.liquid"),
+            "<aside class=\"{{kind}}\">{{content}}</aside>".to_string(),
+        )?;
+        fs.write_str(
+            &pages_dir.join("_gallery.liquid"),
+            "{% for link in object.links %}<img src=\"{{link.url}}\">{% endfor %}".to_string(),
+        )?;
+        let liquid_parser =
+            liquid_parser::get(Some(pages_dir), None, &LiquidExtensions::default(), &fs)?;
+        let mut objects_map = get_objects_map();
+        let home = objects_map["c"].into_iter().next().unwrap().clone();
+        let mut values = home.values.clone();
+        values.insert(
+            "content".to_string(),
+            FieldValue::Markdown(
+                "{{< youtube id=\"abc\" >}}\n\n{{< callout kind='tip' >}}\n**hi**\n{{< /callout >}}\n\n{{< gallery >}} and {{< missing >}}"
+                    .to_string(),
+            ),
+        );
+        objects_map.insert(
+            "c".to_string(),
+            ObjectEntry::from_vec(vec![Object { values, ..home }]),
+        );
+        let page = Page::new(
+            "home".to_string(),
+            "{% assign c = objects.c | first %}{{c.content}}".to_string(),
+            TemplateType::Default,
+            Path::new("pages/home.liquid"),
+        );
+        let rendered = page.render(&liquid_parser, &objects_map)?;
+        println!("rendered: {}", rendered);
+        assert!(
+            rendered.starts_with("<iframe src=\"https://youtube.com/embed/abc\"></iframe>"),
+            "block shortcode"
+        );
+        assert!(
+            rendered.contains("<aside class=\"tip\"><p><strong>hi</strong></p>\n</aside>"),
+            "shortcode with content"
+        );
+        assert!(
+            rendered.contains("<p><img src=\"foo.com\"> and {{&lt; missing &gt;}}</p>"),
+            "object values and missing partials"
+        );
+        Ok(())
+    }
 }
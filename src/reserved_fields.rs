@@ -8,6 +8,24 @@ pub const OBJECTS: &str = "objects";
 pub const OBJECT_NAME: &str = "object_name";
 pub const PAGE: &str = "page";
 pub const PAGE_NAME: &str = "page_name";
+// Definition and object file keys start with an underscore, so that they
+// can't clash with the names of fields or children.
+pub const TRANSLATABLE: &str = "_translatable";
+pub const TRANSLATIONS: &str = "_translations";
+pub const TAXONOMIES: &str = "_taxonomies";
+pub const BLOCKS: &str = "_blocks";
+pub const META_SCHEMAS: &str = "_meta_schemas";
+pub const MARKDOWN_OPTIONS: &str = "_markdown_options";
+
+// These keys are added to objects in templates, unless the object defines a
+// field with the same name, see `is_user_field`.
+pub const TOC: &str = "toc";
+pub const BACKLINKS: &str = "backlinks";
+
+// These are added to the context of localized pages, unless the page renders
+// an object of the same name.
+pub const LOCALE: &str = "locale";
+pub const ALTERNATES: &str = "alternates";
 
 #[derive(Debug, Clone)]
 pub struct ReservedFieldError {
@@ -28,6 +46,12 @@ pub fn reserved_field_from_str(field: &str) -> &'static str {
         TEMPLATE => TEMPLATE,
         OBJECTS => OBJECTS,
         PAGE => PAGE,
+        TRANSLATABLE => TRANSLATABLE,
+        TRANSLATIONS => TRANSLATIONS,
+        TAXONOMIES => TAXONOMIES,
+        BLOCKS => BLOCKS,
+        META_SCHEMAS => META_SCHEMAS,
+        MARKDOWN_OPTIONS => MARKDOWN_OPTIONS,
         _ => panic!("{} is not a reserved field", field),
     }
 }
@@ -35,6 +59,17 @@ pub fn reserved_field_from_str(field: &str) -> &'static str {
 pub fn is_reserved_field(field: &str) -> bool {
     matches!(
         field,
-        OBJECT_NAME | ORDER | OBJECTS | PAGE_NAME | PAGE | TEMPLATE
+        OBJECT_NAME
+            | ORDER
+            | OBJECTS
+            | PAGE_NAME
+            | PAGE
+            | TEMPLATE
+            | TRANSLATABLE
+            | TRANSLATIONS
+            | TAXONOMIES
+            | BLOCKS
+            | META_SCHEMAS
+            | MARKDOWN_OPTIONS
     )
 }
@@ -1,3 +1,4 @@
+use crate::{output_format::OutputFormats, page::TemplateType};
 use std::{
     str::FromStr,
     sync::{RwLock, TryLockError},
@@ -9,6 +10,7 @@ pub struct ServerBuilder {
     hostname: Option<String>,
     port: Option<u16>,
     not_found_path: Option<std::path::PathBuf>,
+    formats: OutputFormats,
 }
 
 impl ServerBuilder {
@@ -19,6 +21,7 @@ impl ServerBuilder {
             source,
             hostname: None,
             port: None,
+            formats: OutputFormats::default(),
         }
     }
 
@@ -36,6 +39,12 @@ impl ServerBuilder {
         self
     }
 
+    /// Serve pages with the content types of a site's output formats
+    pub fn formats(&mut self, formats: OutputFormats) -> &mut Self {
+        self.formats = formats;
+        self
+    }
+
     /// Create a server
     ///
     /// This is needed for accessing the dynamically assigned pot
@@ -53,6 +62,7 @@ impl ServerBuilder {
             addr: format!("{}:{}", hostname, port),
             server: RwLock::new(None),
             not_found_path: self.not_found_path.as_ref().map(|p| p.to_path_buf()),
+            formats: self.formats.clone(),
         }
     }
 
@@ -67,6 +77,7 @@ pub struct Server {
     addr: String,
     server: RwLock<Option<tiny_http::Server>>,
     not_found_path: Option<std::path::PathBuf>,
+    formats: OutputFormats,
 }
 
 impl Server {
@@ -111,7 +122,9 @@ impl Server {
             // unwrap is safe here
             for request in server.as_ref().unwrap().incoming_requests() {
                 // handles the request
-                if let Err(e) = static_file_handler(self.source(), request, &self.not_found_path) {
+                if let Err(e) =
+                    static_file_handler(self.source(), request, &self.not_found_path, &self.formats)
+                {
                     tracing::error!("{}", e);
                 }
             }
@@ -156,6 +169,7 @@ fn static_file_handler(
     dest: &std::path::Path,
     req: tiny_http::Request,
     not_found_path: &Option<std::path::PathBuf>,
+    formats: &OutputFormats,
 ) -> Result<(), Error> {
     // grab the requested path
     let mut req_path = req.url().to_string();
@@ -188,7 +202,16 @@ fn static_file_handler(
     if serve_path.exists() {
         let file = std::fs::File::open(&serve_path).map_err(Error::new)?;
         let mut response = tiny_http::Response::from_file(file);
-        if let Some(mime) = mime_guess::MimeGuess::from_path(&serve_path).first_raw() {
+        // built pages use the mime type of their format, other files are
+        // guessed
+        let format = serve_path
+            .extension()
+            .and_then(|ext| TemplateType::from_ext(&ext.to_string_lossy()).format(formats));
+        let mime = match &format {
+            Some(format) => Some(format.mime_type.as_str()),
+            None => mime_guess::MimeGuess::from_path(&serve_path).first_raw(),
+        };
+        if let Some(mime) = mime {
             let content_type = format!("Content-Type:{}", mime);
             let content_type =
                 tiny_http::Header::from_str(&content_type).expect("formatted correctly");
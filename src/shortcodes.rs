@@ -0,0 +1,204 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::error::Error;
+use tracing::warn;
+
+/// Matches `{{< name key="value" >}}` and its closing `{{< /name >}}`, or an
+/// escaped `{{</* name */>}}`, which is rendered as a literal shortcode.
+static SHORTCODE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"\{\{</\*\s*(.*?)\s*\*/>\}\}|\{\{<\s*(/)?\s*([\w/-]+)((?:\s+[\w-]+=(?:"[^"]*"|'[^']*'|[^\s"'>]+))*)\s*>\}\}"#,
+    )
+    .unwrap()
+});
+static ARG_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"([\w-]+)=(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap());
+
+/// A shortcode in markdown, which renders the partial of the same name, e.g.
+/// `{{< youtube id="abc" >}}` renders `pages/_youtube.liquid` with `id` set.
+///
+/// Shortcodes may also wrap markdown, which is rendered and passed to the
+/// partial as `content`:
+///
+/// ```markdown
+/// {{< callout kind="warning" >}}
+/// Mind the **gap**.
+/// {{< /callout >}}
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shortcode {
+    pub name: String,
+    pub args: Vec<(String, String)>,
+    /// The markdown between the opening and closing tags.
+    pub inner: Option<String>,
+    /// The shortcode as written, which is rendered when it fails.
+    source: String,
+}
+
+impl Shortcode {
+    fn placeholder(index: usize) -> String {
+        // Only letters and digits, so that markdown renders it as-is.
+        format!("ARCHIVALSHORTCODE{}END", index)
+    }
+
+    /// Renders this shortcode's partial, with its args, its rendered `content`
+    /// and the `object` whose markdown it is in.
+    pub fn render(
+        &self,
+        parser: &liquid::Parser,
+        content: Option<String>,
+        object: Option<&liquid::Object>,
+    ) -> Result<String, Box<dyn Error>> {
+        let template = parser.parse(&format!("{{% include \"{}\" %}}", self.name))?;
+        // Shortcodes render into the html of markdown fields
+        let mut globals = liquid::object!({ "output_format": "html" });
+        for (key, value) in &self.args {
+            globals.insert(
+                key.to_string().into(),
+                liquid::model::Value::scalar(value.to_string()),
+            );
+        }
+        if let Some(content) = content {
+            globals.insert("content".into(), liquid::model::Value::scalar(content));
+        }
+        if let Some(object) = object {
+            globals.insert(
+                "object".into(),
+                liquid::model::Value::Object(object.clone()),
+            );
+        }
+        Ok(template.render(&globals)?)
+    }
+}
+
+/// Replaces the shortcodes in markdown with placeholders, returning the
+/// shortcodes in the order of their placeholders. Shortcodes in code must be
+/// escaped, as `{{</* name */>}}`.
+pub fn extract(markdown: &str) -> (String, Vec<Shortcode>) {
+    let tags: Vec<Captures> = SHORTCODE_RE.captures_iter(markdown).collect();
+    let mut output = String::with_capacity(markdown.len());
+    let mut shortcodes = vec![];
+    let mut last = 0;
+    let mut idx = 0;
+    while idx < tags.len() {
+        let tag = &tags[idx];
+        let matched = tag.get(0).unwrap();
+        idx += 1;
+        if let Some(escaped) = tag.get(1) {
+            output.push_str(&markdown[last..matched.start()]);
+            output.push_str(&format!("{{{{< {} >}}}}", escaped.as_str()));
+            last = matched.end();
+            continue;
+        }
+        if tag.get(2).is_some() {
+            // Closing tags without an opening tag are left as-is
+            continue;
+        }
+        let name = &tag[3];
+        let closing = tags[idx..]
+            .iter()
+            .position(|t| t.get(2).is_some() && &t[3] == name)
+            .map(|offset| idx + offset);
+        let (inner, end) = match closing {
+            Some(closing_idx) => {
+                let close = tags[closing_idx].get(0).unwrap();
+                idx = closing_idx + 1;
+                (
+                    Some(markdown[matched.end()..close.start()].to_string()),
+                    close.end(),
+                )
+            }
+            None => (None, matched.end()),
+        };
+        output.push_str(&markdown[last..matched.start()]);
+        output.push_str(&Shortcode::placeholder(shortcodes.len()));
+        shortcodes.push(Shortcode {
+            name: name.to_string(),
+            args: ARG_RE
+                .captures_iter(tag.get(4).map_or("", |a| a.as_str()))
+                .map(|arg| {
+                    let value = arg.get(2).or(arg.get(3)).or(arg.get(4)).unwrap();
+                    (arg[1].to_string(), value.as_str().to_string())
+                })
+                .collect(),
+            inner,
+            source: markdown[matched.start()..end].to_string(),
+        });
+        last = end;
+    }
+    output.push_str(&markdown[last..]);
+    (output, shortcodes)
+}
+
+/// Replaces the placeholders in rendered html with their rendered shortcodes.
+/// Shortcodes that are alone in a paragraph replace the paragraph, so that they
+/// may render block elements. Shortcodes that fail to render are left as
+/// written.
+pub fn insert(
+    mut html: String,
+    shortcodes: &[Shortcode],
+    mut render: impl FnMut(&Shortcode) -> Result<String, Box<dyn Error>>,
+) -> String {
+    for (index, shortcode) in shortcodes.iter().enumerate() {
+        let placeholder = Shortcode::placeholder(index);
+        let rendered = match render(shortcode) {
+            Ok(rendered) => rendered,
+            Err(error) => {
+                warn!("failed rendering shortcode {}: {}", shortcode.name, error);
+                let mut escaped = vec![];
+                comrak::html::escape(&mut escaped, shortcode.source.as_bytes())
+                    .expect("writing to a vec cannot fail");
+                String::from_utf8(escaped).expect("escaping keeps valid utf-8")
+            }
+        };
+        let paragraph = format!("<p>{}</p>\n", placeholder);
+        html = if html.contains(&paragraph) {
+            html.replacen(&paragraph, &rendered, 1)
+        } else {
+            html.replacen(&placeholder, &rendered, 1)
+        };
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracting() {
+        let (markdown, shortcodes) = extract(
+            "a {{< youtube id=\"abc\" start=10 title='A video' >}} b\n\n{{< callout >}}\n**hi**\n{{< /callout >}}\n\n`{{</* youtube */>}}` {{< /orphan >}}",
+        );
+        assert_eq!(
+            markdown,
+            "a ARCHIVALSHORTCODE0END b\n\nARCHIVALSHORTCODE1END\n\n`{{< youtube >}}` {{< /orphan >}}"
+        );
+        assert_eq!(shortcodes[0].name, "youtube");
+        assert_eq!(
+            shortcodes[0].args,
+            vec![
+                ("id".to_string(), "abc".to_string()),
+                ("start".to_string(), "10".to_string()),
+                ("title".to_string(), "A video".to_string()),
+            ]
+        );
+        assert_eq!(shortcodes[0].inner, None);
+        assert_eq!(shortcodes[1].name, "callout");
+        assert_eq!(shortcodes[1].inner.as_deref(), Some("\n**hi**\n"));
+    }
+
+    #[test]
+    fn inserting() {
+        let (_, shortcodes) = extract("{{< a >}}{{< b >}}");
+        let html = insert(
+            "<p>ARCHIVALSHORTCODE0END</p>\n<p>x ARCHIVALSHORTCODE1END</p>\n".to_string(),
+            &shortcodes,
+            |s| match s.name.as_str() {
+                "a" => Ok("<div>a</div>".to_string()),
+                _ => Err("missing".into()),
+            },
+        );
+        assert_eq!(html, "<div>a</div><p>x {{&lt; b &gt;}}</p>\n");
+    }
+}
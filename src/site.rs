@@ -1,15 +1,25 @@
+#[cfg(feature = "image-processing")]
+use crate::image_processing::ImageVariants;
 #[cfg(feature = "json-schema")]
 use crate::json_schema;
 use crate::{
+    assets::AssetManifest,
     check_compatibility,
-    constants::MANIFEST_FILE_NAME,
-    liquid_parser::{self, PARTIAL_FILE_NAME_RE},
+    constants::{ASSET_MANIFEST_FILE_NAME, HIGHLIGHT_CSS_FILE_NAME, MANIFEST_FILE_NAME},
+    fields::MarkdownContext,
+    filters::url::SiteUrls,
+    link_check::{self, BrokenLink},
+    links::Links,
+    liquid_parser::{self, LiquidExtensions, PARTIAL_FILE_NAME_RE},
     manifest::Manifest,
+    minify::{Minifier, MinifyStats},
     object::{Object, ObjectEntry},
-    object_definition::{ObjectDefinition, ObjectDefinitions},
-    page::{Page, TemplateType},
+    object_definition::{ObjectDefinition, ObjectDefinitions, ObjectTemplate},
+    page::{Page, PageLocale, TemplateType},
     read_toml::read_toml,
     tags::layout,
+    taxonomy::Taxonomy,
+    trash::{self, TrashError, TrashedObject},
     ArchivalError, FieldConfig, FileSystemAPI,
 };
 use seahash::SeaHasher;
@@ -17,7 +27,7 @@ use serde::{Deserialize, Serialize};
 use std::{
     cell::{RefCell, RefMut},
     cmp::Ordering,
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     error::Error,
     hash::Hasher,
     path::{Path, PathBuf},
@@ -46,6 +56,8 @@ pub enum BuildError {
     TemplateRenderError(String, String, String),
     #[error("page {0} failed rendering:\n{1}")]
     PageRenderError(String, String),
+    #[error("term {1} of taxonomy {0} would overwrite the taxonomy's index page")]
+    IndexTermClash(String, String),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -57,6 +69,30 @@ pub struct Site {
     obj_cache: RefCell<HashMap<PathBuf, Object>>,
     #[serde(skip)]
     static_file_cache: RefCell<HashMap<PathBuf, u64>>,
+    #[serde(skip)]
+    assets: AssetManifest,
+    #[serde(skip)]
+    minify_stats: RefCell<MinifyStats>,
+    #[cfg(feature = "image-processing")]
+    #[serde(skip)]
+    image_variants: ImageVariants,
+    /// Extensions registered by applications, which are added after the
+    /// site's own and replace any with the same name.
+    #[serde(skip)]
+    pub liquid_extensions: LiquidExtensions,
+}
+
+/// What every page of a build is rendered with. Localized sites build their
+/// pages once per locale, each with its own context.
+struct RenderContext<'a> {
+    all_objects: &'a BTreeMap<String, ObjectEntry>,
+    definitions: &'a ObjectDefinitions,
+    links: &'a Links,
+    build_dir: &'a Path,
+    locale: Option<&'a str>,
+    locales: &'a [String],
+    liquid_parser: &'a liquid::Parser,
+    minifier: &'a Minifier,
 }
 
 impl std::fmt::Display for Site {
@@ -124,6 +160,11 @@ impl Site {
             object_definitions: objects,
             obj_cache: RefCell::new(HashMap::new()),
             static_file_cache: RefCell::new(HashMap::new()),
+            assets: AssetManifest::default(),
+            minify_stats: RefCell::new(MinifyStats::default()),
+            #[cfg(feature = "image-processing")]
+            image_variants: ImageVariants::default(),
+            liquid_extensions: LiquidExtensions::empty(),
         })
     }
 
@@ -212,6 +253,8 @@ impl Site {
 
     pub fn get_field_config(&self) -> FieldConfig {
         FieldConfig::new(self.manifest.uploads_url.as_ref().map(|u| u.to_owned()))
+            .with_timezone(self.manifest.timezone_offset())
+            .with_markdown(self.manifest.markdown.clone())
     }
 
     #[instrument(skip(fs))]
@@ -238,7 +281,10 @@ impl Site {
         fs: &mut T,
         modify: impl FnOnce(&mut Manifest),
     ) -> Result<(), Box<dyn Error>> {
-        modify(&mut self.manifest);
+        let mut manifest = self.manifest.clone();
+        modify(&mut manifest);
+        manifest.validate()?;
+        self.manifest = manifest;
         fs.write_str(Path::new(MANIFEST_FILE_NAME), self.manifest.to_toml()?)
     }
 
@@ -309,6 +355,29 @@ impl Site {
         Ok(all_objects)
     }
 
+    pub fn trashed_objects<T: FileSystemAPI>(
+        &self,
+        fs: &T,
+    ) -> Result<Vec<TrashedObject>, Box<dyn Error>> {
+        match &self.manifest.trash_dir {
+            Some(trash_dir) => trash::list(trash_dir, fs),
+            None => Ok(vec![]),
+        }
+    }
+
+    pub fn purge_trash<T: FileSystemAPI>(
+        &self,
+        object: Option<&str>,
+        fs: &mut T,
+    ) -> Result<usize, Box<dyn Error>> {
+        let trash_dir = self
+            .manifest
+            .trash_dir
+            .as_ref()
+            .ok_or(TrashError::NotEnabled)?;
+        trash::purge(trash_dir, object, fs)
+    }
+
     #[instrument(skip(object_def, cache, fs))]
     fn object_for_path<T: FileSystemAPI>(
         &self,
@@ -357,6 +426,7 @@ impl Site {
         let mut hashes = self.static_file_cache.borrow_mut();
         let last_dist_paths: Vec<PathBuf> = hashes.keys().cloned().collect();
         let mut copied_paths: HashSet<PathBuf> = HashSet::new();
+        let minifier = Minifier::new(&self.manifest.minify);
         // Copy static files
         #[cfg(feature = "verbose-logging")]
         debug!("copying files from {}", static_dir.display());
@@ -366,13 +436,35 @@ impl Site {
                 if let Some(content) = fs.read(&from)? {
                     let current_hash = hash_file(&content);
                     copied_paths.insert(file.clone());
-                    // If there is an existing hash and it matches the current
-                    // file, leave it there.
-                    if let Some(existing_hash) = hashes.get(&file) {
-                        if *existing_hash == current_hash {
-                            continue;
+                    #[cfg(feature = "image-processing")]
+                    if let Some(options) = &self.manifest.images.variants {
+                        if ImageVariants::is_image(&file) {
+                            self.image_variants.process(
+                                &file,
+                                &content,
+                                current_hash,
+                                options,
+                                build_dir,
+                                fs,
+                            )?;
                         }
                     }
+                    // If there is an existing hash and it matches the current
+                    // file, leave it there, unless it has no fingerprinted
+                    // copy yet.
+                    let unchanged = hashes.get(&file) == Some(&current_hash);
+                    let fingerprint = self.manifest.fingerprint_assets
+                        && (!unchanged || self.assets.get(&file).is_none());
+                    if unchanged && !fingerprint {
+                        continue;
+                    }
+                    let content = minifier.static_file(&file, content);
+                    if fingerprint {
+                        self.fingerprint_asset(&file, &content, current_hash, build_dir, fs)?;
+                    }
+                    if unchanged {
+                        continue;
+                    }
                     // Otherwise, copy the file and store the latest hash.
                     let dest = build_dir.join(&file);
                     if let Some(dirname) = dest.parent() {
@@ -384,6 +476,30 @@ impl Site {
                     hashes.insert(file, current_hash);
                 }
             }
+            // Variants are removed with their image, or when a site stops
+            // generating them
+            #[cfg(feature = "image-processing")]
+            self.image_variants.retain(
+                &if self.manifest.images.variants.is_some() {
+                    copied_paths.clone()
+                } else {
+                    HashSet::new()
+                },
+                build_dir,
+                fs,
+            )?;
+            for fingerprinted in self.assets.retain(&copied_paths) {
+                let path = build_dir.join(fingerprinted);
+                if fs.exists(&path)? {
+                    fs.delete(&path)?;
+                }
+            }
+            if self.manifest.fingerprint_assets {
+                fs.write_str(
+                    &build_dir.join(ASSET_MANIFEST_FILE_NAME),
+                    self.assets.to_json(),
+                )?;
+            }
             // Remove any files in dest that are no longer in static
             for path in last_dist_paths {
                 if !copied_paths.contains(&path) {
@@ -394,6 +510,58 @@ impl Site {
         } else {
             debug!("static dir {} does not exist.", static_dir.display());
         }
+        self.minify_stats.borrow_mut().add(&minifier.stats());
+        Ok(())
+    }
+
+    /// Checks the links in the build output, see link_check::check_links.
+    pub fn check_links<T: FileSystemAPI>(&self, fs: &T) -> Result<Vec<BrokenLink>, Box<dyn Error>> {
+        link_check::check_links(
+            &self.manifest.build_dir,
+            self.manifest.site_url.as_deref(),
+            &self.get_field_config().uploads_url,
+            fs,
+        )
+    }
+
+    /// The fingerprinted static assets, as of the last sync of static files.
+    pub fn assets(&self) -> &AssetManifest {
+        &self.assets
+    }
+
+    /// What minification saved since this was last called, see
+    /// Manifest::minify.
+    pub fn take_minify_stats(&self) -> MinifyStats {
+        self.minify_stats.take()
+    }
+
+    /// Copies a static file to its content-hashed name, unless it's already
+    /// there, and removes its previous copy.
+    fn fingerprint_asset<T: FileSystemAPI>(
+        &self,
+        file: &Path,
+        content: &[u8],
+        hash: u64,
+        build_dir: &Path,
+        fs: &mut T,
+    ) -> Result<(), Box<dyn Error>> {
+        let fingerprinted = AssetManifest::fingerprinted_path(file, hash);
+        let dest = build_dir.join(&fingerprinted);
+        if !fs.exists(&dest)? {
+            if let Some(dirname) = dest.parent() {
+                fs.create_dir_all(dirname)?;
+            }
+            fs.write(&dest, content.to_vec())?;
+        }
+        if let Some(previous) = self
+            .assets
+            .insert(file.to_path_buf(), fingerprinted.clone())
+        {
+            let previous = build_dir.join(previous);
+            if previous != dest && fs.exists(&previous)? {
+                fs.delete(&previous)?;
+            }
+        }
         Ok(())
     }
 
@@ -438,6 +606,14 @@ impl Site {
         //     }
         // }
 
+        let extensions = LiquidExtensions::default()
+            .site_url(self.manifest.site_url.as_deref())
+            .image_options(&self.manifest.images)
+            .assets(self.assets.clone())
+            .output_formats(self.manifest.formats.clone());
+        #[cfg(feature = "image-processing")]
+        let extensions = extensions.image_variants(self.image_variants.clone());
+        let extensions = extensions.extend(&self.liquid_extensions);
         let liquid_parser = liquid_parser::get(
             Some(pages_dir),
             if fs.exists(layout_dir)? {
@@ -445,12 +621,107 @@ impl Site {
             } else {
                 None
             },
+            &extensions,
             fs,
         )?;
 
+        let minifier = Minifier::new(&self.manifest.minify);
+        #[cfg(not(feature = "minify"))]
+        if self.manifest.minify.iter().any(|o| o != "json") {
+            warn!("not minifying html, css or js, archival was built without minify");
+        }
+        let urls = SiteUrls::new(self.manifest.site_url.as_deref());
+        // Pages render once per locale, but each dangling link is only
+        // reported once
+        let mut dangling = BTreeSet::new();
+        if self.manifest.locales.is_empty() {
+            let links = Links::new(&all_objects, &self.object_definitions, None, &urls);
+            dangling.extend(links.dangling().iter().cloned());
+            let ctx = RenderContext {
+                all_objects: &all_objects,
+                definitions: &self.object_definitions,
+                links: &links,
+                build_dir,
+                locale: None,
+                locales: &self.manifest.locales,
+                liquid_parser: &liquid_parser,
+                minifier: &minifier,
+            };
+            self.build_pages(&ctx, fs)?;
+        } else {
+            // Localized sites render every page once per locale, into
+            // build_dir/<locale>/
+            for locale in &self.manifest.locales {
+                let localized_objects: BTreeMap<String, ObjectEntry> = all_objects
+                    .iter()
+                    .map(|(name, entry)| (name.to_string(), entry.localized(locale)))
+                    .collect();
+                let links = Links::new(
+                    &localized_objects,
+                    &self.object_definitions,
+                    Some(locale),
+                    &urls,
+                );
+                dangling.extend(links.dangling().iter().cloned());
+                let ctx = RenderContext {
+                    all_objects: &localized_objects,
+                    definitions: &self.object_definitions,
+                    links: &links,
+                    build_dir: &build_dir.join(locale),
+                    locale: Some(locale),
+                    locales: &self.manifest.locales,
+                    liquid_parser: &liquid_parser,
+                    minifier: &minifier,
+                };
+                self.build_pages(&ctx, fs)?;
+            }
+        }
+        for (source, target) in dangling {
+            warn!("{} links to {}, which has no page", source, target);
+        }
+        self.write_highlight_css(build_dir, fs)?;
+        let stats = minifier.stats();
+        if stats.files > 0 {
+            debug!("{}", stats);
+        }
+        self.minify_stats.borrow_mut().add(&stats);
+        Ok(())
+    }
+
+    /// Writes the css for the manifest's `markdown.highlight_theme`, if set.
+    #[cfg_attr(not(feature = "syntax-highlighting"), allow(unused_variables))]
+    fn write_highlight_css<T: FileSystemAPI>(
+        &self,
+        build_dir: &Path,
+        fs: &mut T,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some(theme) = &self.manifest.markdown.highlight_theme else {
+            return Ok(());
+        };
+        #[cfg(feature = "syntax-highlighting")]
+        if let Some(css) = crate::fields::theme_css(theme) {
+            fs.write_str(&build_dir.join(HIGHLIGHT_CSS_FILE_NAME), css)?;
+        }
+        #[cfg(not(feature = "syntax-highlighting"))]
+        warn!(
+            "not writing {} for theme {}, archival was built without syntax highlighting",
+            HIGHLIGHT_CSS_FILE_NAME, theme
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(ctx, fs), fields(locale = ctx.locale))]
+    fn build_pages<T: FileSystemAPI>(
+        &self,
+        ctx: &RenderContext,
+        fs: &mut T,
+    ) -> Result<(), Box<dyn Error>> {
+        let pages_dir = &self.manifest.pages_dir;
+
         // Render template pages
         for (name, object_def) in self.object_definitions.iter() {
-            if let Some(template) = &object_def.template {
+            for object_template in &object_def.templates {
+                let template = &object_template.template;
                 let template_path = pages_dir.join(format!("{}.liquid", template));
                 #[cfg(feature = "verbose-logging")]
                 debug!("rendering template objects for {}", template_path.display());
@@ -465,19 +736,18 @@ impl Site {
                 }
                 let template_str = template_r?;
                 if let Some(template_str) = template_str {
-                    if let Some(t_objects) = all_objects.get(name) {
+                    if let Some(t_objects) = ctx.all_objects.get(name) {
                         for object in t_objects.into_iter() {
                             #[cfg(feature = "verbose-logging")]
                             debug!("rendering {}", object.filename);
                             if let Err(error) = Self::render_template_page(
                                 object,
                                 object_def,
+                                object_template,
                                 &template_str,
                                 &template_path,
-                                build_dir,
-                                &all_objects,
+                                ctx,
                                 fs,
-                                &liquid_parser,
                             ) {
                                 return Err(BuildError::TemplateRenderError(
                                     object.filename.to_string(),
@@ -492,13 +762,30 @@ impl Site {
             }
         }
 
+        // Render taxonomy pages
+        for (name, object_def) in self.object_definitions.iter() {
+            for taxonomy in &object_def.taxonomies {
+                if let Some(t_objects) = ctx.all_objects.get(name) {
+                    self.render_taxonomy(taxonomy, t_objects, object_def, ctx, fs)?;
+                }
+            }
+        }
+
         // Render regular pages
         #[cfg(feature = "verbose-logging")]
         debug!("building pages in {}", pages_dir.display());
         let template_pages: HashSet<&str> = self
             .object_definitions
             .values()
-            .flat_map(|object| object.template.as_deref())
+            .flat_map(|object| {
+                object.templates.iter().map(|t| t.template.as_str()).chain(
+                    object.taxonomies.iter().flat_map(|t| {
+                        [Some(t.template.as_str()), t.index_template.as_deref()]
+                            .into_iter()
+                            .flatten()
+                    }),
+                )
+            })
             .collect();
         for rel_path in fs.walk_dir(pages_dir, false)? {
             let file_path = pages_dir.join(&rel_path);
@@ -519,16 +806,9 @@ impl Site {
                         file_path.display(),
                         page_type.extension()
                     );
-                    if let Err(error) = Self::render_page(
-                        &rel_path,
-                        &file_path,
-                        page_name,
-                        page_type,
-                        build_dir,
-                        &all_objects,
-                        fs,
-                        &liquid_parser,
-                    ) {
+                    if let Err(error) =
+                        Self::render_page(&rel_path, &file_path, page_name, page_type, ctx, fs)
+                    {
                         return Err(BuildError::PageRenderError(
                             page_name.to_string(),
                             error.to_string(),
@@ -541,33 +821,159 @@ impl Site {
         Ok(())
     }
 
-    #[instrument(skip(all_objects, fs, liquid_parser))]
-    #[allow(clippy::too_many_arguments)]
+    fn read_template<T: FileSystemAPI>(
+        &self,
+        template: &str,
+        fs: &T,
+    ) -> Result<(PathBuf, String), Box<dyn Error>> {
+        let template_path = self.manifest.pages_dir.join(format!("{}.liquid", template));
+        match fs.read_to_string(&template_path)? {
+            Some(template_str) => Ok((template_path, template_str)),
+            None => Err(BuildError::MissingTemplate(template_path.display().to_string()).into()),
+        }
+    }
+
+    #[instrument(skip(objects, object_def, ctx, fs))]
+    fn render_taxonomy<T: FileSystemAPI>(
+        &self,
+        taxonomy: &Taxonomy,
+        objects: &ObjectEntry,
+        object_def: &ObjectDefinition,
+        ctx: &RenderContext,
+        fs: &mut T,
+    ) -> Result<(), Box<dyn Error>> {
+        let markdown_context = MarkdownContext {
+            links: Some(ctx.links),
+            parser: Some(ctx.liquid_parser),
+            object: None,
+        };
+        let terms: Vec<(String, liquid::Object)> = taxonomy
+            .terms(objects)
+            .into_iter()
+            .map(|(slug, term)| {
+                let objects: Vec<liquid::model::Value> = term
+                    .objects
+                    .iter()
+                    .map(|o| o.liquid_object(Some(object_def), &markdown_context))
+                    .collect();
+                let term = liquid::object!({
+                    "name": term.name,
+                    "slug": slug,
+                    "path": taxonomy.term_path(&slug),
+                    "objects": objects,
+                });
+                (slug, term)
+            })
+            .collect();
+        if taxonomy.index_template.is_some() {
+            if let Some((slug, _)) = terms.iter().find(|(slug, _)| slug == "index") {
+                return Err(BuildError::IndexTermClash(
+                    taxonomy.path.to_string(),
+                    slug.to_string(),
+                )
+                .into());
+            }
+        }
+        let t_dir = ctx.build_dir.join(&taxonomy.path);
+        fs.create_dir_all(&t_dir)?;
+        let term_template = self.read_template(&taxonomy.template, fs)?;
+        let mut pages: Vec<(&str, &str, &(PathBuf, String), liquid::Object)> = terms
+            .iter()
+            .map(|(slug, term)| {
+                (
+                    slug.as_str(),
+                    taxonomy.template.as_str(),
+                    &term_template,
+                    liquid::object!({ "term": term }),
+                )
+            })
+            .collect();
+        let index = match &taxonomy.index_template {
+            Some(index_template) => Some((index_template, self.read_template(index_template, fs)?)),
+            None => None,
+        };
+        if let Some((index_template, index_page)) = &index {
+            let terms: Vec<&liquid::Object> = terms.iter().map(|(_, term)| term).collect();
+            pages.push((
+                "index",
+                index_template,
+                index_page,
+                liquid::object!({ "terms": terms }),
+            ));
+        }
+        for (page_name, template, (template_path, template_str), context) in pages {
+            let page = Page::new(
+                page_name.to_string(),
+                template_str.to_string(),
+                TemplateType::Default,
+                template_path,
+            )
+            .with_context(context)
+            .with_definitions(ctx.definitions)
+            .with_links(ctx.links);
+            let render_name = format!("{}.{}", page_name, page.extension());
+            let page = page.with_locale(ctx.locale.map(|locale| {
+                PageLocale::new(
+                    locale,
+                    ctx.locales,
+                    &Path::new(&taxonomy.path).join(&render_name),
+                )
+            }));
+            let rendered = page
+                .render(ctx.liquid_parser, ctx.all_objects)
+                .map_err(|error| {
+                    BuildError::TemplateRenderError(
+                        page_name.to_string(),
+                        template.to_string(),
+                        error.to_string(),
+                    )
+                })?;
+            fs.write_str(
+                &t_dir.join(render_name),
+                ctx.minifier
+                    .page(&TemplateType::Default, layout::post_process(rendered)),
+            )?;
+        }
+        Ok(())
+    }
+
+    #[instrument(skip(ctx, fs))]
     fn render_template_page<T: FileSystemAPI>(
         object: &Object,
         object_def: &ObjectDefinition,
+        object_template: &ObjectTemplate,
         template_str: &String,
         template_path: &PathBuf,
-        build_dir: &PathBuf,
-        all_objects: &BTreeMap<String, ObjectEntry>,
+        ctx: &RenderContext,
         fs: &mut T,
-        liquid_parser: &liquid::Parser,
     ) -> Result<(), Box<dyn Error>> {
+        let template_type = object_template.template_type();
         let page = Page::new_with_template(
             object.filename.clone(),
             object_def,
             object,
             template_str.to_owned(),
-            TemplateType::Default,
+            template_type.clone(),
             template_path,
-        );
-        let render_o = page.render(liquid_parser, all_objects);
+        )
+        .with_definitions(ctx.definitions)
+        .with_links(ctx.links);
+        let render_name = format!("{}.{}", object.filename, object_template.extension);
+        let page = page.with_locale(ctx.locale.map(|locale| {
+            PageLocale::new(
+                locale,
+                ctx.locales,
+                &Path::new(&object_def.name).join(&render_name),
+            )
+        }));
+        let render_o = page.render(ctx.liquid_parser, ctx.all_objects);
         if render_o.is_err() {
             warn!("failed rendering {}", object.filename);
         }
-        let rendered = layout::post_process(render_o?);
-        let render_name = format!("{}.{}", object.filename, page.extension());
-        let t_dir = build_dir.join(&object_def.name);
+        let rendered = ctx
+            .minifier
+            .page(&template_type, layout::post_process(render_o?));
+        let t_dir = ctx.build_dir.join(&object_def.name);
         fs.create_dir_all(&t_dir)?;
         let build_path = t_dir.join(render_name);
         #[cfg(feature = "verbose-logging")]
@@ -576,36 +982,41 @@ impl Site {
         Ok(())
     }
 
-    #[instrument(skip(all_objects, fs, liquid_parser))]
-    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip(ctx, fs))]
     fn render_page<T: FileSystemAPI>(
         rel_path: &PathBuf,
         file_path: &PathBuf,
         page_name: &str,
         page_type: TemplateType,
-        build_dir: &PathBuf,
-        all_objects: &BTreeMap<String, ObjectEntry>,
+        ctx: &RenderContext,
         fs: &mut T,
-        liquid_parser: &liquid::Parser,
     ) -> Result<(), Box<dyn Error>> {
         if let Some(template_str) = fs.read_to_string(file_path)? {
+            let render_name = format!("{}.{}", page_name, page_type.extension());
             let page = Page::new(
                 page_name.to_string(),
                 template_str,
-                TemplateType::Default,
+                page_type.clone(),
                 file_path,
-            );
-            let render_o = page.render(liquid_parser, all_objects);
+            )
+            .with_locale(ctx.locale.map(|locale| {
+                PageLocale::new(locale, ctx.locales, &rel_path.with_file_name(&render_name))
+            }))
+            .with_definitions(ctx.definitions)
+            .with_links(ctx.links);
+            let render_o = page.render(ctx.liquid_parser, ctx.all_objects);
             if render_o.is_err() {
                 warn!("failed rendering {}", file_path.display());
             }
-            let rendered = layout::post_process(render_o?);
-            let mut render_dir = build_dir.to_path_buf();
+            let rendered = ctx
+                .minifier
+                .page(&page_type, layout::post_process(render_o?));
+            let mut render_dir = ctx.build_dir.to_path_buf();
             if let Some(parent_dir) = rel_path.parent() {
                 render_dir = render_dir.join(parent_dir);
                 fs.create_dir_all(&render_dir)?;
             }
-            let render_path = render_dir.join(format!("{}.{}", page_name, page_type.extension()));
+            let render_path = render_dir.join(render_name);
             #[cfg(feature = "verbose-logging")]
             debug!("write {}", render_path.display());
             fs.write_str(&render_path, rendered)?;
@@ -0,0 +1,199 @@
+use crate::object_definition::BLOCK_TYPE;
+use liquid_core::error::ResultLiquidExt;
+use liquid_core::Expression;
+use liquid_core::Language;
+use liquid_core::Renderable;
+use liquid_core::ValueView;
+use liquid_core::{runtime::StackFrame, Runtime};
+use liquid_core::{Error, Result};
+use liquid_core::{Object, Value};
+use liquid_core::{ParseTag, TagReflection, TagTokenIter};
+use std::io::Write;
+
+/// The directory block partials are loaded from when one is not provided.
+static DEFAULT_BLOCKS_DIR: &str = "blocks";
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BlocksTag;
+
+impl BlocksTag {}
+
+impl TagReflection for BlocksTag {
+    fn tag(&self) -> &'static str {
+        "blocks"
+    }
+
+    fn description(&self) -> &'static str {
+        "Renders each item of a blocks field with the partial for its type"
+    }
+}
+
+impl ParseTag for BlocksTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let blocks = arguments
+            .expect_next("Identifier expected.")?
+            .expect_value()
+            .into_result()?;
+        let dir = match arguments.expect_next("") {
+            Ok(dir) => Some(dir.expect_value().into_result()?),
+            Err(_) => None,
+        };
+
+        arguments.expect_nothing()?;
+
+        Ok(Box::new(Blocks { blocks, dir }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Blocks {
+    blocks: Expression,
+    dir: Option<Expression>,
+}
+
+impl Renderable for Blocks {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let dir = match &self.dir {
+            Some(dir) => dir.evaluate(runtime)?.to_kstr().into_owned(),
+            None => DEFAULT_BLOCKS_DIR.into(),
+        };
+        let blocks = self.blocks.evaluate(runtime)?;
+        let blocks = match blocks.as_array() {
+            Some(blocks) => blocks,
+            None if blocks.is_nil() => return Ok(()),
+            None => {
+                return Error::with_msg("Can only use `blocks` with a list of blocks")
+                    .context("blocks", format!("{}", blocks.source()))
+                    .into_err();
+            }
+        };
+        for (index, block) in blocks.values().enumerate() {
+            let block_type = block
+                .as_object()
+                .and_then(|b| b.get(BLOCK_TYPE))
+                .map(|t| t.to_kstr().into_owned())
+                .ok_or_else(|| {
+                    Error::with_msg("block has no type")
+                        .context("block", format!("{}", block.source()))
+                })?;
+            let name = format!("{}/{}", dir, block_type);
+            let mut vars = Object::new();
+            vars.insert("block".into(), block.to_value());
+            vars.insert("block_index".into(), Value::scalar(index as i64));
+            let scope = StackFrame::new(runtime, &vars);
+            let partial = scope
+                .partials()
+                .get(&name)
+                .trace_with(|| format!("{{% blocks {} %}}", self.blocks).into())?;
+            partial
+                .render_to(writer, &scope)
+                .trace_with(|| format!("{{% blocks {} %}}", self.blocks).into())
+                .context_key_with(|| "block".into())
+                .value_with(|| name.clone().into())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow;
+    use std::error::Error;
+
+    use liquid_core::partials::PartialCompiler;
+    use liquid_core::runtime::RuntimeBuilder;
+    use liquid_core::{object, parser, partials, runtime, Language, Template};
+
+    use super::*;
+
+    pub trait ToTemplate {
+        fn to_template(&self, options: &Language) -> Result<Template, Box<dyn Error>>;
+    }
+    impl ToTemplate for &str {
+        fn to_template(&self, options: &Language) -> Result<Template, Box<dyn Error>> {
+            Ok(parser::parse(self, options).map(runtime::Template::new)?)
+        }
+    }
+
+    fn options() -> Language {
+        let mut options = Language::default();
+        options
+            .tags
+            .register("blocks".to_string(), BlocksTag.into());
+        options
+    }
+
+    #[derive(Default, Debug, Clone, Copy)]
+    struct TestSource;
+
+    impl partials::PartialSource for TestSource {
+        fn contains(&self, _name: &str) -> bool {
+            true
+        }
+
+        fn names(&self) -> Vec<&str> {
+            vec![]
+        }
+
+        fn try_get<'a>(&'a self, name: &str) -> Option<borrow::Cow<'a, str>> {
+            match name {
+                "blocks/hero" => Some("<h1>{{ block.heading }}</h1>".into()),
+                "blocks/quote" => Some("<q>{{ block.text }}</q>{{ block_index }}".into()),
+                "sections/hero" => Some("<header>{{ block.heading }}</header>".into()),
+                "sections/quote" => Some("<blockquote>{{ block.text }}</blockquote>".into()),
+                _ => None,
+            }
+        }
+    }
+
+    fn runtime_with_sections(template: &str) -> Result<String, Box<dyn Error>> {
+        let options = options();
+        let template = template.to_template(&options)?;
+        let partials = partials::OnDemandCompiler::<TestSource>::empty()
+            .compile(::std::sync::Arc::new(options))
+            .unwrap();
+        let runtime = RuntimeBuilder::new()
+            .set_partials(partials.as_ref())
+            .build();
+        runtime.set_global(
+            "sections".into(),
+            Value::Array(vec![
+                Value::Object(object!({"type": "hero", "heading": "Hello"})),
+                Value::Object(object!({"type": "quote", "text": "Quoted"})),
+            ]),
+        );
+        Ok(template.render(&runtime)?)
+    }
+
+    #[test]
+    fn renders_partial_per_block() -> Result<(), Box<dyn Error>> {
+        let output = runtime_with_sections("{% blocks sections %}")?;
+        assert_eq!(output, "<h1>Hello</h1><q>Quoted</q>1");
+        Ok(())
+    }
+
+    #[test]
+    fn custom_directory() -> Result<(), Box<dyn Error>> {
+        let output = runtime_with_sections("{% blocks sections \"sections\" %}")?;
+        assert_eq!(
+            output,
+            "<header>Hello</header><blockquote>Quoted</blockquote>"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn missing_partial() -> Result<(), Box<dyn Error>> {
+        let output = runtime_with_sections("{% blocks sections \"missing\" %}");
+        assert!(output.is_err());
+        Ok(())
+    }
+}
@@ -1,2 +1,3 @@
+pub mod blocks;
 pub mod default;
 pub mod layout;
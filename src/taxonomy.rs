@@ -0,0 +1,214 @@
+use crate::{
+    fields::{FieldType, InvalidFieldError},
+    object::Object,
+    FieldValue,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A taxonomy groups the objects of a type by the distinct values of one of
+/// their fields, and renders a page per value (term) as well as an optional
+/// index of all terms.
+///
+/// In objects.toml, taxonomies are declared per object type and keyed by
+/// field, either with just a template name or with a table:
+///
+/// ```toml
+/// [post]
+/// tags = "string[]"
+/// category = "string"
+/// author = "string"
+/// _taxonomies = { tags = "tag", category = "category", author = { template = "author", index_template = "authors", path = "people" } }
+/// ```
+///
+/// The index is rendered to `{path}/index`, so taxonomies with an index may not
+/// have a term with the slug `index`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
+pub struct Taxonomy {
+    pub field: String,
+    pub template: String,
+    pub index_template: Option<String>,
+    /// The directory terms are rendered into, defaults to the field name.
+    pub path: String,
+}
+
+impl Taxonomy {
+    pub fn from_toml(
+        object_name: &str,
+        field: &str,
+        value: &toml::Value,
+    ) -> Result<Self, InvalidFieldError> {
+        let invalid = || InvalidFieldError::InvalidTaxonomy(field.to_string(), object_name.into());
+        if let Some(template) = value.as_str() {
+            return Ok(Self {
+                field: field.to_string(),
+                template: template.to_string(),
+                index_template: None,
+                path: field.to_string(),
+            });
+        }
+        let table = value.as_table().ok_or_else(invalid)?;
+        let get_str = |key: &str| {
+            table
+                .get(key)
+                .and_then(|v| v.as_str().map(|s| s.to_string()))
+        };
+        Ok(Self {
+            field: field.to_string(),
+            template: get_str("template").ok_or_else(invalid)?,
+            index_template: get_str("index_template"),
+            path: get_str("path").unwrap_or_else(|| field.to_string()),
+        })
+    }
+
+    pub fn supports_type(field_type: &FieldType) -> bool {
+        match field_type {
+            FieldType::String | FieldType::Slug => true,
+            FieldType::Alias(a) => Self::supports_type(&a.0),
+            FieldType::List(t) => Self::supports_type(t),
+            _ => false,
+        }
+    }
+
+    /// Collects the distinct terms for this taxonomy, keyed by slug, along
+    /// with the objects that have each term. Terms with the same slug, e.g.
+    /// `Rust` and `rust`, are one term, named as it first appears.
+    pub fn terms<'a>(
+        &self,
+        objects: impl IntoIterator<Item = &'a Object>,
+    ) -> BTreeMap<String, Term<'a>> {
+        let mut terms: BTreeMap<String, Term<'a>> = BTreeMap::new();
+        for object in objects {
+            if let Some(value) = object.values.get(&self.field) {
+                for name in field_terms(value) {
+                    let slug = slugify(&name);
+                    if slug.is_empty() {
+                        continue;
+                    }
+                    let term = terms.entry(slug).or_insert_with(|| Term {
+                        name,
+                        objects: vec![],
+                    });
+                    if !term
+                        .objects
+                        .last()
+                        .is_some_and(|o| std::ptr::eq(*o, object))
+                    {
+                        term.objects.push(object);
+                    }
+                }
+            }
+        }
+        terms
+    }
+
+    pub fn term_path(&self, term: &str) -> String {
+        format!("{}/{}", self.path, slugify(term))
+    }
+}
+
+/// A term of a taxonomy and the objects that have it.
+#[derive(Debug, Clone)]
+pub struct Term<'a> {
+    pub name: String,
+    pub objects: Vec<&'a Object>,
+}
+
+fn field_terms(value: &FieldValue) -> Vec<String> {
+    match value {
+        FieldValue::String(s) if !s.trim().is_empty() => vec![s.trim().to_string()],
+        FieldValue::List(l) => l.iter().flat_map(field_terms).collect(),
+        _ => vec![],
+    }
+}
+
+/// Lowercases a string and replaces runs of non-alphanumeric characters with
+/// dashes, so it can be used in a url.
+pub fn slugify(string: &str) -> String {
+    let mut slug = String::with_capacity(string.len());
+    for c in string.trim().chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields::ObjectValues;
+
+    fn post(filename: &str, category: &str) -> Object {
+        Object {
+            filename: filename.to_string(),
+            object_name: "post".to_string(),
+            order: -1,
+            path: format!("post/{}", filename),
+            values: ObjectValues::from([(
+                "category".to_string(),
+                FieldValue::String(category.to_string()),
+            )]),
+            translations: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn collects_terms() {
+        let taxonomy =
+            Taxonomy::from_toml("post", "category", &toml::Value::String("cat".into())).unwrap();
+        assert_eq!(taxonomy.path, "category");
+        let posts = [
+            post("a", "Rust"),
+            post("b", "Go"),
+            post("c", "Rust"),
+            post("d", ""),
+        ];
+        let terms = taxonomy.terms(&posts);
+        assert_eq!(terms.len(), 2);
+        assert_eq!(terms["rust"].name, "Rust");
+        assert_eq!(terms["rust"].objects.len(), 2);
+        assert_eq!(terms["go"].objects.len(), 1);
+        assert_eq!(taxonomy.term_path("Rust"), "category/rust");
+    }
+
+    #[test]
+    fn groups_terms_by_slug() {
+        let taxonomy =
+            Taxonomy::from_toml("post", "category", &toml::Value::String("cat".into())).unwrap();
+        let mut tagged = post("d", "");
+        tagged.values.insert(
+            "category".to_string(),
+            FieldValue::List(vec![
+                FieldValue::String("rust".to_string()),
+                FieldValue::String("RUST".to_string()),
+            ]),
+        );
+        let posts = [
+            post("a", "Rust"),
+            post("b", "rust"),
+            post("c", "Rust!"),
+            tagged,
+            post("e", "!!!"),
+        ];
+        let terms = taxonomy.terms(&posts);
+        assert_eq!(terms.keys().collect::<Vec<_>>(), vec!["rust"]);
+        assert_eq!(terms["rust"].name, "Rust");
+        let filenames: Vec<&str> = terms["rust"]
+            .objects
+            .iter()
+            .map(|o| o.filename.as_str())
+            .collect();
+        assert_eq!(filenames, vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn slugs() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  rust  "), "rust");
+        assert_eq!(slugify("Crème brûlée"), "crème-brûlée");
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{fields::DateTime, FileSystemAPI};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use tracing::instrument;
+
+#[derive(Error, Debug, Clone)]
+pub enum TrashError {
+    #[error("trash is not enabled for this site (set trash_dir in the manifest)")]
+    NotEnabled,
+    #[error("no trashed {0} named {1}")]
+    NotFound(String, String),
+    #[error("cannot restore {0} '{1}', {2} already exists.")]
+    RestoreConflict(String, String, String),
+}
+
+/// Metadata written next to a trashed object so that it can be restored to
+/// its original location.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "typescript", derive(typescript_type_def::TypeDef))]
+pub struct TrashedObject {
+    pub object: String,
+    pub filename: String,
+    /// Which of the objects trashed with this filename this is, counting up
+    /// from 0.
+    #[serde(default)]
+    pub trash_id: usize,
+    /// Where the object was, relative to the site root.
+    pub original_path: PathBuf,
+    pub deleted_at: String,
+    pub source: Option<String>,
+}
+
+impl TrashedObject {
+    pub fn new(object: &str, filename: &str, original_path: &Path, source: Option<String>) -> Self {
+        Self {
+            object: object.to_string(),
+            filename: filename.to_string(),
+            trash_id: 0,
+            original_path: original_path.to_path_buf(),
+            deleted_at: DateTime::now().to_string(),
+            source,
+        }
+    }
+
+    /// The directory this object is stored in, inside the trash dir.
+    fn trash_path(&self, trash_dir: &Path) -> PathBuf {
+        trash_dir
+            .join(&self.object)
+            .join(&self.filename)
+            .join(self.trash_id.to_string())
+    }
+}
+
+// Trashed objects are stored in a directory per object type, filename and
+// trash id, as `<trash_dir>/<object>/<filename>/<trash_id>/object.toml`, with
+// their metadata next to them in `meta.toml`. Since filenames can't contain
+// a path separator, no object can end up in another object's directory.
+const CONTENT_FILE: &str = "object.toml";
+const META_FILE: &str = "meta.toml";
+
+/// The trashed objects with an object type and filename, oldest first.
+fn trashed_versions(
+    trash_dir: &Path,
+    object: &str,
+    filename: &str,
+    fs: &impl FileSystemAPI,
+) -> Result<Vec<TrashedObject>, Box<dyn Error>> {
+    let mut versions: Vec<TrashedObject> = list(trash_dir, fs)?
+        .into_iter()
+        .filter(|t| t.object == object && t.filename == filename)
+        .collect();
+    versions.sort_by_key(|t| t.trash_id);
+    Ok(versions)
+}
+
+/// Moves an object to the trash. Objects trashed with the same filename
+/// before are kept.
+#[instrument(skip(fs))]
+pub fn trash_object(
+    root: &Path,
+    trash_dir: &Path,
+    trashed: &TrashedObject,
+    fs: &mut impl FileSystemAPI,
+) -> Result<(), Box<dyn Error>> {
+    let original_path = root.join(&trashed.original_path);
+    let content = fs
+        .read(&original_path)?
+        .ok_or_else(|| TrashError::NotFound(trashed.object.clone(), trashed.filename.clone()))?;
+    let mut trashed = trashed.clone();
+    if let Some(last) = trashed_versions(trash_dir, &trashed.object, &trashed.filename, fs)?.last()
+    {
+        trashed.trash_id = last.trash_id + 1;
+    }
+    let trash_path = trashed.trash_path(trash_dir);
+    fs.create_dir_all(&trash_path)?;
+    fs.write(&trash_path.join(CONTENT_FILE), content)?;
+    fs.write_str(
+        &trash_path.join(META_FILE),
+        toml::to_string_pretty(&trashed)?,
+    )?;
+    fs.delete(&original_path)?;
+    Ok(())
+}
+
+/// Restores the most recently trashed object with an object type and
+/// filename.
+#[instrument(skip(fs))]
+pub fn restore_object(
+    root: &Path,
+    trash_dir: &Path,
+    object: &str,
+    filename: &str,
+    fs: &mut impl FileSystemAPI,
+) -> Result<TrashedObject, Box<dyn Error>> {
+    let trashed = trashed_versions(trash_dir, object, filename, fs)?
+        .pop()
+        .ok_or_else(|| TrashError::NotFound(object.to_string(), filename.to_string()))?;
+    let trash_path = trashed.trash_path(trash_dir);
+    let content = fs
+        .read(&trash_path.join(CONTENT_FILE))?
+        .ok_or_else(|| TrashError::NotFound(object.to_string(), filename.to_string()))?;
+    let original_path = root.join(&trashed.original_path);
+    if fs.exists(&original_path)? {
+        return Err(TrashError::RestoreConflict(
+            object.to_string(),
+            filename.to_string(),
+            trashed.original_path.display().to_string(),
+        )
+        .into());
+    }
+    if let Some(parent) = original_path.parent() {
+        fs.create_dir_all(parent)?;
+    }
+    fs.write(&original_path, content)?;
+    fs.remove_dir_all(&trash_path)?;
+    Ok(trashed)
+}
+
+#[instrument(skip(fs))]
+pub fn list(
+    trash_dir: &Path,
+    fs: &impl FileSystemAPI,
+) -> Result<Vec<TrashedObject>, Box<dyn Error>> {
+    let mut trashed = vec![];
+    if !fs.exists(trash_dir)? {
+        return Ok(trashed);
+    }
+    for file in fs.walk_dir(trash_dir, false)? {
+        if file.file_name().is_none_or(|n| n != META_FILE) {
+            continue;
+        }
+        let Some(trash_id) = file
+            .parent()
+            .and_then(|p| p.file_name())
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.parse().ok())
+        else {
+            continue;
+        };
+        if let Some(meta) = fs.read_to_string(&trash_dir.join(&file))? {
+            let mut t: TrashedObject = toml::from_str(&meta)?;
+            t.trash_id = trash_id;
+            trashed.push(t);
+        }
+    }
+    Ok(trashed)
+}
+
+/// Permanently deletes trashed objects, optionally only those of the given
+/// object type. Returns the number of objects purged.
+#[instrument(skip(fs))]
+pub fn purge(
+    trash_dir: &Path,
+    object: Option<&str>,
+    fs: &mut impl FileSystemAPI,
+) -> Result<usize, Box<dyn Error>> {
+    let trashed = list(trash_dir, fs)?;
+    let mut purged = 0;
+    for t in trashed {
+        if object.is_some_and(|o| o != t.object) {
+            continue;
+        }
+        fs.remove_dir_all(&t.trash_path(trash_dir))?;
+        purged += 1;
+    }
+    Ok(purged)
+}
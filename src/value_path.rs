@@ -1,6 +1,7 @@
 use crate::{
     fields::{field_value, meta::Meta, FieldType, FieldValue, MetaValue, ObjectValues},
     object::Object,
+    object_definition::BLOCK_TYPE,
     ObjectDefinition,
 };
 use liquid::ValueView;
@@ -18,6 +19,10 @@ pub enum ValuePathError {
     NotFound(String, String),
     #[error("Cannot remove {0}")]
     InvalidRemovePath(String),
+    #[error("Cannot move {0} to {1}")]
+    InvalidMovePath(String, usize),
+    #[error("Block type {0:?} not found for path {1}")]
+    BlockTypeNotFound(String, String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
@@ -234,7 +239,13 @@ impl ValuePath {
         while let Some(cmp) = &i_path.next() {
             match cmp {
                 ValuePathComponent::Index(i) => {
-                    if let FieldValue::Objects(o) = field {
+                    if let FieldValue::List(l) = last_val {
+                        if let Some(FieldValue::String(s)) = l.get(*i) {
+                            if i_path.len() == 0 {
+                                return Ok(FoundValue::String(s));
+                            }
+                        }
+                    } else if let FieldValue::Objects(o) = field {
                         if let Some(v) = o.get(*i) {
                             if let Some(ValuePathComponent::Key(k)) = i_path.next() {
                                 if let Some(fv) = v.get(&k) {
@@ -305,6 +316,12 @@ impl ValuePath {
                             }
                         }
                     }
+                } else if let Some(FieldValue::List(items)) = last_val {
+                    // Lists may be indexed directly
+                    if let ValuePathComponent::Index(index) = cmp {
+                        last_val = items.get(index);
+                        continue;
+                    }
                 }
             }
             break;
@@ -429,31 +446,134 @@ impl ValuePath {
         Ok(last_val)
     }
 
+    /// Like `get_definition`, but also resolves the definitions of block
+    /// items along the path, using the block types found in the object.
+    pub fn get_object_definition<'a>(
+        &self,
+        def: &'a ObjectDefinition,
+        object: &Object,
+    ) -> Result<&'a ObjectDefinition, ValuePathError> {
+        let mut last_def = def;
+        let mut values = Some(&object.values);
+        let mut children = None;
+        for cmp in self.path.iter() {
+            match cmp {
+                ValuePathComponent::Key(k) => {
+                    last_def = last_def.children.get(k).ok_or_else(|| {
+                        ValuePathError::ChildDefNotFound(self.to_string(), format!("{:?}", def))
+                    })?;
+                    children = match values.and_then(|v| v.get(k)) {
+                        Some(FieldValue::Objects(c)) => Some(c),
+                        _ => None,
+                    };
+                }
+                ValuePathComponent::Index(i) => {
+                    values = children.and_then(|c| c.get(*i));
+                    if last_def.is_blocks() {
+                        last_def = values
+                            .and_then(|v| last_def.block_definition(v))
+                            .ok_or_else(|| {
+                                ValuePathError::BlockTypeNotFound(
+                                    values
+                                        .and_then(|v| v.get(BLOCK_TYPE))
+                                        .map(|t| t.to_string())
+                                        .unwrap_or_default(),
+                                    self.to_string(),
+                                )
+                            })?;
+                    }
+                }
+            }
+        }
+        Ok(last_def)
+    }
+
+    /// Adds a child at this path. For blocks fields, `block` is the type of
+    /// block to add.
     pub fn add_child(
         &self,
         object: &mut Object,
         obj_def: &ObjectDefinition,
+        block: Option<&str>,
     ) -> Result<usize, ValuePathError> {
-        let child_def = self.get_definition(obj_def)?;
-        let new_child = field_value::def_to_values(&child_def.fields);
-        self.modify_children(object, |children| {
+        let child_def = self.get_object_definition(obj_def, object)?;
+        let new_child = if child_def.is_blocks() {
+            let block_type = block.unwrap_or_default();
+            let block_def = child_def.blocks.get(block_type).ok_or_else(|| {
+                ValuePathError::BlockTypeNotFound(block_type.to_string(), self.to_string())
+            })?;
+            let mut values = field_value::def_to_values(&block_def.fields);
+            values.insert(
+                BLOCK_TYPE.to_string(),
+                FieldValue::String(block_type.to_string()),
+            );
+            values
+        } else {
+            field_value::def_to_values(&child_def.fields)
+        };
+        self.modify_children(&mut object.values, |children| {
             children.push(new_child);
             children.len() - 1
         })
     }
 
+    /// Removes the child at this path, along with its translations.
     pub fn remove_child(&mut self, object: &mut Object) -> Result<(), ValuePathError> {
         if let Some(ValuePathComponent::Index(index)) = self.pop() {
-            self.modify_children(object, |children| {
+            self.modify_children(&mut object.values, |children| {
                 children.remove(index);
-            })
+            })?;
+            for translated in object.translations.values_mut() {
+                // Translations only contain children up to the last translated
+                // one, so there may be nothing to remove.
+                let _ = self.modify_children(translated, |children| {
+                    if index < children.len() {
+                        children.remove(index);
+                    }
+                });
+            }
+            Ok(())
         } else {
             Err(ValuePathError::InvalidRemovePath(self.to_string()))
         }
     }
+
+    /// Moves the child at this path to a new index in the same list. Any
+    /// translations of the child move with it.
+    pub fn move_child(&mut self, object: &mut Object, to: usize) -> Result<(), ValuePathError> {
+        let path = self.to_string();
+        if let Some(ValuePathComponent::Index(index)) = self.pop() {
+            self.modify_children(&mut object.values, |children| {
+                if index >= children.len() || to >= children.len() {
+                    return Err(ValuePathError::InvalidMovePath(path, to));
+                }
+                let child = children.remove(index);
+                children.insert(to, child);
+                Ok(())
+            })??;
+            for translated in object.translations.values_mut() {
+                let _ = self.modify_children(translated, |children| {
+                    if index >= children.len() && to >= children.len() {
+                        return;
+                    }
+                    while children.len() <= index.max(to) {
+                        children.push(ObjectValues::new());
+                    }
+                    let child = children.remove(index);
+                    children.insert(to, child);
+                    while children.last().is_some_and(|c| c.is_empty()) {
+                        children.pop();
+                    }
+                });
+            }
+            Ok(())
+        } else {
+            Err(ValuePathError::InvalidMovePath(path, to))
+        }
+    }
     fn modify_children<R>(
         &self,
-        object: &mut Object,
+        values: &mut ObjectValues,
         modify: impl FnOnce(&mut Vec<ObjectValues>) -> R,
     ) -> Result<R, ValuePathError> {
         let mut i_path = self.path.iter().map(|v| match v {
@@ -465,7 +585,7 @@ impl ValuePath {
             if last_val.is_none() {
                 // At the root, we must have a key string
                 if let ValuePathComponent::Key(k) = cmp {
-                    last_val = object.values.get_mut(&k);
+                    last_val = values.get_mut(&k);
                     continue;
                 }
             } else {
@@ -486,7 +606,7 @@ impl ValuePath {
             }
             return Err(ValuePathError::NotChildren(
                 self.to_string(),
-                format!("{:?}", object),
+                format!("{:?}", values),
             ));
         }
         if let Some(FieldValue::Objects(children)) = last_val {
@@ -494,12 +614,30 @@ impl ValuePath {
         } else {
             Err(ValuePathError::NotChildren(
                 self.to_string(),
-                format!("{:?}", object),
+                format!("{:?}", values),
             ))
         }
     }
 
     pub fn set_in_object(&self, object: &mut Object, value: Option<FieldValue>) {
+        self.set_in_values(&mut object.values, value, false);
+    }
+
+    /// Sets a value for the given locale. Since translations only contain the
+    /// values that have been translated, any missing children along the path
+    /// are created.
+    pub fn set_in_translation(&self, object: &mut Object, locale: &str, value: Option<FieldValue>) {
+        let values = object.translations.entry(locale.to_string()).or_default();
+        self.set_in_values(values, value, true);
+        copy_block_types(values, &object.values);
+    }
+
+    fn set_in_values(
+        &self,
+        values: &mut ObjectValues,
+        value: Option<FieldValue>,
+        create_children: bool,
+    ) {
         let mut i_path = self.path.iter().map(|v| match v {
             ValuePathComponent::Index(i) => ValuePathComponent::Index(*i),
             ValuePathComponent::Key(k) => ValuePathComponent::Key(k.to_owned()),
@@ -510,12 +648,16 @@ impl ValuePath {
                 // At the root, we must have a key string
                 if let ValuePathComponent::Key(k) = cmp {
                     if i_path.len() > 0 {
-                        last_val = object.values.get_mut(&k);
+                        last_val = if create_children {
+                            Some(values.entry(k).or_insert(FieldValue::Objects(vec![])))
+                        } else {
+                            values.get_mut(&k)
+                        };
                         continue;
                     } else {
                         match value {
-                            Some(value) => object.values.insert(k, value),
-                            None => object.values.remove(&k),
+                            Some(value) => values.insert(k, value),
+                            None => values.remove(&k),
                         };
                         break;
                     }
@@ -524,7 +666,22 @@ impl ValuePath {
                 // more than one level deep. We only allow accessing child
                 // values, not children themselves - so this finds a child at
                 // the index and then finds a key on it.
-                if let Some(FieldValue::Objects(children)) = last_val {
+                if let Some(FieldValue::List(items)) = last_val {
+                    // Lists may be indexed directly. Setting past the end of
+                    // a list appends to it.
+                    if let ValuePathComponent::Index(index) = cmp {
+                        if i_path.len() == 0 {
+                            match value {
+                                Some(value) if index < items.len() => items[index] = value,
+                                Some(value) => items.push(value),
+                                None if index < items.len() => {
+                                    items.remove(index);
+                                }
+                                None => {}
+                            }
+                        }
+                    }
+                } else if let Some(FieldValue::Objects(children)) = last_val {
                     if let ValuePathComponent::Index(index) = cmp {
                         while children.len() <= index {
                             // No child yet - since we're setting, insert one
@@ -534,7 +691,11 @@ impl ValuePath {
                         let child = children.get_mut(index).unwrap();
                         if let Some(ValuePathComponent::Key(k)) = i_path.next() {
                             if i_path.len() > 0 {
-                                last_val = child.get_mut(&k);
+                                last_val = if create_children {
+                                    Some(child.entry(k).or_insert(FieldValue::Objects(vec![])))
+                                } else {
+                                    child.get_mut(&k)
+                                };
                                 continue;
                             } else {
                                 match value {
@@ -551,6 +712,23 @@ impl ValuePath {
     }
 }
 
+/// Stores the type of each default block on its translated values, so that
+/// translations are only applied to the block they were written for.
+fn copy_block_types(translated: &mut ObjectValues, values: &ObjectValues) {
+    for (key, value) in translated.iter_mut() {
+        if let (FieldValue::Objects(t_children), Some(FieldValue::Objects(children))) =
+            (value, values.get(key))
+        {
+            for (t_child, child) in t_children.iter_mut().zip(children) {
+                if let Some(block_type) = child.get(BLOCK_TYPE) {
+                    t_child.insert(BLOCK_TYPE.to_string(), block_type.clone());
+                }
+                copy_block_types(t_child, child);
+            }
+        }
+    }
+}
+
 impl From<&str> for ValuePath {
     fn from(value: &str) -> Self {
         Self::from_string(value)
@@ -585,7 +763,15 @@ pub mod tests {
                         )]),
                     ]),
                 ),
+                (
+                    "tags".to_string(),
+                    FieldValue::List(vec![
+                        FieldValue::String("one".to_string()),
+                        FieldValue::String("two".to_string()),
+                    ]),
+                ),
             ]),
+            translations: Default::default(),
         }
     }
 
@@ -600,4 +786,108 @@ pub mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn list_indexing() -> Result<(), Box<dyn Error>> {
+        let mut object = object();
+        let vp = ValuePath::from_string("tags.1");
+        assert_eq!(
+            vp.get_in_object(&object),
+            Some(&FieldValue::String("two".to_string()))
+        );
+        assert_eq!(
+            ValuePath::from_string("1")
+                .get_value(object.values.get("tags").unwrap())
+                .map(|v| v.to_string())?,
+            "two"
+        );
+        vp.set_in_object(&mut object, Some(FieldValue::String("deux".to_string())));
+        ValuePath::from_string("tags.2")
+            .set_in_object(&mut object, Some(FieldValue::String("trois".to_string())));
+        ValuePath::from_string("tags.0").set_in_object(&mut object, None);
+        assert_eq!(
+            object.values.get("tags"),
+            Some(&FieldValue::List(vec![
+                FieldValue::String("deux".to_string()),
+                FieldValue::String("trois".to_string()),
+            ]))
+        );
+        Ok(())
+    }
+
+    fn translated_name(object: &Object, index: usize) -> Option<String> {
+        ValuePath::from_string(&format!("children.{}.name", index))
+            .get_in_object(&object.localized("es"))
+            .map(|v| v.to_string())
+    }
+
+    #[test]
+    fn move_translated_child() -> Result<(), Box<dyn Error>> {
+        let mut object = object();
+        ValuePath::from_string("children.0.name").set_in_translation(
+            &mut object,
+            "es",
+            Some(FieldValue::String("NOMBRE UNO".to_string())),
+        );
+        ValuePath::from_string("children.0").move_child(&mut object, 1)?;
+        assert_eq!(translated_name(&object, 0), Some("NAME TWO!".to_string()));
+        assert_eq!(translated_name(&object, 1), Some("NOMBRE UNO".to_string()));
+        ValuePath::from_string("children.1").move_child(&mut object, 0)?;
+        assert_eq!(translated_name(&object, 0), Some("NOMBRE UNO".to_string()));
+        assert_eq!(translated_name(&object, 1), Some("NAME TWO!".to_string()));
+        // Trailing untranslated children are not stored
+        assert_eq!(
+            object.translations["es"].get("children"),
+            Some(&FieldValue::Objects(vec![ObjectValues::from([(
+                "name".to_string(),
+                FieldValue::String("NOMBRE UNO".to_string())
+            )])]))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn remove_translated_child() -> Result<(), Box<dyn Error>> {
+        let mut object = object();
+        ValuePath::from_string("children.1.name").set_in_translation(
+            &mut object,
+            "es",
+            Some(FieldValue::String("NOMBRE DOS".to_string())),
+        );
+        ValuePath::from_string("children.0").remove_child(&mut object)?;
+        assert_eq!(translated_name(&object, 0), Some("NOMBRE DOS".to_string()));
+        ValuePath::from_string("children.0").remove_child(&mut object)?;
+        assert_eq!(
+            object.translations["es"].get("children"),
+            Some(&FieldValue::Objects(vec![]))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn translated_blocks_match_type() -> Result<(), Box<dyn Error>> {
+        let mut object = object();
+        if let Some(FieldValue::Objects(children)) = object.values.get_mut("children") {
+            children[0].insert(BLOCK_TYPE.to_string(), FieldValue::String("hero".into()));
+            children[1].insert(BLOCK_TYPE.to_string(), FieldValue::String("quote".into()));
+        }
+        ValuePath::from_string("children.0.name").set_in_translation(
+            &mut object,
+            "es",
+            Some(FieldValue::String("NOMBRE UNO".to_string())),
+        );
+        // The translation stores the type of the block it was written for
+        if let Some(FieldValue::Objects(children)) = object.translations["es"].get("children") {
+            assert_eq!(
+                children[0].get(BLOCK_TYPE),
+                Some(&FieldValue::String("hero".into()))
+            );
+        }
+        // A block of another type at the same index is not translated
+        if let Some(FieldValue::Objects(children)) = object.values.get_mut("children") {
+            children.swap(0, 1);
+        }
+        assert_eq!(translated_name(&object, 0), Some("NAME TWO!".to_string()));
+        Ok(())
+    }
 }